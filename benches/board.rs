@@ -0,0 +1,30 @@
+//! Criterion benchmarks of the board operations that run on every click.
+//!
+//! Run with `cargo bench`; `termsweeper bench` gives a quicker summary.
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use termsweeper::bench::{SIZES, Target};
+
+fn board(c: &mut Criterion) {
+    for target in Target::ALL {
+        let mut group = c.benchmark_group(target.key());
+        for size in SIZES {
+            let id = BenchmarkId::from_parameter(format!("{}x{}", size, size));
+            group.bench_function(id, |b| {
+                if target.consumes_board() {
+                    b.iter_batched_ref(
+                        || target.setup(size),
+                        |board| target.run(board),
+                        BatchSize::LargeInput,
+                    );
+                } else {
+                    let mut board = target.setup(size);
+                    b.iter(|| target.run(&mut board));
+                }
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, board);
+criterion_main!(benches);
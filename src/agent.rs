@@ -0,0 +1,466 @@
+//! Computer players and a harness to compare them.
+//!
+//! An [`Agent`] is shown the board as a player sees it, a [`View`], and returns its
+//! next action. Three reference agents come built in, see [`Builtin`]:
+//!
+//! - `random-safe` reveals hidden cells at random, trusting only the safe first click.
+//! - `rule-based` plays the two classic rules: a number with all its mines flagged
+//!   clears its other neighbours, and a number with as many hidden neighbours as
+//!   mines left flags them all. It guesses at random when neither applies.
+//! - `probability` reveals the cells the [`solver`](crate::solver) proves safe, and
+//!   otherwise the cell least likely to hold a mine.
+//!
+//! [`run_games`] plays many seeded games with an agent in parallel, so agents can be
+//! compared on the very same boards, and changes to board generation show up as
+//! changes in their win rates.
+use crate::game_logic::Board;
+use crate::solver::Analysis;
+use crate::types::{Action, CellKind, CellState, DEFAULT_SETTINGS, GameConfig, GameState};
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use std::ops::Range;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A cell as a player sees it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Seen {
+    /// Not revealed yet, including cells marked with a "?".
+    Hidden,
+    Flagged,
+    /// A revealed number, as shown.
+    Revealed(u8),
+    /// A revealed mine, which only lives mode leaves on a board still in play.
+    Mine,
+}
+
+/// The board as a player sees it.
+#[derive(Clone, Debug)]
+pub struct View {
+    pub width: usize,
+    pub height: usize,
+    /// Mines on the board, found or not.
+    pub mines: usize,
+    cells: Vec<Seen>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl View {
+    /// Returns what a player can see of `board`.
+    pub fn of(board: &Board) -> View {
+        let (width, height) = (board.width, board.height);
+        let coords = (0..height as isize).flat_map(|y| (0..width as isize).map(move |x| (x, y)));
+        let cells = coords
+            .clone()
+            .map(|(x, y)| match board.get_cell(x, y) {
+                Some(cell) => match (cell.state, cell.kind) {
+                    (CellState::Flagged(_), _) => Seen::Flagged,
+                    (CellState::Revealed, CellKind::Mine(_)) => Seen::Mine,
+                    (CellState::Revealed, CellKind::Number(n)) => {
+                        Seen::Revealed(board.get_shown_number(x, y).unwrap_or(n))
+                    }
+                    _ => Seen::Hidden,
+                },
+                None => Seen::Hidden,
+            })
+            .collect();
+        let neighbours = coords
+            .map(|(x, y)| {
+                board
+                    .neighbours(x, y)
+                    .into_iter()
+                    .map(|(nx, ny)| ny as usize * width + nx as usize)
+                    .collect()
+            })
+            .collect();
+        View {
+            width,
+            height,
+            mines: board.get_config().mines(),
+            cells,
+            neighbours,
+        }
+    }
+
+    /// Returns the index of (x, y) among the cells, row by row, if it is on the board.
+    pub fn index(&self, x: isize, y: isize) -> Option<usize> {
+        let on_board =
+            (0..self.width as isize).contains(&x) && (0..self.height as isize).contains(&y);
+        on_board.then(|| y as usize * self.width + x as usize)
+    }
+
+    /// Returns the coordinates of the cell at `index`.
+    pub fn coords(&self, index: usize) -> (isize, isize) {
+        ((index % self.width) as isize, (index / self.width) as isize)
+    }
+
+    /// Returns the cell at (x, y), if it is on the board.
+    pub fn get(&self, x: isize, y: isize) -> Option<Seen> {
+        Some(self.cells[self.index(x, y)?])
+    }
+
+    /// Returns the cell at `index`.
+    pub fn seen(&self, index: usize) -> Seen {
+        self.cells[index]
+    }
+
+    /// Returns the indices of the neighbours of the cell at `index`.
+    pub fn neighbours_of(&self, index: usize) -> &[usize] {
+        &self.neighbours[index]
+    }
+
+    /// Returns every hidden cell.
+    pub fn hidden(&self) -> Vec<(isize, isize)> {
+        (0..self.cells.len())
+            .filter(|&index| self.cells[index] == Seen::Hidden)
+            .map(|index| self.coords(index))
+            .collect()
+    }
+}
+
+/// A computer player.
+pub trait Agent {
+    /// Name the agent is reported under.
+    fn name(&self) -> &'static str;
+
+    /// Returns the next action to play on the board in `view`, which has a hidden
+    /// cell left.
+    fn next_action(&mut self, view: &View) -> Action;
+
+    /// Returns what the agent made of the board when it chose its last action, for
+    /// agents that can say.
+    fn thinking(&self) -> Option<&Thinking> {
+        None
+    }
+}
+
+/// What an agent made of the board when it chose an action.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Thinking {
+    /// Cells it worked out are safe.
+    pub safe: Vec<(isize, isize)>,
+    /// Cells it worked out hold a mine.
+    pub mines: Vec<(isize, isize)>,
+    /// The action it chose.
+    pub action: Option<Action>,
+    /// The chance of a mine under the chosen cell, if it worked one out.
+    pub chance: Option<f64>,
+}
+
+impl Thinking {
+    /// Notes the action chosen and returns it.
+    fn chose(&mut self, action: Action, chance: Option<f64>) -> Action {
+        self.action = Some(action);
+        self.chance = chance;
+        action
+    }
+}
+
+/// Reveals hidden cells at random.
+pub struct RandomSafe {
+    rng: StdRng,
+}
+
+impl RandomSafe {
+    pub fn new(seed: u64) -> RandomSafe {
+        RandomSafe {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomSafe {
+    fn name(&self) -> &'static str {
+        Builtin::RandomSafe.key()
+    }
+
+    fn next_action(&mut self, view: &View) -> Action {
+        random_reveal(view, &mut self.rng)
+    }
+}
+
+/// Plays the two classic single-number rules, guessing at random when stuck.
+pub struct RuleBased {
+    rng: StdRng,
+    /// Moves found by the last look at the board, still to be played.
+    planned: Vec<Action>,
+    thinking: Thinking,
+}
+
+impl RuleBased {
+    pub fn new(seed: u64) -> RuleBased {
+        RuleBased {
+            rng: StdRng::seed_from_u64(seed),
+            planned: Vec::new(),
+            thinking: Thinking::default(),
+        }
+    }
+}
+
+impl Agent for RuleBased {
+    fn name(&self) -> &'static str {
+        Builtin::RuleBased.key()
+    }
+
+    fn next_action(&mut self, view: &View) -> Action {
+        if let Some(action) = next_planned(&mut self.planned, view) {
+            return self.thinking.chose(action, Some(0.0));
+        }
+        for index in 0..view.width * view.height {
+            let Seen::Revealed(number) = view.seen(index) else {
+                continue;
+            };
+            let neighbours = view.neighbours_of(index);
+            let count = |wanted: &[Seen]| {
+                neighbours
+                    .iter()
+                    .filter(|&&other| wanted.contains(&view.seen(other)))
+                    .count()
+            };
+            let hidden = count(&[Seen::Hidden]);
+            if hidden == 0 {
+                continue;
+            }
+            let found = count(&[Seen::Flagged, Seen::Mine]);
+            let hidden_cells = neighbours
+                .iter()
+                .filter(|&&other| view.seen(other) == Seen::Hidden)
+                .map(|&other| view.coords(other));
+            if found == number as usize {
+                self.planned
+                    .extend(hidden_cells.map(|(x, y)| Action::Reveal { x, y }));
+            } else if found + hidden == number as usize {
+                self.planned
+                    .extend(hidden_cells.map(|(x, y)| Action::Flag { x, y }));
+            }
+        }
+        self.thinking.safe.clear();
+        self.thinking.mines.clear();
+        for &action in &self.planned {
+            match action {
+                Action::Reveal { x, y } => self.thinking.safe.push((x, y)),
+                Action::Flag { x, y } => self.thinking.mines.push((x, y)),
+                _ => {}
+            }
+        }
+        match next_planned(&mut self.planned, view) {
+            Some(action) => self.thinking.chose(action, Some(0.0)),
+            None => {
+                let action = random_reveal(view, &mut self.rng);
+                self.thinking.chose(action, None)
+            }
+        }
+    }
+
+    fn thinking(&self) -> Option<&Thinking> {
+        Some(&self.thinking)
+    }
+}
+
+/// Reveals cells proven safe, or else the one least likely to hold a mine.
+pub struct Probability {
+    /// Cells the last analysis proved safe, still to be revealed.
+    planned: Vec<Action>,
+    thinking: Thinking,
+}
+
+impl Probability {
+    pub fn new() -> Probability {
+        Probability {
+            planned: Vec::new(),
+            thinking: Thinking::default(),
+        }
+    }
+}
+
+impl Default for Probability {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Agent for Probability {
+    fn name(&self) -> &'static str {
+        Builtin::Probability.key()
+    }
+
+    fn next_action(&mut self, view: &View) -> Action {
+        if let Some(action) = next_planned(&mut self.planned, view) {
+            return self.thinking.chose(action, Some(0.0));
+        }
+        let analysis = Analysis::of(view);
+        self.thinking.safe = analysis.safe_cells(view);
+        self.thinking.mines = analysis.mines(view);
+        self.planned.extend(
+            self.thinking
+                .safe
+                .iter()
+                .map(|&(x, y)| Action::Reveal { x, y }),
+        );
+        if let Some(action) = next_planned(&mut self.planned, view) {
+            return self.thinking.chose(action, Some(0.0));
+        }
+        let ((x, y), chance) = analysis.best_guess(view).unwrap_or_default();
+        self.thinking.chose(Action::Reveal { x, y }, Some(chance))
+    }
+
+    fn thinking(&self) -> Option<&Thinking> {
+        Some(&self.thinking)
+    }
+}
+
+/// Returns the next planned move that still makes sense: one on a hidden cell.
+fn next_planned(planned: &mut Vec<Action>, view: &View) -> Option<Action> {
+    while let Some(action) = planned.pop() {
+        if let Action::Reveal { x, y } | Action::Flag { x, y } = action
+            && view.get(x, y) == Some(Seen::Hidden)
+        {
+            return Some(action);
+        }
+    }
+    None
+}
+
+/// Returns a reveal of a random hidden cell.
+fn random_reveal(view: &View, rng: &mut impl Rng) -> Action {
+    let (x, y) = view.hidden().choose(rng).copied().unwrap_or_default();
+    Action::Reveal { x, y }
+}
+
+/// The agents that come with the library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Builtin {
+    RandomSafe,
+    RuleBased,
+    Probability,
+}
+
+impl Builtin {
+    /// Every built-in agent, weakest first.
+    pub const ALL: [Builtin; 3] = [
+        Builtin::RandomSafe,
+        Builtin::RuleBased,
+        Builtin::Probability,
+    ];
+
+    /// Returns the name used for the agent on the command line and in reports.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Builtin::RandomSafe => "random-safe",
+            Builtin::RuleBased => "rule-based",
+            Builtin::Probability => "probability",
+        }
+    }
+
+    /// Returns the agent named `key`.
+    pub fn from_key(key: &str) -> Option<Builtin> {
+        Builtin::ALL.into_iter().find(|agent| agent.key() == key)
+    }
+
+    /// Creates the agent, with any random choices it makes seeded by `seed`.
+    pub fn create(&self, seed: u64) -> Box<dyn Agent + Send> {
+        match self {
+            Builtin::RandomSafe => Box::new(RandomSafe::new(seed)),
+            Builtin::RuleBased => Box::new(RuleBased::new(seed)),
+            Builtin::Probability => Box::new(Probability::new()),
+        }
+    }
+}
+
+/// How one game played by an agent went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub won: bool,
+    /// Actions the agent played.
+    pub moves: usize,
+    /// Time the game took, thinking included.
+    pub time: Duration,
+}
+
+/// Plays `board` with `agent` until the game is won or lost.
+///
+/// An agent that stops changing the board loses once it has played more moves than
+/// there are cells, twice over.
+pub fn play_game(agent: &mut dyn Agent, board: &mut Board) -> GameResult {
+    let start = Instant::now();
+    let limit = 2 * board.width * board.height;
+    let mut moves = 0;
+    let mut state = GameState::Ongoing;
+    while state == GameState::Ongoing && moves < limit {
+        let view = View::of(board);
+        let action = agent.next_action(&view);
+        moves += 1;
+        if let Some(changed) = board.apply_action(action) {
+            state = changed;
+        }
+    }
+    GameResult {
+        won: state == GameState::Won,
+        moves,
+        time: start.elapsed(),
+    }
+}
+
+/// Results of many games played by one agent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub agent: Builtin,
+    pub games: usize,
+    pub wins: usize,
+    /// Time all the games took together.
+    pub total_time: Duration,
+}
+
+impl Summary {
+    /// Returns the share of games won, from 0 to 1.
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    /// Returns the average time a game took.
+    pub fn average_time(&self) -> Duration {
+        self.total_time / self.games.max(1) as u32
+    }
+}
+
+/// Plays a game of `config` with `agent` on the board dealt from each seed in `seeds`,
+/// spread over `threads` threads.
+///
+/// Boards have the default settings. The same seeds always deal the same boards, and
+/// the agents make the same choices on them.
+pub fn run_games(agent: Builtin, config: GameConfig, seeds: Range<u64>, threads: usize) -> Summary {
+    let seeds: Vec<u64> = seeds.collect();
+    let chunk = seeds.len().div_ceil(threads.max(1)).max(1);
+    let results: Vec<GameResult> = thread::scope(|scope| {
+        let workers: Vec<_> = seeds
+            .chunks(chunk)
+            .map(|seeds| {
+                scope.spawn(move || {
+                    seeds
+                        .iter()
+                        .map(|&seed| {
+                            let mut board = Board::new_with_seed(config, seed);
+                            board.settings = DEFAULT_SETTINGS;
+                            play_game(agent.create(seed).as_mut(), &mut board)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    Summary {
+        agent,
+        games: results.len(),
+        wins: results.iter().filter(|result| result.won).count(),
+        total_time: results.iter().map(|result| result.time).sum(),
+    }
+}
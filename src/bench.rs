@@ -0,0 +1,142 @@
+//! Timings of the board operations that run on every click.
+//!
+//! Each [`Target`] sets up a large board and times one operation on it. The
+//! `bench` subcommand prints the timings, and `cargo bench` runs the same targets
+//! under criterion for a closer look.
+use crate::game_logic::Board;
+use crate::types::{Action, FirstClick, GameConfig, MAX_WIDTH, MIN_MINES, Preset};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Sides of the square boards timed: an intermediate board and the largest custom board.
+pub const SIZES: [usize; 2] = [16, MAX_WIDTH];
+/// Seed of every board timed, so runs are comparable.
+const SEED: u64 = 0;
+
+/// An operation worth timing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Placing the mines and counting the numbers, at expert density.
+    InitializeBoard,
+    /// Revealing a board with a single mine from one click.
+    FloodFill,
+    /// Checking a cleared board for a win.
+    WinCheck,
+    /// Counting the flags on a board with every mine flagged.
+    FlagCount,
+}
+
+impl Target {
+    pub const ALL: [Target; 4] = [
+        Target::InitializeBoard,
+        Target::FloodFill,
+        Target::WinCheck,
+        Target::FlagCount,
+    ];
+
+    /// Returns the name of the function timed.
+    pub fn key(self) -> &'static str {
+        match self {
+            Target::InitializeBoard => "initialize_board",
+            Target::FloodFill => "reveal_adjacent_empty",
+            Target::WinCheck => "check_win_condition",
+            Target::FlagCount => "get_flags_count",
+        }
+    }
+
+    /// Returns whether running the target changes its board, so that each run needs
+    /// a board of its own.
+    pub fn consumes_board(self) -> bool {
+        matches!(self, Target::InitializeBoard | Target::FloodFill)
+    }
+
+    /// Returns a board of `size` by `size` cells, ready to run the target on.
+    ///
+    /// Panics if `size` is outside the custom board limits.
+    pub fn setup(self, size: usize) -> Board {
+        let checked = |config: crate::Result<GameConfig>| {
+            config.expect("bench boards are within the board limits")
+        };
+        match self {
+            Target::InitializeBoard => {
+                let density = Preset::Expert.config().density();
+                Board::new_with_seed(checked(GameConfig::with_density(size, size, density)), SEED)
+            }
+            Target::FloodFill => {
+                let mut board =
+                    Board::new_with_seed(checked(GameConfig::new(size, size, MIN_MINES)), SEED);
+                // Keeps the mine away from the corner, so the click opens the board
+                board.settings.first_click = FirstClick::Opening;
+                board.initialize_board(0, 0);
+                board
+            }
+            Target::WinCheck => {
+                // A cleared board is the case where scanning for a win looks at every cell
+                let mut board = Target::FloodFill.setup(size);
+                for y in 0..size as isize {
+                    for x in 0..size as isize {
+                        if board
+                            .get_cell(x, y)
+                            .is_some_and(|cell| !cell.kind.is_mine())
+                        {
+                            board.reveal_adjacent_empty(x, y);
+                        }
+                    }
+                }
+                board
+            }
+            Target::FlagCount => {
+                let mut board = Target::InitializeBoard.setup(size);
+                board.apply_action(Action::Reveal { x: 0, y: 0 });
+                for (x, y) in board.get_mine_positions() {
+                    board.apply_action(Action::Flag {
+                        x: x as isize,
+                        y: y as isize,
+                    });
+                }
+                board
+            }
+        }
+    }
+
+    /// Runs the target once on a board from [`Target::setup`].
+    pub fn run(self, board: &mut Board) {
+        match self {
+            Target::InitializeBoard => {
+                let (x, y) = (board.width as isize / 2, board.height as isize / 2);
+                board.initialize_board(x, y);
+            }
+            Target::FloodFill => {
+                black_box(board.reveal_adjacent_empty(0, 0));
+            }
+            Target::WinCheck => {
+                black_box(board.check_win_condition());
+            }
+            Target::FlagCount => {
+                black_box(board.get_flags_count());
+            }
+        }
+    }
+
+    /// Runs the target `runs` times on boards of `size` by `size` cells and returns
+    /// the average time of a run, leaving out the setup.
+    pub fn measure(self, size: usize, runs: u32) -> Duration {
+        let runs = runs.max(1);
+        if self.consumes_board() {
+            let mut total = Duration::ZERO;
+            for _ in 0..runs {
+                let mut board = self.setup(size);
+                let start = Instant::now();
+                self.run(black_box(&mut board));
+                total += start.elapsed();
+            }
+            return total / runs;
+        }
+        let mut board = self.setup(size);
+        let start = Instant::now();
+        for _ in 0..runs {
+            self.run(black_box(&mut board));
+        }
+        start.elapsed() / runs
+    }
+}
@@ -0,0 +1,266 @@
+//! Cooperative play, several players on one board.
+//!
+//! Players act on the same [`Board`], each with their own cursor and colour. Whoever
+//! places a flag owns it until it is taken down, and each player's [`Contribution`]
+//! is counted as they play. A mine hit by anyone ends the game for everyone.
+//!
+//! # Ordering
+//!
+//! Actions never run at the same time: a [`Crew`] applies them one at a time, in the
+//! order they reach [`Crew::apply`], and logs each as the next [`Turn`]. On one
+//! keyboard that is the order of the key presses; on the [`server`](crate::server)
+//! it is the order the room's requests arrive in. An action that no longer makes
+//! sense by its turn, such as revealing a cell someone has just flagged, does
+//! nothing but is still logged. The board records the same actions in the same
+//! order, so its replay reproduces the game, and [`Crew::reproduce`] plays the log
+//! back on a fresh board to reproduce who did what too.
+use crate::game_logic::Board;
+use crate::race::{self, Keys};
+use crate::types::{Action, COLOR_CONFIG, CellState, GameConfig, GameState, LossReason, Settings};
+use crossterm::event::KeyCode;
+use crossterm::style::Color;
+use std::collections::{BTreeMap, HashMap};
+
+/// One action in a co-op game, in the order it was applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Turn {
+    pub player: usize,
+    pub action: Action,
+}
+
+/// What one player has done for the team.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Contribution {
+    /// Cells revealed by their reveals and chords.
+    pub revealed: usize,
+    /// Flags they have placed, including ones since taken down.
+    pub flags_placed: usize,
+    /// Chords that revealed at least one cell.
+    pub chords: usize,
+    /// Mines they hit.
+    pub mines_hit: usize,
+}
+
+/// Orders the actions of several players on one board and keeps track of who did what.
+///
+/// Players are told apart by any number the caller likes, such as their index or
+/// connection.
+#[derive(Clone, Debug, Default)]
+pub struct Crew {
+    turns: Vec<Turn>,
+    flag_owners: HashMap<(isize, isize), usize>,
+    contributions: BTreeMap<usize, Contribution>,
+}
+
+impl Crew {
+    /// Creates a crew that hasn't played yet.
+    pub fn new() -> Crew {
+        Crew::default()
+    }
+
+    /// Applies `player`'s action to `board` as the next turn, crediting them with
+    /// what it did.
+    ///
+    /// Returns Some(GameState) if the game state changes (win/loss), like
+    /// [`Board::apply_action`].
+    pub fn apply(&mut self, board: &mut Board, player: usize, action: Action) -> Option<GameState> {
+        let (Action::Reveal { x, y }
+        | Action::Flag { x, y }
+        | Action::Chord { x, y }
+        | Action::FlagChord { x, y }) = action;
+        self.turns.push(Turn { player, action });
+        let revealed = board.get_revealed_count();
+        let before = board.get_cell(x, y).map(|cell| cell.state);
+        let state = board.apply_action(action);
+        let after = board.get_cell(x, y).map(|cell| cell.state);
+
+        let contribution = self.contributions.entry(player).or_default();
+        let newly_revealed = board.get_revealed_count().saturating_sub(revealed);
+        contribution.revealed += newly_revealed;
+        if matches!(action, Action::Chord { .. }) && newly_revealed > 0 {
+            contribution.chords += 1;
+        }
+        if state == Some(GameState::Lost) && board.get_loss_reason() == Some(LossReason::Mine) {
+            contribution.mines_hit += 1;
+        }
+        match (before, after) {
+            // Adding a mine to a flag keeps it with whoever planted it
+            (Some(CellState::Flagged(_)), Some(CellState::Flagged(_))) => {}
+            (_, Some(CellState::Flagged(_))) => {
+                contribution.flags_placed += 1;
+                self.flag_owners.insert((x, y), player);
+            }
+            _ => {
+                self.flag_owners.remove(&(x, y));
+            }
+        }
+        state
+    }
+
+    /// Returns every turn so far, in the order they were applied.
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    /// Returns the player who placed the flag on (x, y), if it is flagged.
+    pub fn flag_owner(&self, x: isize, y: isize) -> Option<usize> {
+        self.flag_owners.get(&(x, y)).copied()
+    }
+
+    /// Returns how many of the flags on the board `player` placed.
+    pub fn flags_of(&self, player: usize) -> usize {
+        self.flag_owners
+            .values()
+            .filter(|&&owner| owner == player)
+            .count()
+    }
+
+    /// Returns what `player` has done so far.
+    pub fn contribution(&self, player: usize) -> Contribution {
+        self.contributions.get(&player).copied().unwrap_or_default()
+    }
+
+    /// Plays `turns` back in order on `board`, which must be dealt the way the
+    /// original was: same size, settings and seed.
+    ///
+    /// Returns the crew that played them and the final state of the game.
+    pub fn reproduce(board: &mut Board, turns: &[Turn]) -> (Crew, GameState) {
+        let mut crew = Crew::new();
+        let mut state = GameState::Ongoing;
+        for turn in turns {
+            if let Some(changed) = crew.apply(board, turn.player, turn.action) {
+                state = changed;
+            }
+        }
+        (crew, state)
+    }
+}
+
+/// One player sharing a co-op board at this terminal.
+pub struct Teammate {
+    pub name: String,
+    pub color: Color,
+    pub keys: Keys,
+    pub cursor: (isize, isize),
+}
+
+/// A co-op game on one keyboard, each player with their own keys.
+pub struct Coop {
+    pub board: Board,
+    pub crew: Crew,
+    pub players: Vec<Teammate>,
+    pub state: GameState,
+}
+
+impl Coop {
+    /// Starts a co-op game. Players take the colours in
+    /// [`COLOR_CONFIG`]`.players` in order and start with their cursors spread
+    /// along the middle row.
+    ///
+    /// Co-op games have a single life, whatever `settings` say.
+    pub fn new(
+        config: GameConfig,
+        settings: Settings,
+        seed: u64,
+        players: &[(&str, Keys)],
+    ) -> Coop {
+        let mut board = Board::new_with_seed(config, seed);
+        board.settings = Settings {
+            lives: 1,
+            ..settings
+        };
+        let (_, y) = race::start_cell(config);
+        let players = players
+            .iter()
+            .enumerate()
+            .map(|(i, &(name, keys))| Teammate {
+                name: name.to_string(),
+                color: COLOR_CONFIG.players[i % COLOR_CONFIG.players.len()],
+                keys,
+                cursor: (
+                    (config.width() * (2 * i + 1) / (2 * players.len())) as isize,
+                    y,
+                ),
+            })
+            .collect();
+        Coop {
+            board,
+            crew: Crew::new(),
+            players,
+            state: GameState::Ongoing,
+        }
+    }
+
+    /// Handles a key press for whichever player it belongs to, moving their cursor
+    /// or playing the cell under it.
+    ///
+    /// Returns the player and the action they played, if any. Nothing is played once
+    /// the game is over.
+    pub fn handle_key(&mut self, code: KeyCode) -> Option<(usize, Action)> {
+        if self.state != GameState::Ongoing {
+            return None;
+        }
+        let index = self.players.iter().position(|player| {
+            let keys = player.keys;
+            [
+                keys.up,
+                keys.down,
+                keys.left,
+                keys.right,
+                keys.reveal,
+                keys.flag,
+            ]
+            .contains(&code)
+        })?;
+        let player = &mut self.players[index];
+        let keys = player.keys;
+        let (x, y) = player.cursor;
+        let step = match code {
+            code if code == keys.up => Some((0, -1)),
+            code if code == keys.down => Some((0, 1)),
+            code if code == keys.left => Some((-1, 0)),
+            code if code == keys.right => Some((1, 0)),
+            _ => None,
+        };
+        if let Some((dx, dy)) = step {
+            player.cursor = (
+                (x + dx).clamp(0, self.board.width as isize - 1),
+                (y + dy).clamp(0, self.board.height as isize - 1),
+            );
+            return None;
+        }
+        let action = if code == keys.flag {
+            Action::Flag { x, y }
+        } else if self.board.get_cell(x, y)?.state == CellState::Revealed {
+            Action::Chord { x, y }
+        } else {
+            Action::Reveal { x, y }
+        };
+        if let Some(state) = self.crew.apply(&mut self.board, index, action) {
+            self.finish(state);
+        }
+        Some((index, action))
+    }
+
+    /// Ends the game as lost once the time limit has run out.
+    pub fn check_deadline(&mut self) {
+        if self.state == GameState::Ongoing
+            && let Some(state) = self.board.check_deadline()
+        {
+            self.finish(state);
+        }
+    }
+
+    /// Ends the game, showing every mine if it was lost.
+    fn finish(&mut self, state: GameState) {
+        self.state = state;
+        if state == GameState::Lost {
+            self.board.reveal_all_mines();
+        }
+    }
+
+    /// Returns the player who hit a mine, once the game is lost to one.
+    pub fn culprit(&self) -> Option<usize> {
+        (0..self.players.len()).find(|&player| self.crew.contribution(player).mines_hit > 0)
+    }
+}
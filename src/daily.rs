@@ -0,0 +1,154 @@
+//! Daily challenge: one board per calendar day, the same for every player.
+//!
+//! The seed comes only from the local date, so the challenge works offline.
+//! The first attempt of a day is official and recorded in its own result log;
+//! later attempts on the same board are practice.
+use crate::Result;
+use crate::game_logic::Board;
+use crate::race;
+use crate::shape::{Neighbourhood, Shape};
+use crate::stats::{Outcome, ResultEntry, ResultLog, Rules};
+use crate::topology::Topology;
+use crate::types::{Action, FirstClick, GameMode, Noise, Preset, Settings};
+use chrono::{Datelike, Days, Local, NaiveDate};
+
+/// Difficulty of every daily challenge.
+pub const DAILY_PRESET: Preset = Preset::Intermediate;
+/// Result log file for official daily attempts, in the [`crate::stats::data_dir`].
+pub const DAILY_LOG: &str = "daily.txt";
+
+/// Returns the local date.
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Returns the board seed for `date`.
+pub fn seed(date: NaiveDate) -> u64 {
+    // SplitMix64, so neighbouring days get unrelated seeds
+    let mut z = (date.num_days_from_ce() as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Creates the daily board for `date`, played with the player's `settings`.
+///
+/// Settings that change the rules are overridden so everyone plays the same game:
+/// there is no time limit, one life and the edges don't wrap. The mines are dealt
+/// around the centre rather than the first click, and the opening there is
+/// revealed, so every player starts from the same board. The clock starts with
+/// the player's first move.
+pub fn new_board(date: NaiveDate, official: bool, settings: Settings) -> Board {
+    let config = DAILY_PRESET.config();
+    let mut board = Board::new_with_seed(config, seed(date));
+    board.settings = Settings {
+        first_click: FirstClick::Opening,
+        time_limit: None,
+        lives: 1,
+        topology: Topology::Bounded,
+        shape: Shape::Square,
+        neighbourhood: Neighbourhood::Touching,
+        mines_per_cell: 1,
+        noise: Noise::Off,
+        ..settings
+    };
+    board.mode = GameMode::Daily { date, official };
+    let (x, y) = race::start_cell(config);
+    board.apply_action(Action::Reveal { x, y });
+    board.cursor = Some((x, y));
+    board.timer.reset();
+    board
+}
+
+/// Official daily results, at most one per day.
+pub struct DailyHistory {
+    log: ResultLog,
+}
+
+impl DailyHistory {
+    /// Loads the history from the data directory.
+    pub fn load() -> Result<DailyHistory> {
+        Ok(DailyHistory::from_log(ResultLog::open(DAILY_LOG)?))
+    }
+
+    /// Wraps an already loaded result log.
+    pub fn from_log(log: ResultLog) -> DailyHistory {
+        DailyHistory { log }
+    }
+
+    /// Returns the official result for `date`, if that day was attempted.
+    pub fn entry(&self, date: NaiveDate) -> Option<&ResultEntry> {
+        self.log.entries.iter().find(|entry| entry.date == date)
+    }
+
+    /// Returns true if the official attempt for `date` has been used.
+    pub fn has_attempted(&self, date: NaiveDate) -> bool {
+        self.entry(date).is_some()
+    }
+
+    /// Uses up the official attempt for `date`.
+    ///
+    /// It is saved as abandoned right away, so quitting doesn't give another try.
+    pub fn start(&mut self, date: NaiveDate) -> Result<()> {
+        if !self.has_attempted(date) {
+            return self.log.push(ResultEntry {
+                date,
+                outcome: Outcome::Abandoned,
+                time_ms: 0,
+                config: DAILY_PRESET.config(),
+                rules: Rules::default(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Records how the official attempt for `date` ended.
+    pub fn finish(&mut self, date: NaiveDate, outcome: Outcome, time_ms: u32) -> Result<()> {
+        let entry = ResultEntry {
+            date,
+            outcome,
+            time_ms,
+            config: DAILY_PRESET.config(),
+            rules: Rules::default(),
+        };
+        match self.log.entries.iter_mut().find(|entry| entry.date == date) {
+            Some(existing) => *existing = entry,
+            None => self.log.entries.push(entry),
+        }
+        self.log.save()
+    }
+
+    /// Returns the number of consecutive days won up to `today`.
+    ///
+    /// A day that hasn't been played yet doesn't break the streak until it's over.
+    pub fn streak(&self, today: NaiveDate) -> usize {
+        let won = |date| {
+            self.entry(date)
+                .is_some_and(|entry| entry.outcome == Outcome::Won)
+        };
+        let mut date = today;
+        if !self.has_attempted(today) {
+            match today.checked_sub_days(Days::new(1)) {
+                Some(yesterday) => date = yesterday,
+                None => return 0,
+            }
+        }
+        let mut streak = 0;
+        while won(date) {
+            streak += 1;
+            match date.checked_sub_days(Days::new(1)) {
+                Some(previous) => date = previous,
+                None => break,
+            }
+        }
+        streak
+    }
+
+    /// Returns the `days` days up to `today`, newest first, with their results.
+    pub fn recent(&self, today: NaiveDate, days: u64) -> Vec<(NaiveDate, Option<&ResultEntry>)> {
+        (0..days)
+            .filter_map(|offset| today.checked_sub_days(Days::new(offset)))
+            .map(|date| (date, self.entry(date)))
+            .collect()
+    }
+}
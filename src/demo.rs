@@ -0,0 +1,91 @@
+//! Demo mode: a built-in agent plays on its own.
+//!
+//! Picking "Demo" in the main menu, or leaving the menu alone for
+//! [`IDLE_TIMEOUT`], has the [`Probability`] agent play one game after another
+//! at a pace a person can follow, showing what it worked out before each move.
+use crate::agent::{Agent, Probability, Thinking, View};
+use crate::game_logic::Board;
+use crate::types::{Action, DEFAULT_SETTINGS, GameState, Preset};
+use std::time::{Duration, Instant};
+
+/// Board the demo is played on.
+pub const DEMO_PRESET: Preset = Preset::Intermediate;
+/// How long the menu sits untouched before the demo starts.
+pub const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time between the agent's moves.
+const MOVE_DELAY: Duration = Duration::from_millis(350);
+/// How long a finished game stays on screen before the next is dealt.
+const GAME_OVER_DELAY: Duration = Duration::from_secs(3);
+
+/// A game the agent is playing for show.
+pub struct Demo {
+    pub board: Board,
+    pub state: GameState,
+    agent: Probability,
+    /// The move the agent is about to play, shown before it is played.
+    next: Option<Action>,
+    /// When the last move was chosen or played.
+    changed: Instant,
+}
+
+impl Default for Demo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Demo {
+    /// Deals a demo game on a random board.
+    pub fn new() -> Demo {
+        let mut board = Board::new_with_seed(DEMO_PRESET.config(), rand::random());
+        // The agent plays by the classic rules
+        board.settings = DEFAULT_SETTINGS;
+        Demo {
+            board,
+            state: GameState::Ongoing,
+            agent: Probability::new(),
+            next: None,
+            changed: Instant::now(),
+        }
+    }
+
+    /// Moves the game on once it is time: the agent chooses a move, which stays on
+    /// screen for a moment, then plays it. A new game is dealt a while after one ends.
+    pub fn update(&mut self) {
+        if self.state != GameState::Ongoing {
+            if self.changed.elapsed() >= GAME_OVER_DELAY {
+                *self = Demo::new();
+            }
+            return;
+        }
+        if self.changed.elapsed() < MOVE_DELAY {
+            return;
+        }
+        self.changed = Instant::now();
+        match self.next.take() {
+            Some(action) => {
+                if let Some(state) = self.board.apply_action(action) {
+                    self.state = state;
+                    if state == GameState::Lost {
+                        self.board.reveal_all_mines();
+                    }
+                }
+            }
+            None => {
+                let action = self.agent.next_action(&View::of(&self.board));
+                let (Action::Reveal { x, y }
+                | Action::Flag { x, y }
+                | Action::Chord { x, y }
+                | Action::FlagChord { x, y }) = action;
+                self.board.cursor = Some((x, y));
+                self.next = Some(action);
+            }
+        }
+    }
+
+    /// Returns what the agent made of the board when choosing the move it is about
+    /// to play, if it has chosen one.
+    pub fn thinking(&self) -> Option<&Thinking> {
+        self.next.and(self.agent.thinking())
+    }
+}
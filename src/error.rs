@@ -0,0 +1,76 @@
+//! Error type for the termsweeper library.
+//!
+//! Library functions return [`Error`] through the [`Result`] alias; the binary
+//! wraps it in `anyhow` at the top level.
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Errors returned by the termsweeper library.
+///
+/// I/O failures keep the underlying error as their [`source`](std::error::Error::source),
+/// and name it in their message so it shows wherever the error is displayed.
+#[derive(Debug)]
+pub enum Error {
+    /// A game configuration outside the allowed limits.
+    InvalidConfig(String),
+    /// Reading from or writing to the terminal failed.
+    Terminal(io::Error),
+    /// Reading from or writing to a network socket failed.
+    Network(io::Error),
+    /// Loading or saving a file failed.
+    Persistence { path: PathBuf, source: io::Error },
+    /// Text input could not be parsed. Line and column are 1-based.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A binary file is not valid in its format. The offset is in bytes.
+    Format {
+        format: &'static str,
+        offset: usize,
+        message: String,
+    },
+}
+
+/// Result alias using the library [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidConfig(message) => write!(f, "invalid configuration: {}", message),
+            Error::Terminal(source) => write!(f, "terminal error: {}", source),
+            Error::Network(source) => write!(f, "network error: {}", source),
+            Error::Persistence { path, source } => {
+                write!(f, "could not access {}: {}", path.display(), source)
+            }
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "parse error at line {}, column {}: {}",
+                line, column, message
+            ),
+            Error::Format {
+                format,
+                offset,
+                message,
+            } => write!(f, "invalid {} file at byte {}: {}", format, offset, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Terminal(source)
+            | Error::Network(source)
+            | Error::Persistence { source, .. } => Some(source),
+            Error::InvalidConfig(_) | Error::Parse { .. } | Error::Format { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Readers and writers for community Minesweeper file formats.
+//!
+//! - `.mbf` (Minesweeper Arbiter board): [`read_mbf`], [`write_mbf`]
+//! - `.avf` (Minesweeper Arbiter video): [`read_avf`]
+//! - `.rmv` (Viennasweeper video): [`read_rmv`], [`write_rmv`]
+//!
+//! Videos are converted to [`Replay`]s, so they play back through the normal board.
+//! All multi-byte numbers in these formats are big-endian.
+use crate::game_logic::Board;
+use crate::replay::{self, MouseInput, MouseRecord, Replay};
+use crate::shape::{Neighbourhood, Shape};
+use crate::topology::Topology;
+use crate::types::MAX_MINES_PER_CELL;
+use crate::{Error, Result};
+use std::fs;
+use std::path::Path;
+
+/// Pixel size of a cell in Arbiter videos.
+const AVF_SQUARE_SIZE: u16 = 16;
+/// Pixel size of a cell in the RMV files we write.
+const RMV_SQUARE_SIZE: u16 = 16;
+/// Name stored in the `clone_name` property of the RMV files we write.
+const RMV_CLONE_NAME: &str = "termsweeper";
+/// RMV extension property holding the board topology, written for wrap-around boards only.
+const RMV_TOPOLOGY_KEY: &str = "topology";
+/// RMV extension property holding the cell shape, written for hex boards only.
+const RMV_SHAPE_KEY: &str = "shape";
+/// RMV extension property holding the neighbourhood, written when it isn't the touching cells.
+const RMV_NEIGHBOURHOOD_KEY: &str = "neighbourhood";
+/// RMV extension property holding the most mines a cell could hold, written when above 1.
+///
+/// Cells with several mines are listed once per mine in the mine list.
+const RMV_MINES_PER_CELL_KEY: &str = "mines_per_cell";
+/// RMV extension property for a number that lies, as `x,y=shown`. There is one per lie.
+const RMV_LIE_KEY: &str = "lie";
+
+/// Reads a board from `.mbf` bytes: width, height, mine count (u16), then one (x, y) byte pair per mine.
+pub fn read_mbf(bytes: &[u8]) -> Result<Board> {
+    let mut reader = ByteReader::new(bytes, "MBF");
+    let width = reader.u8()? as usize;
+    let height = reader.u8()? as usize;
+    let count = reader.u16()?;
+    let mut mines = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let x = reader.u8()? as usize;
+        let y = reader.u8()? as usize;
+        mines.push((x, y));
+    }
+    Board::from_mines(width, height, &mines)
+}
+
+/// Writes the mines of a board as `.mbf` bytes.
+///
+/// The format has no room for a topology, cell shape or neighbourhood, so boards
+/// read back as classic ones.
+pub fn write_mbf(board: &Board) -> Vec<u8> {
+    let mines = board.get_mine_positions();
+    let mut bytes = vec![board.width as u8, board.height as u8];
+    bytes.extend_from_slice(&(mines.len() as u16).to_be_bytes());
+    for (x, y) in mines {
+        bytes.extend_from_slice(&[x as u8, y as u8]);
+    }
+    bytes
+}
+
+/// Reads a Minesweeper Arbiter `.avf` video.
+pub fn read_avf(bytes: &[u8]) -> Result<Replay> {
+    let mut reader = ByteReader::new(bytes, "AVF");
+    reader.u8()?; // version
+    reader.skip(4)?;
+    let (width, height, mine_count) = match reader.u8()? {
+        3 => (8, 8, 10),
+        4 => (16, 16, 40),
+        5 => (30, 16, 99),
+        6 => {
+            let width = reader.u8()? as usize + 1;
+            let height = reader.u8()? as usize + 1;
+            (width, height, reader.u16()? as usize)
+        }
+        level => return Err(reader.error(format!("unknown level {}", level))),
+    };
+    let mut mines = Vec::with_capacity(mine_count);
+    for _ in 0..mine_count {
+        // 1-based row, then column
+        let y = reader.u8()? as usize;
+        let x = reader.u8()? as usize;
+        if x == 0 || y == 0 {
+            return Err(reader.error("mine position out of range".to_string()));
+        }
+        mines.push((x - 1, y - 1));
+    }
+
+    // The header text starts at "[<level>|", preceded by the question mark flag two bytes earlier
+    let mut window = [0u8; 5];
+    loop {
+        window.rotate_left(1);
+        window[4] = reader.u8()?;
+        if window[2] == b'[' && (b'0'..=b'3').contains(&window[3]) && window[4] == b'|' {
+            break;
+        }
+    }
+    let question_marks = match window[0] {
+        17 => true,
+        127 => false,
+        _ => return Err(reader.error("invalid question mark flag".to_string())),
+    };
+    // Skip the timestamps and other fields up to "|B<3bv>T<time>]"
+    let mut pair = [0u8; 2];
+    while pair != *b"|B" {
+        pair[0] = pair[1];
+        pair[1] = reader.u8()?;
+    }
+    let mut bbbv = None;
+    let mut byte = reader.u8()?;
+    while byte.is_ascii_digit() {
+        bbbv = Some(bbbv.unwrap_or(0) * 10 + (byte - b'0') as usize);
+        byte = reader.u8()?;
+    }
+    if byte != b']' {
+        reader.until(b']')?;
+    }
+
+    // Events are 8 bytes each; the first one is a mouse move at second 1
+    let mut event = [0u8; 8];
+    while event[2] != 1 || event[1] > 1 {
+        event[0] = event[1];
+        event[1] = event[2];
+        event[2] = reader.u8()?;
+    }
+    for byte in event.iter_mut().skip(3) {
+        *byte = reader.u8()?;
+    }
+    let mut records = Vec::new();
+    loop {
+        let input = match event[0] {
+            1 => MouseInput::Move,
+            3 => MouseInput::LeftDown,
+            5 | 21 => MouseInput::LeftUp,
+            9 => MouseInput::RightDown,
+            17 | 145 => MouseInput::RightUp,
+            33 => MouseInput::MiddleDown,
+            65 | 193 => MouseInput::MiddleUp,
+            code => return Err(reader.error(format!("unknown event {}", code))),
+        };
+        // Seconds start at 1, hundredths are stored separately
+        let seconds = (u16::from(event[6]) << 8 | u16::from(event[2])) as u32;
+        records.push(MouseRecord {
+            time_ms: seconds.saturating_sub(1) * 1000 + u32::from(event[4]) * 10,
+            input,
+            x: u16::from(event[1]) << 8 | u16::from(event[3]),
+            y: u16::from(event[5]) << 8 | u16::from(event[7]),
+        });
+        for byte in event.iter_mut() {
+            *byte = reader.u8()?;
+        }
+        if event[2] == 0 && event[6] == 0 {
+            break;
+        }
+    }
+    let events = replay::actions_from_mouse(&records, AVF_SQUARE_SIZE);
+    finish_replay(Replay {
+        width,
+        height,
+        mines,
+        topology: Topology::Bounded,
+        shape: Shape::Square,
+        neighbourhood: Neighbourhood::Touching,
+        mines_per_cell: 1,
+        lies: Vec::new(),
+        question_marks,
+        completed: false,
+        bbbv,
+        events,
+    })
+}
+
+/// Reads a Viennasweeper `.rmv` video (format versions 1 and 2).
+pub fn read_rmv(bytes: &[u8]) -> Result<Replay> {
+    let mut reader = ByteReader::new(bytes, "RMV");
+    if reader.bytes(4)? != b"*rmv" {
+        return Err(reader.error("missing *rmv signature".to_string()));
+    }
+    let version = reader.u16()?;
+    if version == 0 || version > 2 {
+        return Err(reader.error(format!("unsupported format version {}", version)));
+    }
+    if version >= 2 {
+        reader.skip(2)?; // clone id and clone major version
+    }
+    reader.skip(4)?;
+    let result_string_size = if version == 1 { reader.u16()? } else { 0 };
+    let version_info_size = reader.u16()?;
+    reader.skip(4)?;
+    let preflags_size = reader.u16()?;
+    let properties_size = reader.u16()? as usize;
+    if version >= 2 {
+        reader.skip(2)?; // extension properties size
+    }
+    reader.skip(6)?;
+    if version == 1 {
+        reader.skip(result_string_size as usize + 1)?;
+    }
+    reader.skip(version_info_size as usize)?;
+    let player_fields = reader.u16()?;
+    for _ in 0..player_fields.min(4) {
+        let length = reader.u8()? as usize;
+        reader.skip(length)?;
+    }
+    reader.skip(4)?; // board generation timestamp
+
+    let width = reader.u8()? as usize;
+    let height = reader.u8()? as usize;
+    let mine_count = reader.u16()?;
+    let mut mines = Vec::with_capacity(mine_count as usize);
+    for _ in 0..mine_count {
+        let x = reader.u8()? as usize;
+        let y = reader.u8()? as usize;
+        mines.push((x, y));
+    }
+    let mut preflags = Vec::new();
+    if preflags_size > 0 {
+        for _ in 0..reader.u16()? {
+            let x = reader.u8()? as u16;
+            let y = reader.u8()? as u16;
+            preflags.push((x, y));
+        }
+    }
+
+    let question_marks = reader.u8()? == 1;
+    reader.skip(3)?; // no flags, mode and level
+    let mut square_size = 16;
+    let mut properties_read = 4;
+    let mut bbbv = None;
+    if version >= 2 {
+        bbbv = Some(reader.u16()? as usize);
+        square_size = reader.u8()? as u16;
+        properties_read += 3;
+    }
+    reader.skip(properties_size.saturating_sub(properties_read))?;
+    // Our own files say when the board wraps around, has hex cells or counts other
+    // neighbours; other programs only make classic boards
+    let mut topology = Topology::Bounded;
+    let mut shape = Shape::Square;
+    let mut neighbourhood = Neighbourhood::Touching;
+    let mut mines_per_cell = 1;
+    let mut lies = Vec::new();
+    if version >= 2 {
+        for _ in 0..reader.u16()? {
+            let key_length = reader.u8()? as usize;
+            let key = reader.bytes(key_length)?;
+            let value_length = reader.u8()? as usize;
+            let value = reader.bytes(value_length)?;
+            let (key, value) = (String::from_utf8_lossy(key), String::from_utf8_lossy(value));
+            let unknown = || reader.error(format!("unknown {} '{}'", key, value));
+            match key.as_ref() {
+                RMV_TOPOLOGY_KEY => topology = Topology::from_key(&value).ok_or_else(unknown)?,
+                RMV_SHAPE_KEY => shape = Shape::from_key(&value).ok_or_else(unknown)?,
+                RMV_NEIGHBOURHOOD_KEY => {
+                    neighbourhood = Neighbourhood::from_key(&value).ok_or_else(unknown)?
+                }
+                RMV_MINES_PER_CELL_KEY => {
+                    mines_per_cell = value
+                        .parse()
+                        .ok()
+                        .filter(|n| (1..=MAX_MINES_PER_CELL).contains(n))
+                        .ok_or_else(unknown)?
+                }
+                RMV_LIE_KEY => lies.push(parse_lie(&value).ok_or_else(unknown)?),
+                _ => {}
+            }
+        }
+    }
+
+    let mut records: Vec<MouseRecord> = preflags
+        .into_iter()
+        .flat_map(|(x, y)| {
+            let (x, y) = (x * square_size, y * square_size);
+            [MouseInput::RightDown, MouseInput::RightUp].map(|input| MouseRecord {
+                time_ms: 0,
+                input,
+                x,
+                y,
+            })
+        })
+        .collect();
+    // Version 1 mouse positions include the window frame
+    let (x_offset, y_offset) = if version == 1 { (12, 56) } else { (0, 0) };
+    let (mut x, mut y, mut time_ms) = (0u16, 0u16, 0u32);
+    loop {
+        match reader.u8()? {
+            0 if version == 1 => reader.skip(4)?,
+            code @ (1..=7 | 28) => {
+                if code == 28 {
+                    if records.is_empty() || version < 2 {
+                        return Err(reader.error("unexpected compressed move".to_string()));
+                    }
+                    // Time delta, then packed signed 4-bit x and y deltas
+                    time_ms += reader.u8()? as u32;
+                    let delta = reader.u8()?;
+                    x = x.wrapping_add(((delta >> 4) & 7) as u16);
+                    x = x.wrapping_sub(((delta >> 4) & 8) as u16);
+                    y = y.wrapping_add((delta & 7) as u16);
+                    y = y.wrapping_sub((delta & 8) as u16);
+                } else {
+                    time_ms = reader.u32()? >> 8;
+                    x = reader.u16()?.wrapping_sub(x_offset);
+                    y = reader.u16()?.wrapping_sub(y_offset);
+                }
+                let input = match code {
+                    2 => MouseInput::LeftDown,
+                    3 => MouseInput::LeftUp,
+                    4 => MouseInput::RightDown,
+                    5 => MouseInput::RightUp,
+                    6 => MouseInput::MiddleDown,
+                    7 => MouseInput::MiddleUp,
+                    _ => MouseInput::Move,
+                };
+                records.push(MouseRecord {
+                    time_ms,
+                    input,
+                    x,
+                    y,
+                });
+            }
+            9..=14 | 18..=27 => reader.skip(2)?, // board and score events
+            15..=17 => break,                    // end of game
+            code => return Err(reader.error(format!("unknown event {}", code))),
+        }
+    }
+    let events = replay::actions_from_mouse(&records, square_size);
+    finish_replay(Replay {
+        width,
+        height,
+        mines,
+        topology,
+        shape,
+        neighbourhood,
+        mines_per_cell,
+        lies,
+        question_marks,
+        completed: false,
+        bbbv,
+        events,
+    })
+}
+
+/// Writes a replay as a version 2 `.rmv` video.
+pub fn write_rmv(replay: &Replay) -> Result<Vec<u8>> {
+    let board = replay.to_board()?;
+    let level = match (replay.width, replay.height, replay.mines.len()) {
+        (8, 8, 10) | (9, 9, 10) => 0,
+        (16, 16, 40) => 1,
+        (30, 16, 99) => 2,
+        _ => 3,
+    };
+    let version_info = concat!("termsweeper ", env!("CARGO_PKG_VERSION")).as_bytes();
+    let mut properties = vec![("clone_name", RMV_CLONE_NAME)];
+    if replay.topology != Topology::Bounded {
+        properties.push((RMV_TOPOLOGY_KEY, replay.topology.key()));
+    }
+    if replay.shape != Shape::Square {
+        properties.push((RMV_SHAPE_KEY, replay.shape.key()));
+    }
+    if replay.neighbourhood != Neighbourhood::Touching {
+        properties.push((RMV_NEIGHBOURHOOD_KEY, replay.neighbourhood.key()));
+    }
+    let mines_per_cell = replay.mines_per_cell.to_string();
+    if replay.mines_per_cell > 1 {
+        properties.push((RMV_MINES_PER_CELL_KEY, &mines_per_cell));
+    }
+    let lies: Vec<String> = replay
+        .lies
+        .iter()
+        .map(|((x, y), shown)| format!("{},{}={}", x, y, shown))
+        .collect();
+    properties.extend(lies.iter().map(|lie| (RMV_LIE_KEY, lie.as_str())));
+    let mut extension = (properties.len() as u16).to_be_bytes().to_vec();
+    for (key, value) in properties {
+        extension.push(key.len() as u8);
+        extension.extend_from_slice(key.as_bytes());
+        extension.push(value.len() as u8);
+        extension.extend_from_slice(value.as_bytes());
+    }
+    let bbbv = board.get_3bv() as u16;
+
+    let mut bytes = b"*rmv".to_vec();
+    bytes.extend_from_slice(&2u16.to_be_bytes()); // format version
+    bytes.extend_from_slice(&[0, 0]); // clone id (named by extension property), clone version
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&(version_info.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&0u16.to_be_bytes()); // preflags size
+    bytes.extend_from_slice(&7u16.to_be_bytes()); // properties size
+    bytes.extend_from_slice(&(extension.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&[0; 6]);
+    bytes.extend_from_slice(version_info);
+    bytes.extend_from_slice(&0u16.to_be_bytes()); // player fields
+    bytes.extend_from_slice(&0u32.to_be_bytes()); // board generation timestamp
+    bytes.extend_from_slice(&[replay.width as u8, replay.height as u8]);
+    bytes.extend_from_slice(&(replay.mines.len() as u16).to_be_bytes());
+    for &(x, y) in &replay.mines {
+        bytes.extend_from_slice(&[x as u8, y as u8]);
+    }
+    bytes.extend_from_slice(&[replay.question_marks as u8, 0, 0, level]);
+    bytes.extend_from_slice(&bbbv.to_be_bytes());
+    bytes.push(RMV_SQUARE_SIZE as u8);
+    bytes.extend_from_slice(&extension);
+    for record in replay::mouse_from_actions(&replay.events, RMV_SQUARE_SIZE) {
+        let code = match record.input {
+            MouseInput::Move => 1,
+            MouseInput::LeftDown => 2,
+            MouseInput::LeftUp => 3,
+            MouseInput::RightDown => 4,
+            MouseInput::RightUp => 5,
+            MouseInput::MiddleDown => 6,
+            MouseInput::MiddleUp => 7,
+        };
+        bytes.push(code);
+        bytes.extend_from_slice(&(record.time_ms << 8).to_be_bytes());
+        bytes.extend_from_slice(&record.x.to_be_bytes());
+        bytes.extend_from_slice(&record.y.to_be_bytes());
+    }
+    bytes.push(if replay.completed { 16 } else { 15 });
+    Ok(bytes)
+}
+
+/// Reads a replay from a file, choosing the format from the `.avf` or `.rmv` extension.
+pub fn load_replay(path: &Path) -> Result<Replay> {
+    let bytes = read_file(path)?;
+    match extension(path).as_str() {
+        "avf" => read_avf(&bytes),
+        "rmv" => read_rmv(&bytes),
+        other => Err(Error::InvalidConfig(format!(
+            "unsupported replay format '{}', expected .avf or .rmv",
+            other
+        ))),
+    }
+}
+
+/// Writes a replay to a file as `.rmv`.
+pub fn save_replay(path: &Path, replay: &Replay) -> Result<()> {
+    fs::write(path, write_rmv(replay)?).map_err(|source| Error::Persistence {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Reads a board from a file: `.mbf` files are binary, anything else is a text layout.
+pub fn load_board(path: &Path) -> Result<Board> {
+    if extension(path) == "mbf" {
+        read_mbf(&read_file(path)?)
+    } else {
+        Board::load_layout(path)
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|source| Error::Persistence {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+/// Parses an `x,y=shown` lie property.
+fn parse_lie(value: &str) -> Option<((usize, usize), u8)> {
+    let (position, shown) = value.split_once('=')?;
+    let (x, y) = position.split_once(',')?;
+    Some(((x.parse().ok()?, y.parse().ok()?), shown.parse().ok()?))
+}
+
+/// Finishes reading a replay, checking the mines fit the board and working out
+/// whether it was completed.
+fn finish_replay(mut replay: Replay) -> Result<Replay> {
+    // Play the actions through once to learn whether the board was cleared
+    let mut board = replay.to_board()?;
+    for event in &replay.events {
+        if board.apply_action(event.action).is_some() {
+            break;
+        }
+    }
+    replay.completed = board.get_revealed_count() == board.get_safe_cells_count();
+    Ok(replay)
+}
+
+/// Reads big-endian values from a byte slice, reporting errors with the byte offset.
+struct ByteReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    format: &'static str,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(data: &'a [u8], format: &'static str) -> Self {
+        ByteReader {
+            data,
+            offset: 0,
+            format,
+        }
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::Format {
+            format: self.format,
+            offset: self.offset,
+            message,
+        }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        let end = self.offset + count;
+        let slice = self
+            .data
+            .get(self.offset..end)
+            .ok_or_else(|| self.error("unexpected end of file".to_string()))?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<()> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Skips past the next occurrence of `byte`.
+    fn until(&mut self, byte: u8) -> Result<()> {
+        while self.u8()? != byte {}
+        Ok(())
+    }
+}
@@ -1,140 +1,465 @@
+use crate::daily;
+use crate::grid::{ChunkedGrid, Grid};
+use crate::layout;
+use crate::replay::{Replay, ReplayEvent};
+use crate::shape::{Neighbourhood, Shape};
 use crate::timer::Timer;
+use crate::topology::Topology;
 use crate::types::*;
+use crate::{Error, Result};
 use crossterm::event;
 use rand::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 
 /// Represents the Minesweeper game board and its state.
 pub struct Board {
-    grid: Vec<CellBox>,
+    grid: Grid,
     /// Board width in cells.
     pub width: usize,
     /// Board height in cells.
     pub height: usize,
     /// Timer tracking game duration.
     pub timer: Timer,
+    /// Player preferences for this board.
+    pub settings: Settings,
+    /// Last cell the mouse pointed at, if any.
+    pub cursor: Option<(isize, isize)>,
+    /// Top-left cell of the visible area. Only endless boards scroll away from (0, 0).
+    pub viewport: (isize, isize),
+    /// Mode this game is played in.
+    pub mode: GameMode,
+    loss_reason: Option<LossReason>,
+    /// Mines revealed so far; each one costs a life.
+    mines_hit: usize,
+    /// Safe cells revealed so far, kept up to date so win checks don't scan the grid.
+    revealed: usize,
+    /// Mines flagged so far, kept up to date for the mine counter.
+    flags: usize,
+    /// Cells without a mine, counted once the mines are placed.
+    safe_cells: usize,
     mines_placed: bool,
-    mines_to_place: usize,
+    /// Size and mines the board was dealt with.
+    config: GameConfig,
+    seed: u64,
+    left_held: bool,
+    right_held: bool,
+    /// Fixed starting grid for boards loaded from a layout, restored on reset.
+    initial_grid: Option<Vec<CellBox>>,
+    /// Actions taken so far, with the time they happened.
+    recording: Vec<ReplayEvent>,
+    /// Numbers shown instead of the true count, for cells whose number lies.
+    lies: HashMap<(isize, isize), u8>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
     /// Creates a new board with default configuration.
     pub fn new() -> Board {
-        let grid = vec![
-            CellBox {
-                kind: CellKind::Number(0),
-                state: CellState::Hidden,
-            };
-            DEFAULT_CONFIG.width * DEFAULT_CONFIG.height
-        ];
+        Board::new_with_config(DEFAULT_CONFIG)
+    }
+
+    /// Creates a new board with a custom configuration.
+    pub fn new_with_config(config: GameConfig) -> Board {
         Board {
-            grid,
-            width: DEFAULT_CONFIG.width,
-            height: DEFAULT_CONFIG.height,
+            grid: Grid::dense(config.width(), config.height()),
+            width: config.width(),
+            height: config.height(),
             timer: Timer::new(),
-            mines_to_place: DEFAULT_CONFIG.mines,
+            settings: DEFAULT_SETTINGS,
+            cursor: None,
+            viewport: (0, 0),
+            mode: GameMode::Classic,
+            loss_reason: None,
+            mines_hit: 0,
+            revealed: 0,
+            flags: 0,
+            safe_cells: (config.width() * config.height()).saturating_sub(config.mines()),
+            config,
             mines_placed: false,
+            seed: rand::random(),
+            left_held: false,
+            right_held: false,
+            initial_grid: None,
+            recording: Vec::new(),
+            lies: HashMap::new(),
         }
     }
 
-    /// Creates a new board with a custom configuration.
-    pub fn new_with_config(config: GameConfig) -> Board {
-        let grid = vec![
+    /// Creates a new board whose mines are placed from `seed`.
+    pub fn new_with_seed(config: GameConfig, seed: u64) -> Board {
+        let mut board = Board::new_with_config(config);
+        board.seed = seed;
+        board
+    }
+
+    /// Creates an endless board on an unbounded plane, with the opening around (0, 0) revealed.
+    ///
+    /// The player's `settings` are used, except that there is one life and no time limit:
+    /// the score is the number of cells cleared before the first mine.
+    pub fn new_endless(seed: u64, settings: Settings) -> Board {
+        let mut board = Board::new_with_seed(ENDLESS_CONFIG, seed);
+        board.grid = Grid::Chunked(ChunkedGrid::new(seed, ENDLESS_MINES_PER_CHUNK));
+        board.settings = Settings {
+            lives: 1,
+            time_limit: None,
+            topology: Topology::Bounded,
+            shape: Shape::Square,
+            neighbourhood: Neighbourhood::Touching,
+            mines_per_cell: 1,
+            noise: Noise::Off,
+            ..settings
+        };
+        board.mode = GameMode::Endless;
+        board.mines_placed = true;
+        board.viewport = (
+            -(ENDLESS_WIDTH as isize) / 2,
+            -(ENDLESS_HEIGHT as isize) / 2,
+        );
+        board.reveal_adjacent_empty(0, 0);
+        board
+    }
+
+    /// Creates a board from a plain-text layout, see [`crate::layout`].
+    ///
+    /// Mines are fixed by the layout, so the first click is not protected.
+    pub fn from_layout(text: &str) -> Result<Board> {
+        let layout = layout::parse(text)?;
+        let mut board = Board::from_cells(layout.width, layout.height, layout.cells)?;
+        board.settings.topology = layout.topology;
+        board.settings.shape = layout.shape;
+        board.settings.neighbourhood = layout.neighbourhood;
+        Ok(board)
+    }
+
+    /// Creates a board with mines at the given (x, y) positions and all cells hidden.
+    ///
+    /// A position listed more than once gets a mine for each time it is listed, up
+    /// to [`MAX_MINES_PER_CELL`].
+    pub fn from_mines(width: usize, height: usize, mines: &[(usize, usize)]) -> Result<Board> {
+        if !(1..=MAX_WIDTH).contains(&width) || !(1..=MAX_HEIGHT).contains(&height) {
+            return Err(Error::InvalidConfig(format!(
+                "boards can be at most {}x{}, got {}x{}",
+                MAX_WIDTH, MAX_HEIGHT, width, height
+            )));
+        }
+        let mut cells = vec![
             CellBox {
                 kind: CellKind::Number(0),
                 state: CellState::Hidden,
             };
-            config.width * config.height
+            width * height
         ];
-        Board {
-            grid,
-            width: config.width,
-            height: config.height,
-            timer: Timer::new(),
-            mines_to_place: config.mines,
-            mines_placed: false,
+        for &(x, y) in mines {
+            if x >= width || y >= height {
+                return Err(Error::InvalidConfig(format!(
+                    "mine at ({}, {}) is outside the {}x{} board",
+                    x, y, width, height
+                )));
+            }
+            let cell = &mut cells[y * width + x];
+            if cell.kind.mines() >= MAX_MINES_PER_CELL as usize {
+                return Err(Error::InvalidConfig(format!(
+                    "({}, {}) holds more than {} mines",
+                    x, y, MAX_MINES_PER_CELL
+                )));
+            }
+            cell.kind = CellKind::Mine(cell.kind.mines() as u8 + 1);
+        }
+        Topology::Bounded.count_numbers(
+            Shape::Square,
+            Neighbourhood::Touching,
+            &mut cells,
+            width,
+            height,
+        );
+        Board::from_cells(width, height, cells)
+    }
+
+    /// Creates a board with fixed cells that are restored on reset.
+    ///
+    /// Flags can step up to the most mines any cell holds.
+    fn from_cells(width: usize, height: usize, cells: Vec<CellBox>) -> Result<Board> {
+        let mines = cells.iter().map(|cell| cell.kind.mines()).sum();
+        let mut board = Board::new_with_config(GameConfig::fixed(width, height, mines)?);
+        board.settings.mines_per_cell = cells
+            .iter()
+            .map(|cell| cell.kind.mines() as u8)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        board.grid = Grid::Dense {
+            width,
+            height,
+            cells: cells.clone(),
+        };
+        board.initial_grid = Some(cells);
+        board.mines_placed = true;
+        board.tally();
+        Ok(board)
+    }
+
+    /// Reads a board layout from a file.
+    pub fn load_layout(path: &Path) -> Result<Board> {
+        let text = fs::read_to_string(path).map_err(|source| Error::Persistence {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Board::from_layout(&text)
+    }
+
+    /// Returns the board, including mines and cell states, as a plain-text layout.
+    pub fn to_layout(&self) -> String {
+        let mut text = String::new();
+        if self.settings.topology != Topology::Bounded {
+            text += &format!("topology: {}\n", self.settings.topology.key());
+        }
+        if self.settings.shape != Shape::Square {
+            text += &format!("shape: {}\n", self.settings.shape.key());
+        }
+        if self.settings.neighbourhood != Neighbourhood::Touching {
+            text += &format!("neighbourhood: {}\n", self.settings.neighbourhood.key());
+        }
+        text + &layout::format(self.width, &self.layout_cells())
+    }
+
+    /// Changes the topology, recounting the numbers of a board whose mines are placed.
+    ///
+    /// Mines placed later are counted with the new topology anyway.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.settings.topology = topology;
+        self.recount();
+    }
+
+    /// Changes the cell shape, recounting the numbers of a board whose mines are placed.
+    pub fn set_shape(&mut self, shape: Shape) {
+        self.settings.shape = shape;
+        self.recount();
+    }
+
+    /// Changes the neighbourhood numbers count over, recounting a board whose mines are placed.
+    pub fn set_neighbourhood(&mut self, neighbourhood: Neighbourhood) {
+        self.settings.neighbourhood = neighbourhood;
+        self.recount();
+    }
+
+    /// Recounts the numbers of a fixed-size board after its neighbourhoods change.
+    fn recount(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let Settings {
+            topology,
+            shape,
+            neighbourhood,
+            ..
+        } = self.settings;
+        if let Grid::Dense { cells, .. } = &mut self.grid
+            && self.mines_placed
+        {
+            topology.count_numbers(shape, neighbourhood, cells, width, height);
+        }
+        if let Some(initial_grid) = &mut self.initial_grid {
+            topology.count_numbers(shape, neighbourhood, initial_grid, width, height);
         }
     }
 
     /// Initializes the board, placing mines and calculating numbers.
     ///
-    /// Ensures the first click is never a mine.
+    /// The cells kept free of mines depend on the `first_click` setting. If the
+    /// board is too dense to keep them all free, only the clicked cell is kept free.
+    /// When cells can hold several mines, each free cell offers that many places
+    /// and the mines are spread over the places.
+    /// Mine placement is derived from the board seed, so the same seed, policy and
+    /// first click give the same board.
     pub fn initialize_board(&mut self, initial_click_x: isize, initial_click_y: isize) {
-        let mut rng = rand::rng();
-        let mut set_index = (0..(self.width * self.height)).collect::<Vec<usize>>();
-        set_index.shuffle(&mut rng);
-        for i in 0..self.mines_to_place {
-            let mut idx = set_index[i];
-            if (idx % self.width) as isize == initial_click_x
-                && (idx as isize - initial_click_x) / self.width as isize == initial_click_y
-            {
-                // if the random index is the same as the first clicked cell
-                // pick the next index in the shuffled list this will always be valid
-                idx = set_index[self.mines_to_place];
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let clicked = (initial_click_x, initial_click_y);
+        let protected = match self.settings.first_click {
+            FirstClick::Unprotected => Vec::new(),
+            FirstClick::SafeCell => vec![clicked],
+            FirstClick::Opening => {
+                let mut cells = self.neighbours(initial_click_x, initial_click_y);
+                cells.push(clicked);
+                cells
             }
-            self.grid[idx].kind = CellKind::Mine;
+        };
+        let width = self.width;
+        let free_cells = |protected: &[(isize, isize)]| {
+            (0..(self.width * self.height))
+                .filter(|&idx| {
+                    !protected.contains(&((idx % width) as isize, (idx / width) as isize))
+                })
+                .collect::<Vec<usize>>()
+        };
+        let per_cell = self.settings.mines_per_cell.max(1) as usize;
+        let mut set_index = free_cells(&protected);
+        if set_index.len() * per_cell < self.config.mines() {
+            set_index = free_cells(&[clicked]);
+        }
+        // One place per mine a cell can hold; with one mine per cell these are just the cells
+        let mut places: Vec<usize> = set_index
+            .into_iter()
+            .flat_map(|idx| std::iter::repeat_n(idx, per_cell))
+            .collect();
+        places.shuffle(&mut rng);
+        for &idx in places.iter().take(self.config.mines()) {
             let x = (idx % self.width) as isize;
-            let y = (idx as isize - x) / self.width as isize;
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    if let Some(neighbor) = self.get_cell_mut(x + dx, y + dy) {
-                        if let CellKind::Number(ref mut n) = neighbor.kind {
-                            *n += 1;
-                        }
-                    }
+            let y = (idx / self.width) as isize;
+            if let Some(cell) = self.get_cell_mut(x, y) {
+                cell.kind = CellKind::Mine(cell.kind.mines() as u8 + 1);
+            }
+            for (nx, ny) in self.neighbours(x, y) {
+                if let Some(neighbor) = self.get_cell_mut(nx, ny)
+                    && let CellKind::Number(ref mut n) = neighbor.kind
+                {
+                    *n += 1;
                 }
             }
         }
+        self.lies = self.choose_lies(&mut rng);
+        self.mines_placed = true;
+        self.tally();
         self.timer.start();
     }
 
+    /// Picks the numbers that lie under the `noise` setting, and what they show.
+    ///
+    /// Empty cells never lie, and a lie never shows as empty or as more mines than
+    /// the cell's neighbours can hold.
+    fn choose_lies(&self, rng: &mut StdRng) -> HashMap<(isize, isize), u8> {
+        let candidates = |xs: std::ops::Range<usize>, ys: std::ops::Range<usize>| {
+            ys.flat_map(move |y| xs.clone().map(move |x| (x as isize, y as isize)))
+                .filter(|&(x, y)| {
+                    self.get_cell(x, y)
+                        .is_some_and(|cell| matches!(cell.kind, CellKind::Number(n) if n > 0))
+                })
+                .collect::<Vec<_>>()
+        };
+        let lying = match self.settings.noise {
+            Noise::Off => Vec::new(),
+            Noise::Noisy(percent) => candidates(0..self.width, 0..self.height)
+                .into_iter()
+                .filter(|_| rng.random_range(0..100) < percent)
+                .collect(),
+            Noise::OnePerRegion => {
+                let mut lying = Vec::new();
+                for top in (0..self.height).step_by(NOISE_REGION) {
+                    for left in (0..self.width).step_by(NOISE_REGION) {
+                        let region = candidates(
+                            left..(left + NOISE_REGION).min(self.width),
+                            top..(top + NOISE_REGION).min(self.height),
+                        );
+                        lying.extend(region.choose(rng));
+                    }
+                }
+                lying
+            }
+        };
+        let per_cell = self.settings.mines_per_cell.max(1) as usize;
+        lying
+            .into_iter()
+            .filter_map(|(x, y)| {
+                let CellKind::Number(n) = self.get_cell(x, y)?.kind else {
+                    return None;
+                };
+                let most = self.neighbours(x, y).len() * per_cell;
+                let up = (n as usize) < most && (n == 1 || rng.random_bool(0.5));
+                Some(((x, y), if up { n + 1 } else { n - 1 }))
+            })
+            .collect()
+    }
+
     /// Resets the board to its initial state.
+    ///
+    /// Boards loaded from a layout go back to that layout and daily boards keep
+    /// their seed; others, including endless boards, get a new seed.
     pub fn reset(&mut self) {
-        self.grid.iter_mut().for_each(|cell| {
+        if self.mode == GameMode::Endless {
+            *self = Board::new_endless(rand::random(), self.settings);
+            return;
+        }
+        if let GameMode::Daily { date, official } = self.mode {
+            *self = daily::new_board(date, official, self.settings);
+            return;
+        }
+        self.recording.clear();
+        self.lies.clear();
+        self.loss_reason = None;
+        self.mines_hit = 0;
+        if let Some(initial_grid) = &self.initial_grid {
+            self.grid = Grid::Dense {
+                width: self.width,
+                height: self.height,
+                cells: initial_grid.clone(),
+            };
+            self.tally();
+            self.timer.reset();
+            return;
+        }
+        self.grid.cells_mut().for_each(|cell| {
             cell.kind = CellKind::Number(0);
             cell.state = CellState::Hidden;
         });
+        self.revealed = 0;
+        self.flags = 0;
+        self.safe_cells = (self.width * self.height).saturating_sub(self.config.mines());
         self.mines_placed = false;
+        self.seed = rand::random();
         self.timer.reset();
     }
 
     /// Gets a reference to a cell at (x, y).
+    ///
+    /// On endless boards every cell exists; cells that haven't been generated yet read as hidden.
     pub fn get_cell(&self, x: isize, y: isize) -> Option<&CellBox> {
-        if x >= 0 && x < self.width as isize && y >= 0 && y < self.height as isize {
-            let index = y * self.width as isize + x;
-            Some(&self.grid[index as usize])
-        } else {
-            None
-        }
+        self.grid.get(x, y)
     }
 
     /// Gets a mutable reference to a cell at (x, y).
+    ///
+    /// On endless boards this generates the cell's chunk if needed.
     pub fn get_cell_mut(&mut self, x: isize, y: isize) -> Option<&mut CellBox> {
-        if x >= 0 && x < self.width as isize && y >= 0 && y < self.height as isize {
-            let index = y * self.width as isize + x;
-            Some(&mut self.grid[index as usize])
-        } else {
-            None
-        }
+        self.grid.get_mut(x, y)
     }
 
     /// Returns the starting position for rendering the board in the terminal.
-    pub fn get_board_start_pos(&self) -> (u16, u16) {
-        let (cols, rows) = crossterm::terminal::size().expect("Failed to get terminal size");
-        let board_start_x = (cols as i16 - (self.width * 2 + 2) as i16) / 2;
+    pub fn get_board_start_pos(&self) -> Result<(u16, u16)> {
+        let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+        let board_width = self.width * 2 + 2 + self.settings.shape.extra_width() as usize;
+        let board_start_x = (cols as i16 - board_width as i16) / 2;
         let board_start_y = (rows as i16 - self.height as i16) / 2;
-        (board_start_x as u16, board_start_y as u16)
+        Ok((board_start_x as u16, board_start_y as u16))
     }
 
-    /// Checks if the win condition is met.
-    fn check_win_condition(&mut self) -> Option<GameState> {
-        for cell in self.grid.iter() {
-            if cell.kind != CellKind::Mine && cell.state != CellState::Revealed {
-                return None; // Found a non-mine cell that is not revealed
+    /// Recounts the revealed cells, flags and safe cells from the grid, after
+    /// cells change wholesale.
+    fn tally(&mut self) {
+        let (mut revealed, mut flags, mut safe_cells) = (0, 0, 0);
+        for cell in self.grid.cells() {
+            flags += cell.state.flags();
+            if !cell.kind.is_mine() {
+                safe_cells += 1;
+                if cell.state == CellState::Revealed {
+                    revealed += 1;
+                }
             }
         }
+        (self.revealed, self.flags, self.safe_cells) = (revealed, flags, safe_cells);
+    }
+
+    /// Checks if the win condition is met, stopping the timer if it is.
+    ///
+    /// Endless boards can't be won.
+    pub fn check_win_condition(&mut self) -> Option<GameState> {
+        if self.grid.is_unbounded() || self.revealed < self.safe_cells {
+            return None;
+        }
         self.timer.stop();
         Some(GameState::Won) // All non-mine cells are revealed
     }
@@ -144,71 +469,88 @@ impl Board {
     /// Returns Some(GameState) if the game state changes (win/loss).
     pub fn reveal_adjacent_empty(&mut self, x: isize, y: isize) -> Option<GameState> {
         let mut to_reveal = vec![(x, y)];
+        // Empty cells revealed here are all connected, so they form at most one opening
+        let mut opened = false;
         while let Some((cx, cy)) = to_reveal.pop() {
             if let Some(cell) = self.get_cell_mut(cx, cy) {
+                let kind = cell.kind;
                 match cell.state {
-                    CellState::Revealed | CellState::Flagged => continue, // Skip if already revealed or flagged
-                    CellState::Hidden => {
+                    CellState::Revealed | CellState::Flagged(_) => continue, // Skip if already revealed or flagged
+                    CellState::Hidden | CellState::Question => {
                         cell.state = CellState::Revealed;
-                        if cell.kind == CellKind::Mine {
+                        if kind.is_mine() {
+                            // Every mine in the cell costs a life
+                            self.mines_hit += kind.mines();
+                            // With lives left the mine stays revealed, marking it as found
+                            if self.get_lives_left() > 0 {
+                                continue;
+                            }
                             self.timer.stop();
+                            self.loss_reason = Some(LossReason::Mine);
                             return Some(GameState::Lost); // Stop if it's a mine
                         }
+                        self.revealed += 1;
                     }
                 }
-                if cell.kind != CellKind::Number(0) {
+                if kind != CellKind::Number(0) {
                     continue; // Stop if it's not an empty cell
                 }
-                for dy in -1..=1 {
-                    for dx in -1..=1 {
-                        if dx == 0 && dy == 0 {
-                            continue;
-                        }
-                        if let Some(neighbor) = self.get_cell_mut(cx + dx, cy + dy) {
-                            match neighbor.kind {
-                                CellKind::Number(_) => {
-                                    to_reveal.push((cx + dx, cy + dy));
-                                }
-                                _ => (), // Do nothing for mines
-                            }
-                        }
+                opened = true;
+                for (nx, ny) in self.neighbours(cx, cy) {
+                    if let Some(neighbor) = self.get_cell_mut(nx, ny)
+                        && let CellKind::Number(_) = neighbor.kind
+                    {
+                        to_reveal.push((nx, ny));
                     }
                 }
             }
         }
-        return self.check_win_condition();
+        if opened {
+            let bonus = Duration::from_secs(self.settings.time_bonus.into());
+            self.timer.extend_deadline(bonus);
+        }
+        self.check_win_condition()
     }
 
     /// Reveals all non-flagged adjacent cells around a given cell.
     ///
+    /// Cells marked with a "?" are also skipped when `question_blocks_chord` is set.
     /// Returns Some(GameState) if the game state changes (win/loss).
     pub fn reveal_non_flagged(&mut self, x: isize, y: isize) -> Option<GameState> {
+        let question_blocks = self.settings.question_blocks_chord;
         let mut ret = None;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                if let Some(neighbor) = self.get_cell_mut(x + dx, y + dy) {
-                    if neighbor.state != CellState::Flagged {
-                        ret = self.reveal_adjacent_empty(x + dx, y + dy);
-                        if ret.is_some() {
-                            return ret;
-                        }
-                    }
+        for (nx, ny) in self.neighbours(x, y) {
+            if let Some(neighbor) = self.get_cell_mut(nx, ny)
+                && neighbor.state.flags() == 0
+                && !(question_blocks && neighbor.state == CellState::Question)
+            {
+                ret = self.reveal_adjacent_empty(nx, ny);
+                if ret.is_some() {
+                    return ret;
                 }
             }
         }
         if ret.is_none() {
             ret = self.check_win_condition();
         }
-        return ret;
+        ret
     }
 
     /// Reveals all mines on the board.
+    ///
+    /// On endless boards, that is the mines generated so far and those in view.
     pub fn reveal_all_mines(&mut self) {
-        for cell in self.grid.iter_mut() {
-            if cell.kind == CellKind::Mine {
+        if self.grid.is_unbounded() {
+            let (left, top) = self.viewport;
+            for y in top..top + self.height as isize {
+                for x in left..left + self.width as isize {
+                    self.get_cell_mut(x, y);
+                }
+            }
+        }
+        for cell in self.grid.cells_mut() {
+            if cell.kind.is_mine() {
+                self.flags -= cell.state.flags();
                 cell.state = CellState::Revealed;
             }
         }
@@ -216,97 +558,560 @@ impl Board {
 
     /// Converts mouse event coordinates to board cell coordinates.
     fn cell_coords_from_mouse(&self, event: event::MouseEvent) -> Option<(isize, isize)> {
-        let (board_start_x, board_start_y) = self.get_board_start_pos();
+        let (board_start_x, board_start_y) = self.get_board_start_pos().ok()?;
 
-        let cell_x = (event.column as isize - board_start_x as isize - 2) / 2;
         let cell_y = event.row as isize - board_start_y as isize - 1;
+        // Shifted hex rows start a column further right
+        let indent = self.settings.shape.indent(cell_y + self.viewport.1) as isize;
+        let cell_x = (event.column as isize - board_start_x as isize - 2 - indent).div_euclid(2);
 
         if cell_x >= 0
             && cell_x < self.width as isize
             && cell_y >= 0
             && cell_y < self.height as isize
         {
-            Some((cell_x, cell_y))
+            Some((cell_x + self.viewport.0, cell_y + self.viewport.1))
         } else {
             None // Click was outside the board
         }
     }
 
+    /// Tracks the cell under the mouse pointer for the status line.
+    pub fn handle_mouse_move(&mut self, event: event::MouseEvent) {
+        self.cursor = self.cell_coords_from_mouse(event);
+    }
+
     /// Handles left mouse click event for revealing cells.
     ///
     /// Returns Some(GameState) if the game state changes (win/loss).
     pub fn handle_mouse_left(&mut self, event: event::MouseEvent) -> Option<GameState> {
-        if let Some((cell_x, cell_y)) = self.cell_coords_from_mouse(event) {
-            if self.mines_placed == false {
-                self.initialize_board(cell_x, cell_y);
-                self.mines_placed = true;
+        self.left_held = true;
+        let (x, y) = self.cell_coords_from_mouse(event)?;
+        let action = match self.get_cell(x, y)?.state {
+            _ if self.settings.click_model == ClickModel::LeftRight && self.right_held => {
+                Action::Chord { x, y }
             }
-            if let Some(cell) = self.get_cell_mut(cell_x, cell_y) {
-                match cell.state {
-                    CellState::Hidden => {
-                        // Only reveal if the cell is hidden
-                        if let Some(game_state_change) = self.reveal_adjacent_empty(cell_x, cell_y)
-                        {
-                            return Some(game_state_change);
-                        };
-                    }
-                    CellState::Revealed => {
-                        // If already revealed, reveal adjacent non-flagged cells if it's a number
-                        if let CellKind::Number(n) = cell.kind {
-                            if n > 0 {
-                                if let Some(game_state_change) =
-                                    self.reveal_non_flagged(cell_x, cell_y)
-                                {
-                                    return Some(game_state_change);
-                                };
-                            }
-                        }
-                    }
-                    _ => (), // Do nothing if it's already revealed or flagged
-                };
+            CellState::Revealed if self.settings.click_model == ClickModel::Classic => {
+                Action::Chord { x, y }
             }
+            _ => Action::Reveal { x, y },
+        };
+        self.apply_action(action)
+    }
+
+    /// Handles right mouse click event for flagging/unflagging cells.
+    ///
+    /// Returns Some(GameState) if the game state changes (win/loss), which can
+    /// only happen through a left + right chord.
+    pub fn handle_mouse_right(&mut self, event: event::MouseEvent) -> Option<GameState> {
+        self.right_held = true;
+        let (x, y) = self.cell_coords_from_mouse(event)?;
+        let action = match self.get_cell(x, y)?.state {
+            _ if self.settings.click_model == ClickModel::LeftRight && self.left_held => {
+                Action::Chord { x, y }
+            }
+            CellState::Revealed if self.settings.flag_chord => Action::FlagChord { x, y },
+            _ => Action::Flag { x, y },
+        };
+        self.apply_action(action)
+    }
+
+    /// Handles middle mouse click event, which chords in the middle click model.
+    ///
+    /// Returns Some(GameState) if the game state changes (win/loss).
+    pub fn handle_mouse_middle(&mut self, event: event::MouseEvent) -> Option<GameState> {
+        let (x, y) = self.cell_coords_from_mouse(event)?;
+        if self.settings.click_model == ClickModel::MiddleClick {
+            return self.apply_action(Action::Chord { x, y });
         }
         None
     }
 
-    /// Handles right mouse click event for flagging/unflagging cells.
-    pub fn handle_mouse_right(&mut self, event: event::MouseEvent) {
-        if let Some((cell_x, cell_y)) = self.cell_coords_from_mouse(event) {
-            if let Some(cell) = self.get_cell_mut(cell_x, cell_y) {
-                cell.state = match cell.state {
-                    CellState::Hidden => CellState::Flagged,
-                    CellState::Flagged => CellState::Hidden,
-                    _ => cell.state, // Do nothing if it's already revealed
-                };
+    /// Handles mouse button release, used to detect left + right chords.
+    pub fn handle_mouse_up(&mut self, button: event::MouseButton) {
+        match button {
+            event::MouseButton::Left => self.left_held = false,
+            event::MouseButton::Right => self.right_held = false,
+            event::MouseButton::Middle => (),
+        }
+    }
+
+    /// Applies a player action and records it for replays.
+    ///
+    /// The first reveal places the mines and starts the timer. Actions outside
+    /// the board are ignored. Returns Some(GameState) if the game state changes (win/loss).
+    pub fn apply_action(&mut self, action: Action) -> Option<GameState> {
+        let (Action::Reveal { x, y }
+        | Action::Flag { x, y }
+        | Action::Chord { x, y }
+        | Action::FlagChord { x, y }) = action;
+        let state = self.get_cell(x, y)?.state;
+        if let Some(state) = self.check_deadline() {
+            return Some(state);
+        }
+        // Flag chords are recorded as the flags they place
+        if !matches!(action, Action::FlagChord { .. }) {
+            self.record(action);
+        }
+        if self.grid.is_unbounded() {
+            self.follow(x, y);
+        }
+        match action {
+            Action::Reveal { .. } => {
+                if !self.timer.is_running() {
+                    let limit = self.settings.time_limit;
+                    self.timer
+                        .set_deadline(limit.map(|secs| Duration::from_secs(secs.into())));
+                }
+                if !self.mines_placed {
+                    self.initialize_board(x, y);
+                }
+                // Fixed boards skip initialize_board, so start the timer here too
+                self.timer.start();
+                match state {
+                    // Only reveal if the cell is hidden
+                    CellState::Hidden | CellState::Question => self.reveal_adjacent_empty(x, y),
+                    _ => None,
+                }
+            }
+            Action::Flag { .. } => {
+                self.toggle_flag(x, y);
+                None
+            }
+            Action::Chord { .. } => self.chord(x, y),
+            Action::FlagChord { .. } => {
+                self.flag_chord(x, y);
+                None
+            }
+        }
+    }
+
+    /// Scrolls the view of an endless board by (dx, dy) cells. Fixed-size boards don't scroll.
+    pub fn scroll(&mut self, dx: isize, dy: isize) {
+        if self.grid.is_unbounded() {
+            self.viewport.0 += dx;
+            self.viewport.1 += dy;
+        }
+    }
+
+    /// Scrolls so that (x, y) is at least [`ENDLESS_MARGIN`] cells inside the view.
+    fn follow(&mut self, x: isize, y: isize) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let (left, top) = &mut self.viewport;
+        *left = (*left).clamp(x + ENDLESS_MARGIN + 1 - width, x - ENDLESS_MARGIN);
+        *top = (*top).clamp(y + ENDLESS_MARGIN + 1 - height, y - ENDLESS_MARGIN);
+    }
+
+    /// Returns the cells of the visible area, row by row.
+    ///
+    /// This is the whole board, except on endless boards.
+    fn layout_cells(&self) -> Vec<CellBox> {
+        match &self.grid {
+            Grid::Dense { cells, .. } => cells.clone(),
+            Grid::Chunked(_) => {
+                let (left, top) = self.viewport;
+                (0..self.height as isize)
+                    .flat_map(|y| (0..self.width as isize).map(move |x| (x, y)))
+                    .filter_map(|(x, y)| self.get_cell(left + x, top + y).copied())
+                    .collect()
             }
         }
     }
 
-    /// Clamps the game configuration values to allowed ranges.
-    pub fn clamp_config(width: usize, height: usize, mines: usize) -> GameConfig {
-        let clamped_width = width.clamp(MIN_WIDTH, MAX_WIDTH);
-        let clamped_height = height.clamp(MIN_HEIGHT, MAX_HEIGHT);
-        let max_mines = clamped_width * clamped_height - 1; // Ensure at least one cell is free
-        let clamped_mines = mines.clamp(MIN_MINES, max_mines);
-        GameConfig {
-            width: clamped_width,
-            height: clamped_height,
-            mines: clamped_mines,
+    /// Ends the game as lost if the time limit has run out.
+    ///
+    /// Returns Some(GameState::Lost) when it does.
+    pub fn check_deadline(&mut self) -> Option<GameState> {
+        if self.timer.is_running() && self.timer.is_expired() {
+            self.timer.stop();
+            self.loss_reason = Some(LossReason::TimeUp);
+            return Some(GameState::Lost);
         }
+        None
     }
 
-    /// Returns the number of remaining mines to be flagged.
+    /// Cycles the mark on an unrevealed cell: Hidden -> Flagged -> (Question ->) Hidden.
+    ///
+    /// When cells can hold several mines, the flag steps through each count first.
+    fn toggle_flag(&mut self, x: isize, y: isize) {
+        let question_marks = self.settings.question_marks;
+        let per_cell = self.settings.mines_per_cell.max(1);
+        if let Some(cell) = self.get_cell_mut(x, y) {
+            let before = cell.state.flags();
+            cell.state = match cell.state {
+                CellState::Hidden => CellState::Flagged(1),
+                CellState::Flagged(flags) if flags < per_cell => CellState::Flagged(flags + 1),
+                CellState::Flagged(_) if question_marks => CellState::Question,
+                CellState::Flagged(_) | CellState::Question => CellState::Hidden,
+                _ => cell.state, // Do nothing if it's already revealed
+            };
+            let after = cell.state.flags();
+            self.flags = self.flags + after - before;
+        }
+    }
+
+    /// Adds an action to the recording, timed from the start of the game.
+    fn record(&mut self, action: Action) {
+        self.recording.push(ReplayEvent {
+            time_ms: self.timer.get_elapsed().as_millis() as u32,
+            action,
+        });
+    }
+
+    /// Returns the neighbours of (x, y) under the board's cell shape, neighbourhood
+    /// and topology.
+    ///
+    /// Endless boards have no edges, so every cell has all its neighbours there.
+    pub fn neighbours(&self, x: isize, y: isize) -> Vec<(isize, isize)> {
+        let Settings {
+            topology,
+            shape,
+            neighbourhood,
+            ..
+        } = self.settings;
+        if self.grid.is_unbounded() {
+            return neighbourhood
+                .offsets(shape, y)
+                .iter()
+                .map(|&(dx, dy)| (x + dx, y + dy))
+                .collect();
+        }
+        topology.neighbours(shape, neighbourhood, x, y, self.width, self.height)
+    }
+
+    /// Counts the neighbours of (x, y) that are in the given state.
+    fn count_neighbours(&self, x: isize, y: isize, state: CellState) -> usize {
+        self.neighbours(x, y)
+            .into_iter()
+            .filter(|&(nx, ny)| {
+                self.get_cell(nx, ny)
+                    .is_some_and(|cell| cell.state == state)
+            })
+            .count()
+    }
+
+    /// Counts the mines around (x, y) known to the player: flags and mines hit
+    /// in lives mode.
+    fn count_marked_neighbours(&self, x: isize, y: isize) -> usize {
+        self.neighbours(x, y)
+            .into_iter()
+            .filter_map(|(nx, ny)| self.get_cell(nx, ny))
+            .map(|cell| match cell.state {
+                CellState::Revealed => cell.kind.mines(),
+                state => state.flags(),
+            })
+            .sum()
+    }
+
+    /// Chords on a revealed number: reveals its unflagged neighbours, but only
+    /// when the number of adjacent flags (and hit mines) equals the number.
+    ///
+    /// Returns Some(GameState) if the game state changes (win/loss).
+    ///
+    /// The number is the one shown, which may be a lie.
+    pub fn chord(&mut self, x: isize, y: isize) -> Option<GameState> {
+        let n = self.get_shown_number(x, y)?;
+        if n > 0 && self.count_marked_neighbours(x, y) == n as usize {
+            return self.reveal_non_flagged(x, y);
+        }
+        None
+    }
+
+    /// Flags every hidden neighbour of a revealed number when the hidden and
+    /// flagged neighbours together equal the number, so all must be mines.
+    ///
+    /// Cells marked with a "?" count as hidden and get flagged too. When cells can
+    /// hold several mines, the hidden neighbours are flagged only when the number
+    /// can only be reached by each holding the most mines a cell can, or when a
+    /// single hidden neighbour holds all the missing mines. As with
+    /// [`Board::chord`], the number is the one shown.
+    pub fn flag_chord(&mut self, x: isize, y: isize) {
+        let Some(n) = self.get_shown_number(x, y) else {
+            return;
+        };
+        let hidden = self.count_neighbours(x, y, CellState::Hidden)
+            + self.count_neighbours(x, y, CellState::Question);
+        let marked = self.count_marked_neighbours(x, y);
+        let per_cell = self.settings.mines_per_cell.max(1);
+        let flags = match (n as usize).checked_sub(marked) {
+            Some(0) | None => return,
+            Some(missing) if per_cell == 1 && missing == hidden => 1,
+            Some(missing) if missing == hidden * per_cell as usize => per_cell,
+            Some(missing) if hidden == 1 && missing <= per_cell as usize => missing as u8,
+            _ => return,
+        };
+        for (nx, ny) in self.neighbours(x, y) {
+            let Some(neighbor) = self.get_cell_mut(nx, ny) else {
+                continue;
+            };
+            let clicks = match neighbor.state {
+                CellState::Hidden => flags,
+                // "?" only exists when right click cycles through it: "?" -> hidden -> flag
+                CellState::Question => flags + 1,
+                _ => continue,
+            };
+            neighbor.state = CellState::Flagged(flags);
+            self.flags += flags as usize;
+            for _ in 0..clicks {
+                self.record(Action::Flag { x: nx, y: ny });
+            }
+        }
+    }
+
+    /// Returns the number of remaining mines to be flagged, counting mines hit in lives mode as found.
     pub fn get_remaining_mines(&self) -> isize {
-        self.mines_to_place as isize - self.get_flags_count() as isize
+        self.config.mines() as isize - self.get_flags_count() as isize - self.mines_hit as isize
+    }
+
+    /// Returns the number of mines that can still be hit before the game is lost.
+    pub fn get_lives_left(&self) -> usize {
+        (self.settings.lives.max(1) as usize).saturating_sub(self.mines_hit)
+    }
+
+    /// Returns true if the mines were placed at random, rather than loaded from a layout or file.
+    pub fn is_generated(&self) -> bool {
+        self.initial_grid.is_none()
+    }
+
+    /// Returns the number shown on the revealed number at (x, y), which differs
+    /// from the true count when the number lies. None for any other cell.
+    pub fn get_shown_number(&self, x: isize, y: isize) -> Option<u8> {
+        match self.get_cell(x, y)? {
+            CellBox {
+                state: CellState::Revealed,
+                kind: CellKind::Number(n),
+            } => Some(self.lies.get(&(x, y)).copied().unwrap_or(*n)),
+            _ => None,
+        }
+    }
+
+    /// Returns the numbers that lie, as ((x, y), shown number) sorted by position.
+    pub fn get_lies(&self) -> Vec<((usize, usize), u8)> {
+        let mut lies: Vec<_> = self
+            .lies
+            .iter()
+            .map(|(&(x, y), &shown)| ((x as usize, y as usize), shown))
+            .collect();
+        lies.sort();
+        lies
+    }
+
+    /// Makes the given numbers lie, as ((x, y), shown number), for replaying a liar game.
+    pub(crate) fn set_lies(&mut self, lies: &[((usize, usize), u8)]) {
+        self.lies = lies
+            .iter()
+            .map(|&((x, y), shown)| ((x as isize, y as isize), shown))
+            .collect();
+    }
+
+    /// Returns true if the game is over: the board is cleared or the game lost.
+    ///
+    /// Endless games only end by losing.
+    pub fn is_finished(&self) -> bool {
+        self.loss_reason.is_some()
+            || (self.mines_placed
+                && !self.grid.is_unbounded()
+                && self.get_revealed_count() == self.get_safe_cells_count())
+    }
+
+    /// Returns the number of mines flagged, which is the number of flagged cells
+    /// unless cells can hold several mines.
+    pub fn get_flags_count(&self) -> usize {
+        self.flags
+    }
+
+    /// Returns the number of revealed cells that are not mines.
+    pub fn get_revealed_count(&self) -> usize {
+        self.revealed
+    }
+
+    /// Returns the number of cells that do not hold a mine.
+    ///
+    /// Endless boards have no total; this is then the size of the view. When cells
+    /// can hold several mines, the count is only exact once the mines are placed.
+    pub fn get_safe_cells_count(&self) -> usize {
+        if self.mines_placed && !self.grid.is_unbounded() {
+            return self.safe_cells;
+        }
+        (self.width * self.height).saturating_sub(self.config.mines())
+    }
+
+    /// Returns the seed used to place the mines.
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the configuration this board was created with.
+    pub fn get_config(&self) -> GameConfig {
+        self.config
     }
 
-    /// Returns the number of flagged cells.
+    /// Returns the time left to clear the board, or None without a time limit.
     ///
-    /// Could be cached for performance.
-    fn get_flags_count(&self) -> usize {
-        self.grid
+    /// Before the first click this is the full limit.
+    pub fn get_time_left(&self) -> Option<Duration> {
+        self.timer.get_remaining().or_else(|| {
+            let limit = self.settings.time_limit?;
+            Some(Duration::from_secs(limit.into()))
+        })
+    }
+
+    /// Returns why the game was lost, if it was.
+    pub fn get_loss_reason(&self) -> Option<LossReason> {
+        self.loss_reason
+    }
+
+    /// Returns the names of the active gameplay modifiers.
+    pub fn get_modifiers(&self) -> Vec<&'static str> {
+        let mut modifiers = Vec::new();
+        match self.mode {
+            GameMode::Classic | GameMode::Endless => {}
+            GameMode::Daily { official: true, .. } => modifiers.push("daily"),
+            GameMode::Daily {
+                official: false, ..
+            } => modifiers.push("daily practice"),
+        }
+        match self.settings.first_click {
+            FirstClick::SafeCell => {}
+            FirstClick::Opening => modifiers.push("opening"),
+            FirstClick::Unprotected => modifiers.push("no protection"),
+        }
+        if self.settings.time_limit.is_some() {
+            modifiers.push("time attack");
+        }
+        if self.settings.lives > 1 {
+            modifiers.push("lives");
+        }
+        if self.settings.topology == Topology::Torus {
+            modifiers.push("torus");
+        }
+        if self.settings.shape == Shape::Hex {
+            // Hex boards always count their touching cells
+            modifiers.push("hex");
+        } else if self.settings.neighbourhood != Neighbourhood::Touching {
+            modifiers.push(self.settings.neighbourhood.key());
+        }
+        if self.settings.mines_per_cell > 1 {
+            modifiers.push("multi-mine");
+        }
+        if self.settings.noise != Noise::Off {
+            modifiers.push("liar");
+        }
+        modifiers
+    }
+
+    /// Returns the (x, y) positions of all mines, row by row.
+    ///
+    /// A cell holding several mines is listed once per mine. On endless boards,
+    /// positions are of the mines in view, relative to its top-left.
+    pub fn get_mine_positions(&self) -> Vec<(usize, usize)> {
+        self.layout_cells()
             .iter()
-            .filter(|cell| cell.state == CellState::Flagged)
-            .count()
+            .enumerate()
+            .flat_map(|(idx, cell)| {
+                std::iter::repeat_n((idx % self.width, idx / self.width), cell.kind.mines())
+            })
+            .collect()
+    }
+
+    /// Returns the 3BV of the board: the minimum number of left clicks needed to
+    /// clear it, counting each opening once and each number not bordering an opening.
+    pub fn get_3bv(&self) -> usize {
+        self.count_3bv().0
+    }
+
+    /// Returns the 3BV solved so far and the board's 3BV, or None before the mines
+    /// are placed and on endless boards.
+    ///
+    /// An opening is solved once it is open, and a number not bordering one once
+    /// it is revealed.
+    pub fn get_3bv_progress(&self) -> Option<(usize, usize)> {
+        if !self.mines_placed || self.grid.is_unbounded() {
+            return None;
+        }
+        let (total, solved) = self.count_3bv();
+        Some((solved, total))
+    }
+
+    /// Returns the 3BV of the visible area and how much of it is solved.
+    fn count_3bv(&self) -> (usize, usize) {
+        let cells = self.layout_cells();
+        let Settings {
+            topology,
+            shape,
+            neighbourhood,
+            ..
+        } = self.settings;
+        let mut seen = vec![false; cells.len()];
+        let mut clicks = 0;
+        let mut solved = 0;
+        // Each opening, with the numbers around it, takes one click
+        for idx in 0..cells.len() {
+            if seen[idx] || cells[idx].kind != CellKind::Number(0) {
+                continue;
+            }
+            clicks += 1;
+            let mut open = false;
+            let mut stack = vec![idx];
+            seen[idx] = true;
+            while let Some(current) = stack.pop() {
+                if cells[current].kind != CellKind::Number(0) {
+                    continue;
+                }
+                open |= cells[current].state == CellState::Revealed;
+                let x = (current % self.width) as isize;
+                let y = (current / self.width) as isize;
+                // The visible area is bounded even on endless boards
+                for (nx, ny) in
+                    topology.neighbours(shape, neighbourhood, x, y, self.width, self.height)
+                {
+                    let neighbor = ny as usize * self.width + nx as usize;
+                    if !seen[neighbor] {
+                        seen[neighbor] = true;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            solved += open as usize;
+        }
+        // Every remaining number takes its own click
+        for (cell, &seen) in cells.iter().zip(&seen) {
+            if seen || cell.kind.is_mine() {
+                continue;
+            }
+            clicks += 1;
+            solved += (cell.state == CellState::Revealed) as usize;
+        }
+        (clicks, solved)
+    }
+
+    /// Returns the actions taken so far as a replay, or None before the mines are
+    /// placed and for endless boards.
+    pub fn get_replay(&self) -> Option<Replay> {
+        if !self.mines_placed || self.grid.is_unbounded() {
+            return None;
+        }
+        Some(Replay {
+            width: self.width,
+            height: self.height,
+            mines: self.get_mine_positions(),
+            topology: self.settings.topology,
+            shape: self.settings.shape,
+            neighbourhood: self.settings.neighbourhood,
+            mines_per_cell: self.settings.mines_per_cell,
+            lies: self.get_lies(),
+            question_marks: self.settings.question_marks,
+            completed: self.get_revealed_count() == self.get_safe_cells_count(),
+            bbbv: Some(self.get_3bv()),
+            events: self.recording.clone(),
+        })
+    }
+
+    /// Returns the difficulty name matching this board's configuration.
+    pub fn get_difficulty_name(&self) -> &'static str {
+        if self.mode == GameMode::Endless {
+            return "Endless";
+        }
+        Preset::from_config(self.get_config()).map_or("Custom", |preset| preset.name())
     }
 }
@@ -0,0 +1,212 @@
+//! Cell storage behind [`Board::get_cell`](crate::game_logic::Board::get_cell).
+//!
+//! Regular boards keep their cells in one dense row-major `Vec`. Endless boards
+//! live on an unbounded plane split into square chunks, which are generated from
+//! the board seed the first time a cell in them is changed.
+use crate::types::{CellBox, CellKind, CellState};
+use rand::prelude::*;
+use std::collections::HashMap;
+
+/// Width and height of an endless board chunk, in cells.
+pub const CHUNK_SIZE: usize = 16;
+
+/// Cells in one chunk, row by row.
+type Chunk = [CellBox; CHUNK_SIZE * CHUNK_SIZE];
+
+/// What cells in chunks that haven't been generated look like.
+const UNEXPLORED: CellBox = CellBox {
+    kind: CellKind::Number(0),
+    state: CellState::Hidden,
+};
+
+/// Storage for the cells of a board.
+#[derive(Clone, Debug)]
+pub enum Grid {
+    /// A fixed-size board stored row by row.
+    Dense {
+        width: usize,
+        height: usize,
+        cells: Vec<CellBox>,
+    },
+    /// An unbounded plane, generated chunk by chunk.
+    Chunked(ChunkedGrid),
+}
+
+impl Grid {
+    /// Creates a dense grid of hidden, empty cells.
+    pub fn dense(width: usize, height: usize) -> Grid {
+        Grid::Dense {
+            width,
+            height,
+            cells: vec![UNEXPLORED; width * height],
+        }
+    }
+
+    /// Gets a reference to the cell at (x, y), or None outside a dense grid.
+    pub fn get(&self, x: isize, y: isize) -> Option<&CellBox> {
+        match self {
+            Grid::Dense {
+                width,
+                height,
+                cells,
+            } => dense_index(*width, *height, x, y).map(|index| &cells[index]),
+            Grid::Chunked(chunked) => Some(chunked.get(x, y)),
+        }
+    }
+
+    /// Gets a mutable reference to the cell at (x, y), or None outside a dense grid.
+    pub fn get_mut(&mut self, x: isize, y: isize) -> Option<&mut CellBox> {
+        match self {
+            Grid::Dense {
+                width,
+                height,
+                cells,
+            } => dense_index(*width, *height, x, y).map(|index| &mut cells[index]),
+            Grid::Chunked(chunked) => Some(chunked.get_mut(x, y)),
+        }
+    }
+
+    /// Iterates over the stored cells: all of them for a dense grid, the generated ones otherwise.
+    pub fn cells(&self) -> Box<dyn Iterator<Item = &CellBox> + '_> {
+        match self {
+            Grid::Dense { cells, .. } => Box::new(cells.iter()),
+            Grid::Chunked(chunked) => {
+                Box::new(chunked.chunks.values().flat_map(|chunk| chunk.iter()))
+            }
+        }
+    }
+
+    /// Iterates mutably over the stored cells, see [`Grid::cells`].
+    pub fn cells_mut(&mut self) -> Box<dyn Iterator<Item = &mut CellBox> + '_> {
+        match self {
+            Grid::Dense { cells, .. } => Box::new(cells.iter_mut()),
+            Grid::Chunked(chunked) => Box::new(
+                chunked
+                    .chunks
+                    .values_mut()
+                    .flat_map(|chunk| chunk.iter_mut()),
+            ),
+        }
+    }
+
+    /// Returns true for an endless, chunked grid.
+    pub fn is_unbounded(&self) -> bool {
+        matches!(self, Grid::Chunked(_))
+    }
+}
+
+fn dense_index(width: usize, height: usize, x: isize, y: isize) -> Option<usize> {
+    if x >= 0 && x < width as isize && y >= 0 && y < height as isize {
+        Some(y as usize * width + x as usize)
+    } else {
+        None
+    }
+}
+
+/// Sparse storage for an endless board.
+///
+/// Mines in a chunk depend only on the seed and the chunk position, so numbers
+/// on chunk borders can be worked out without generating the neighbours.
+#[derive(Clone, Debug)]
+pub struct ChunkedGrid {
+    seed: u64,
+    mines_per_chunk: usize,
+    chunks: HashMap<(isize, isize), Box<Chunk>>,
+}
+
+impl ChunkedGrid {
+    /// Creates an empty plane with `mines_per_chunk` mines in every chunk.
+    ///
+    /// The cells around (0, 0) never hold a mine, so the game can start there with an opening.
+    pub fn new(seed: u64, mines_per_chunk: usize) -> ChunkedGrid {
+        ChunkedGrid {
+            seed,
+            mines_per_chunk: mines_per_chunk.min(CHUNK_SIZE * CHUNK_SIZE),
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of chunks generated so far.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    fn get(&self, x: isize, y: isize) -> &CellBox {
+        let (chunk, index) = split(x, y);
+        self.chunks
+            .get(&chunk)
+            .map_or(&UNEXPLORED, |cells| &cells[index])
+    }
+
+    fn get_mut(&mut self, x: isize, y: isize) -> &mut CellBox {
+        let (chunk, index) = split(x, y);
+        if !self.chunks.contains_key(&chunk) {
+            let cells = self.generate(chunk);
+            self.chunks.insert(chunk, cells);
+        }
+        &mut self
+            .chunks
+            .get_mut(&chunk)
+            .expect("chunk was just generated")[index]
+    }
+
+    /// Returns which cells of a chunk hold mines.
+    fn mines(&self, (chunk_x, chunk_y): (isize, isize)) -> [bool; CHUNK_SIZE * CHUNK_SIZE] {
+        // Mix the chunk position into the seed so neighbouring chunks differ
+        let key = (chunk_x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (chunk_y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        let mut rng = StdRng::seed_from_u64(self.seed ^ key);
+        let mut mines = [false; CHUNK_SIZE * CHUNK_SIZE];
+        for index in rand::seq::index::sample(&mut rng, mines.len(), self.mines_per_chunk) {
+            let x = chunk_x * CHUNK_SIZE as isize + (index % CHUNK_SIZE) as isize;
+            let y = chunk_y * CHUNK_SIZE as isize + (index / CHUNK_SIZE) as isize;
+            // Keep the start free
+            if x.abs() > 1 || y.abs() > 1 {
+                mines[index] = true;
+            }
+        }
+        mines
+    }
+
+    /// Builds the cells of a chunk, with numbers counting mines in neighbouring chunks too.
+    fn generate(&self, (chunk_x, chunk_y): (isize, isize)) -> Box<Chunk> {
+        let mut around = HashMap::new();
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let chunk = (chunk_x + dx, chunk_y + dy);
+                around.insert(chunk, self.mines(chunk));
+            }
+        }
+        let is_mine = |x: isize, y: isize| {
+            let (chunk, index) = split(x, y);
+            around[&chunk][index]
+        };
+        let mut cells = Box::new([UNEXPLORED; CHUNK_SIZE * CHUNK_SIZE]);
+        for (index, cell) in cells.iter_mut().enumerate() {
+            let x = chunk_x * CHUNK_SIZE as isize + (index % CHUNK_SIZE) as isize;
+            let y = chunk_y * CHUNK_SIZE as isize + (index / CHUNK_SIZE) as isize;
+            cell.kind = if is_mine(x, y) {
+                CellKind::Mine(1)
+            } else {
+                let mut count = 0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        if (dx != 0 || dy != 0) && is_mine(x + dx, y + dy) {
+                            count += 1;
+                        }
+                    }
+                }
+                CellKind::Number(count)
+            };
+        }
+        cells
+    }
+}
+
+/// Splits plane coordinates into a chunk position and the index within the chunk.
+fn split(x: isize, y: isize) -> ((isize, isize), usize) {
+    let size = CHUNK_SIZE as isize;
+    let chunk = (x.div_euclid(size), y.div_euclid(size));
+    let index = y.rem_euclid(size) as usize * CHUNK_SIZE + x.rem_euclid(size) as usize;
+    (chunk, index)
+}
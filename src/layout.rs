@@ -0,0 +1,296 @@
+//! Plain-text board layouts.
+//!
+//! A layout has one line per board row and one character per cell. Blank lines
+//! and lines starting with `#` are ignored, and all rows must have the same width.
+//! A `topology: torus` line makes opposite edges neighbours, see [`Topology`],
+//! a `shape: hex` line makes the cells hexagons in offset rows, see [`Shape`], and
+//! a `neighbourhood: <name>` line changes the cells numbers count, see [`Neighbourhood`].
+//! Cells holding more than one mine, up to [`MAX_MINES_PER_CELL`], are listed as
+//! `x,y=count` pairs (0-based) on a `mines:` line, and flags marking more than one
+//! mine likewise on a `flags:` line. At least one cell must be free of mines.
+//!
+//! | Char      | Cell                                   |
+//! |-----------|----------------------------------------|
+//! | `.`       | hidden, safe                           |
+//! | `*`       | hidden, mine                           |
+//! | `_`, `0-9`| revealed, safe (digits must match)     |
+//! | `X`       | revealed mine                          |
+//! | `F` / `f` | flagged mine / flagged safe cell       |
+//! | `Q` / `q` | "?" over a mine / "?" over a safe cell |
+//!
+//! ```text
+//! # 5x3 board with one flag
+//! ..*..
+//! .__F.
+//! .....
+//! ```
+//!
+//! ```text
+//! # The top mine is a pair
+//! mines: 2,0=2
+//! ..*..
+//! .2_*.
+//! .....
+//! ```
+use crate::shape::{Neighbourhood, Shape};
+use crate::topology::Topology;
+use crate::types::{CellBox, CellKind, CellState, MAX_HEIGHT, MAX_MINES_PER_CELL, MAX_WIDTH};
+use crate::{Error, Result};
+
+/// A parsed layout: cells in row-major order, with numbers already counted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<CellBox>,
+    pub topology: Topology,
+    pub shape: Shape,
+    pub neighbourhood: Neighbourhood,
+}
+
+/// Parses a layout from text.
+///
+/// Errors carry the 1-based line and column of the offending character.
+pub fn parse(text: &str) -> Result<Layout> {
+    let mut width = 0;
+    let mut topology = Topology::Bounded;
+    let mut shape = Shape::Square;
+    let mut neighbourhood = Neighbourhood::Touching;
+    let mut rows = Vec::new();
+    // (line, column, digit) of revealed digits, checked once numbers are counted
+    let mut digits = Vec::new();
+    // (line, column, option, x, y, count) of cells holding or flagged with several mines
+    let mut stacks = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // Rows never contain ':', so any line that does is a `name: value` option
+        if let Some((name, value)) = line.split_once(':') {
+            let value = value.trim();
+            let unknown = |what: &str| {
+                parse_error(
+                    line_number,
+                    line.len() - value.len() + 1,
+                    format!("unknown {} '{}'", what, value),
+                )
+            };
+            match name {
+                "topology" => {
+                    topology = Topology::from_key(value).ok_or_else(|| unknown("topology"))?
+                }
+                "shape" => shape = Shape::from_key(value).ok_or_else(|| unknown("shape"))?,
+                "neighbourhood" => {
+                    neighbourhood =
+                        Neighbourhood::from_key(value).ok_or_else(|| unknown("neighbourhood"))?
+                }
+                "mines" | "flags" => {
+                    for pair in value.split_whitespace() {
+                        let column = pair.as_ptr() as usize - line.as_ptr() as usize + 1;
+                        let (x, y, count) = parse_stack(pair).ok_or_else(|| {
+                            parse_error(
+                                line_number,
+                                column,
+                                format!("expected x,y=count, got '{}'", pair),
+                            )
+                        })?;
+                        if name == "mines" && count > MAX_MINES_PER_CELL {
+                            return Err(parse_error(
+                                line_number,
+                                column,
+                                format!("a cell can hold at most {} mines", MAX_MINES_PER_CELL),
+                            ));
+                        }
+                        stacks.push((line_number, column, name, x, y, count));
+                    }
+                }
+                _ => {
+                    return Err(parse_error(
+                        line_number,
+                        1,
+                        format!("unknown option '{}'", name),
+                    ));
+                }
+            }
+            continue;
+        }
+        let mut row = Vec::new();
+        for (column_index, ch) in line.chars().enumerate() {
+            let (kind, state) = match ch {
+                '.' => (CellKind::Number(0), CellState::Hidden),
+                '*' => (CellKind::Mine(1), CellState::Hidden),
+                '_' => (CellKind::Number(0), CellState::Revealed),
+                '0'..='9' => {
+                    digits.push((line_number, column_index + 1, ch as u8 - b'0'));
+                    (CellKind::Number(0), CellState::Revealed)
+                }
+                'X' => (CellKind::Mine(1), CellState::Revealed),
+                'F' => (CellKind::Mine(1), CellState::Flagged(1)),
+                'f' => (CellKind::Number(0), CellState::Flagged(1)),
+                'Q' => (CellKind::Mine(1), CellState::Question),
+                'q' => (CellKind::Number(0), CellState::Question),
+                _ => {
+                    return Err(parse_error(
+                        line_number,
+                        column_index + 1,
+                        format!("unexpected character '{}'", ch),
+                    ));
+                }
+            };
+            row.push(CellBox { kind, state });
+        }
+        if rows.is_empty() {
+            width = row.len();
+            if width > MAX_WIDTH {
+                return Err(parse_error(
+                    line_number,
+                    MAX_WIDTH + 1,
+                    format!("rows can be at most {} cells wide", MAX_WIDTH),
+                ));
+            }
+        } else if row.len() != width {
+            return Err(parse_error(
+                line_number,
+                row.len().min(width) + 1,
+                format!("row has {} cells, expected {}", row.len(), width),
+            ));
+        }
+        if rows.len() == MAX_HEIGHT {
+            return Err(parse_error(
+                line_number,
+                1,
+                format!("layouts can be at most {} rows tall", MAX_HEIGHT),
+            ));
+        }
+        rows.push((line_number, row));
+    }
+    if rows.is_empty() {
+        return Err(parse_error(1, 1, "layout has no rows".to_string()));
+    }
+    let height = rows.len();
+    let line_numbers: Vec<usize> = rows.iter().map(|(line, _)| *line).collect();
+    let mut cells: Vec<CellBox> = rows.into_iter().flat_map(|(_, row)| row).collect();
+    // Where to point at if there are too many mines: the last pair adding some, or the last row
+    let mut last_mines = (line_numbers[height - 1], 1);
+    for (line, column, option, x, y, count) in stacks {
+        if x >= width || y >= height {
+            return Err(parse_error(
+                line,
+                column,
+                format!("({}, {}) is outside the {}x{} board", x, y, width, height),
+            ));
+        }
+        let cell = &mut cells[y * width + x];
+        match (option, cell.kind, cell.state) {
+            ("mines", CellKind::Mine(_), _) => {
+                cell.kind = CellKind::Mine(count);
+                last_mines = (line, column);
+            }
+            ("flags", _, CellState::Flagged(_)) => cell.state = CellState::Flagged(count),
+            _ => {
+                let what = if option == "mines" { "mine" } else { "flag" };
+                return Err(parse_error(
+                    line,
+                    column,
+                    format!("({}, {}) has no {}", x, y, what),
+                ));
+            }
+        }
+    }
+    let mines: usize = cells.iter().map(|cell| cell.kind.mines()).sum();
+    if mines >= width * height {
+        return Err(parse_error(
+            last_mines.0,
+            last_mines.1,
+            format!(
+                "{} mines leave no free cell on a {}x{} board",
+                mines, width, height
+            ),
+        ));
+    }
+    topology.count_numbers(shape, neighbourhood, &mut cells, width, height);
+
+    for (line, column, digit) in digits {
+        let y = line_numbers.iter().position(|&l| l == line).unwrap_or(0);
+        if let CellKind::Number(n) = cells[y * width + column - 1].kind
+            && n != digit
+        {
+            return Err(parse_error(
+                line,
+                column,
+                format!("cell shows {} but has {} adjacent mines", digit, n),
+            ));
+        }
+    }
+    Ok(Layout {
+        width,
+        height,
+        cells,
+        topology,
+        shape,
+        neighbourhood,
+    })
+}
+
+/// Formats cells in row-major order as layout text, one line per row, after
+/// `mines:` and `flags:` lines for any cells holding or flagged with several mines.
+pub fn format(width: usize, cells: &[CellBox]) -> String {
+    let width = width.max(1);
+    let mut text = String::with_capacity(cells.len() + cells.len() / width);
+    let stacked = |count: fn(&CellBox) -> usize| {
+        cells
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| count(cell) > 1)
+            .map(|(idx, cell)| format!(" {},{}={}", idx % width, idx / width, count(cell)))
+            .collect::<String>()
+    };
+    for (option, pairs) in [
+        ("mines", stacked(|cell| cell.kind.mines())),
+        ("flags", stacked(|cell| cell.state.flags())),
+    ] {
+        if !pairs.is_empty() {
+            text += &format!("{}:{}\n", option, pairs);
+        }
+    }
+    for row in cells.chunks(width) {
+        text.extend(row.iter().map(cell_char));
+        text.push('\n');
+    }
+    text
+}
+
+/// Returns the layout character for a cell. Revealed numbers are written as digits,
+/// except those above 9, which are written as `_` and counted again when read.
+pub fn cell_char(cell: &CellBox) -> char {
+    match (cell.state, cell.kind) {
+        (CellState::Hidden, CellKind::Mine(_)) => '*',
+        (CellState::Hidden, CellKind::Number(_)) => '.',
+        (CellState::Revealed, CellKind::Mine(_)) => 'X',
+        (CellState::Revealed, CellKind::Number(0)) => '_',
+        (CellState::Revealed, CellKind::Number(n @ 1..=9)) => (b'0' + n) as char,
+        (CellState::Revealed, CellKind::Number(_)) => '_',
+        (CellState::Flagged(_), CellKind::Mine(_)) => 'F',
+        (CellState::Flagged(_), CellKind::Number(_)) => 'f',
+        (CellState::Question, CellKind::Mine(_)) => 'Q',
+        (CellState::Question, CellKind::Number(_)) => 'q',
+    }
+}
+
+/// Parses an `x,y=count` pair with a count of at least 1.
+fn parse_stack(pair: &str) -> Option<(usize, usize, u8)> {
+    let (position, count) = pair.split_once('=')?;
+    let (x, y) = position.split_once(',')?;
+    let count = count.parse().ok().filter(|&count| count > 0)?;
+    Some((x.parse().ok()?, y.parse().ok()?, count))
+}
+
+fn parse_error(line: usize, column: usize, message: String) -> Error {
+    Error::Parse {
+        line,
+        column,
+        message,
+    }
+}
@@ -12,19 +12,75 @@
 //! See the README for instructions on running the game as an application.
 //!
 //! ## Modules
+//! - [`agent`] - Computer players and a harness to compare them
+//! - [`bench`] - Timings of the board operations that run on every click
+//! - [`coop`] - Cooperative play, several players on one board
+//! - [`daily`] - Daily challenge
+//! - [`demo`] - Demo mode, a built-in agent playing on its own
+//! - [`error`] - Library error type
+//! - [`formats`] - Community board and replay file formats
 //! - [`game_logic`] - Core game logic and board state
+//! - [`grid`] - Cell storage, dense or chunked
+//! - [`layout`] - Plain-text board layouts
 //! - [`menu`] - Menu system and event handling
+//! - [`protocol`] - JSON-lines protocol for bots
+//! - [`race`] - Head-to-head races on identical boards
+//! - [`replay`] - Recorded games and playback
+//! - [`server`] - Local multiplayer server
+//! - [`shape`] - Square and hex cells
+//! - [`solver`] - Exact mine probabilities
+//! - [`spectate`] - Read-only mirrors of a running game
+//! - [`stats`] - Persistent game results
 //! - [`timer`] - Simple timer for tracking game duration
+//! - [`topology`] - Board topologies and neighbours
 //! - [`tui`] - Terminal UI rendering
 //! - [`types`] - Common types and configuration
 
+/// Computer players and a harness to compare them.
+pub mod agent;
+/// Timings of the board operations that run on every click.
+pub mod bench;
+/// Cooperative play, several players on one board.
+pub mod coop;
+/// Daily challenge.
+pub mod daily;
+/// Demo mode, a built-in agent playing on its own.
+pub mod demo;
+/// Library error type.
+pub mod error;
+/// Community board and replay file formats.
+pub mod formats;
 /// Core game logic and board state.
 pub mod game_logic;
+/// Cell storage, dense or chunked.
+pub mod grid;
+/// Plain-text board layouts.
+pub mod layout;
 /// Menu system and event handling.
 pub mod menu;
+/// JSON-lines protocol for bots.
+pub mod protocol;
+/// Head-to-head races on identical boards.
+pub mod race;
+/// Recorded games and playback.
+pub mod replay;
+/// Local multiplayer server.
+pub mod server;
+/// Square and hex cells.
+pub mod shape;
+/// Exact mine probabilities.
+pub mod solver;
+/// Read-only mirrors of a running game.
+pub mod spectate;
+/// Persistent game results.
+pub mod stats;
 /// Simple timer for tracking game duration.
 pub mod timer;
+/// Board topologies and neighbours.
+pub mod topology;
 /// Terminal UI rendering.
 pub mod tui;
 /// Common types and configuration.
 pub mod types;
+
+pub use error::{Error, Result};
@@ -1,12 +1,33 @@
 use termsweeper::{
+    Error,
+    agent::{self, Builtin},
+    bench::{self, Target},
+    coop::Coop,
+    daily::DailyHistory,
+    demo::{self, Demo},
+    formats,
     game_logic::Board,
     menu::{self, Menu},
+    protocol::Session,
+    race::{self, Race, RaceClient},
+    replay::Playback,
+    server::{self, Server},
+    spectate::{self, Broadcast},
+    stats::{self, Outcome},
     tui::{self, cleanup_terminal, render_game_board, setup_terminal},
-    types::GameState,
+    types::{GameConfig, GameMode, GameState, Preset},
 };
 
-use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseButton};
-use std::io::stdout;
+use anyhow::{Context, anyhow, bail};
+
+use crossterm::event::{
+    self, Event, KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::io::{BufRead, Write, stdout};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::mpsc::TryRecvError;
+use std::time::Instant;
 
 fn should_exit(event: &Event) -> bool {
     matches!(
@@ -30,12 +51,475 @@ fn should_menu(event: &Event) -> bool {
     )
 }
 
-fn main() -> Result<(), anyhow::Error> {
+/// Returns the direction an arrow key scrolls an endless board.
+fn scroll_direction(event: &Event) -> Option<(isize, isize)> {
+    match event {
+        Event::Key(key_event) => match key_event.code {
+            KeyCode::Left => Some((-1, 0)),
+            KeyCode::Right => Some((1, 0)),
+            KeyCode::Up => Some((0, -1)),
+            KeyCode::Down => Some((0, 1)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Command line options.
+struct Args {
+    /// Start a game with this preset instead of showing the menu.
+    preset: Option<Preset>,
+    /// Start a game on the board layout in this file.
+    board: Option<PathBuf>,
+    /// Play back the `.avf` or `.rmv` video in this file.
+    replay: Option<PathBuf>,
+    /// Save each finished game to this `.rmv` file.
+    record: Option<PathBuf>,
+    /// Speak the bot protocol on stdin and stdout instead of drawing the game.
+    headless: bool,
+    /// Host games for other clients instead of playing.
+    serve: bool,
+    /// Race in this room on the server at this address.
+    race: Option<(String, String)>,
+    /// Let spectators watch the game being played.
+    spectators: bool,
+    /// Watch the game being played at this address.
+    watch: Option<String>,
+    /// Port to serve on or let spectators in on, instead of the default.
+    port: Option<u16>,
+    /// Address to serve on, instead of the default.
+    bind: Option<IpAddr>,
+    /// Pit the built-in agents against each other instead of playing.
+    arena: bool,
+    /// Games each agent plays in the arena.
+    games: Option<usize>,
+    /// First seed of the arena's boards.
+    seed: Option<u64>,
+    /// Only run this agent in the arena.
+    agent: Option<Builtin>,
+    /// Time the board operations instead of playing.
+    bench: bool,
+    /// Runs of each operation timed by the bench.
+    runs: Option<u32>,
+}
+
+fn usage() -> String {
+    let presets: Vec<&str> = Preset::ALL.iter().map(|preset| preset.key()).collect();
+    let agents: Vec<&str> = Builtin::ALL.iter().map(|agent| agent.key()).collect();
+    format!(
+        "Usage: termsweeper [--preset <name> | --board <file> | --replay <file>] [--record <file>]\n       \
+         termsweeper --headless\n       \
+         termsweeper serve [--bind <address>] [--port <n>]\n       \
+         termsweeper race <address> <room> [--preset <name>]\n       \
+         termsweeper watch <address>\n       \
+         termsweeper arena [--preset <name>] [--games <n>] [--seed <n>] [--agent <name>]\n       \
+         termsweeper bench [--runs <n>]\n\n\
+         Options:\n  \
+         -p, --preset <name>  Start a game right away ({})\n  \
+         -b, --board <file>   Play the board in <file> (text layout or .mbf)\n  \
+         --replay <file>      Play back the .avf or .rmv video in <file>\n  \
+         --record <file>      Save each finished game to <file> as .rmv\n  \
+         --headless           Play over JSON lines on stdin/stdout, for bots\n  \
+         serve                Host games for other clients\n  \
+         --bind <address>     Address to serve on (default {}, 0.0.0.0 for the LAN)\n  \
+         --port <n>           Port to serve on (default {}) or let spectators in on (default {})\n  \
+         race <address> <room> Race whoever joins <room> on a server\n  \
+         --spectators         Let spectators on 127.0.0.1 watch while you play\n  \
+         watch <address>      Watch a game played with --spectators, read only\n  \
+         arena                Compare the built-in agents on the same seeded boards\n  \
+         --games <n>          Games per agent in the arena (default {})\n  \
+         --seed <n>           Seed of the arena's first board (default 0)\n  \
+         --agent <name>       Only run one agent in the arena ({})\n  \
+         bench                Time board generation, flood fill, win checks and flag counts\n  \
+         --runs <n>           Runs of each operation in the bench (default {})\n  \
+         -h, --help           Show this help",
+        presets.join(", "),
+        server::DEFAULT_ADDR,
+        server::DEFAULT_PORT,
+        spectate::DEFAULT_PORT,
+        DEFAULT_ARENA_GAMES,
+        agents.join(", "),
+        DEFAULT_BENCH_RUNS
+    )
+}
+
+fn parse_args() -> Result<Args, anyhow::Error> {
+    let mut args = Args {
+        preset: None,
+        board: None,
+        replay: None,
+        record: None,
+        headless: false,
+        serve: false,
+        race: None,
+        spectators: false,
+        watch: None,
+        port: None,
+        bind: None,
+        arena: false,
+        games: None,
+        seed: None,
+        agent: None,
+        bench: false,
+        runs: None,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-p" | "--preset" => {
+                let key = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs a preset name", arg))?;
+                let preset = Preset::from_key(&key)
+                    .ok_or_else(|| anyhow!("unknown preset '{}'\n\n{}", key, usage()))?;
+                args.preset = Some(preset);
+            }
+            "-b" | "--board" => {
+                let path = iter.next().ok_or_else(|| anyhow!("{} needs a file", arg))?;
+                args.board = Some(PathBuf::from(path));
+            }
+            "--replay" => {
+                let path = iter.next().ok_or_else(|| anyhow!("{} needs a file", arg))?;
+                args.replay = Some(PathBuf::from(path));
+            }
+            "--record" => {
+                let path = iter.next().ok_or_else(|| anyhow!("{} needs a file", arg))?;
+                args.record = Some(PathBuf::from(path));
+            }
+            "--headless" => args.headless = true,
+            "serve" => args.serve = true,
+            "race" => {
+                let addr = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("race needs a server address\n\n{}", usage()))?;
+                let room = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("race needs a room name\n\n{}", usage()))?;
+                args.race = Some((addr, room));
+            }
+            "--spectators" => args.spectators = true,
+            "arena" => args.arena = true,
+            "--games" => {
+                let games = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs a number", arg))?;
+                let games = games
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number of games '{}'\n\n{}", games, usage()))?;
+                args.games = Some(games);
+            }
+            "--seed" => {
+                let seed = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs a number", arg))?;
+                let seed = seed
+                    .parse()
+                    .map_err(|_| anyhow!("invalid seed '{}'\n\n{}", seed, usage()))?;
+                args.seed = Some(seed);
+            }
+            "--agent" => {
+                let key = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs an agent name", arg))?;
+                let agent = Builtin::from_key(&key)
+                    .ok_or_else(|| anyhow!("unknown agent '{}'\n\n{}", key, usage()))?;
+                args.agent = Some(agent);
+            }
+            "bench" => args.bench = true,
+            "--runs" => {
+                let runs = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs a number", arg))?;
+                let runs = runs
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number of runs '{}'\n\n{}", runs, usage()))?;
+                args.runs = Some(runs);
+            }
+            "watch" => {
+                let addr = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("watch needs an address\n\n{}", usage()))?;
+                args.watch = Some(addr);
+            }
+            "--port" => {
+                let port = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs a number", arg))?;
+                let port = port
+                    .parse()
+                    .map_err(|_| anyhow!("invalid port '{}'\n\n{}", port, usage()))?;
+                args.port = Some(port);
+            }
+            "--bind" => {
+                let addr = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{} needs an address", arg))?;
+                let addr = addr
+                    .parse()
+                    .map_err(|_| anyhow!("invalid address '{}'\n\n{}", addr, usage()))?;
+                args.bind = Some(addr);
+            }
+            "-h" | "--help" => {
+                println!("{}", usage());
+                std::process::exit(0);
+            }
+            other => bail!("unknown argument '{}'\n\n{}", other, usage()),
+        }
+    }
+    let starts = [
+        args.preset.is_some(),
+        args.board.is_some(),
+        args.replay.is_some(),
+    ];
+    if starts.iter().filter(|&&given| given).count() > 1 {
+        bail!(
+            "--preset, --board and --replay can't be used together\n\n{}",
+            usage()
+        );
+    }
+    Ok(args)
+}
+
+/// Answers bot protocol requests from stdin until it closes.
+fn run_headless() -> Result<(), anyhow::Error> {
+    let mut session = Session::new();
+    let mut stdout = stdout().lock();
+    writeln!(stdout, "{}", Session::hello())?;
+    stdout.flush()?;
+    for line in std::io::stdin().lock().lines() {
+        if let Some(response) = session.handle_line(&line?) {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Races opponents in a room on a `termsweeper serve` server, until Esc.
+///
+/// Whoever joins an existing room deals the first race on `config`; after that,
+/// either player can deal a rematch.
+fn run_online_race(addr: &str, room: &str, config: GameConfig) -> Result<(), anyhow::Error> {
+    let name = std::env::var("USER").unwrap_or_else(|_| "player".to_string());
+    let (mut client, joined) = RaceClient::connect(addr, room, &name)
+        .with_context(|| format!("failed to join race room '{}' on {}", room, addr))?;
+    if joined {
+        client.start(config)?;
+    }
+    let mut race = None;
+    let waiting = format!("Waiting for an opponent to join room '{}'", room);
+    let mut stdout = stdout();
+    setup_terminal(&stdout)?;
+    tui::set_styles(&stdout)?;
+    // Leave the terminal usable if the server goes away
+    let result = (|| -> Result<(), anyhow::Error> {
+        loop {
+            client
+                .update(&mut race)
+                .context("lost the connection to the server")?;
+            match &race {
+                Some(race) => {
+                    let footer = if race.winner().is_some() {
+                        "Press [r] for a rematch or [Esc] to leave."
+                    } else {
+                        "First to clear their board wins │ [Esc] leave"
+                    };
+                    tui::render_race(&mut stdout, race, footer)?;
+                }
+                None => tui::render_message(&mut stdout, &[&waiting, "", "Press [Esc] to leave."])?,
+            }
+            if !event::poll(std::time::Duration::from_millis(100))? {
+                continue;
+            }
+            let event = event::read()?;
+            if should_exit(&event) {
+                return Ok(());
+            }
+            if let (Some(race), Event::Key(key_event)) = (&mut race, &event) {
+                if race.winner().is_some() {
+                    if should_restart(&event) {
+                        client.start(race.config().unwrap_or(config))?;
+                    }
+                } else if let Some((_, action)) = race.handle_key(key_event.code) {
+                    client.play(action)?;
+                }
+            }
+        }
+    })();
+    cleanup_terminal(&stdout)?;
+    result
+}
+
+/// Games each agent plays in the arena unless told otherwise.
+const DEFAULT_ARENA_GAMES: usize = 100;
+
+/// Plays the same seeded boards with each agent and prints how they did.
+fn run_arena(args: &Args) {
+    let preset = args.preset.unwrap_or(Preset::Beginner);
+    let first = args.seed.unwrap_or(0);
+    // Seeds near the top of the range get fewer games rather than wrapping around
+    let seeds = first..first.saturating_add(args.games.unwrap_or(DEFAULT_ARENA_GAMES) as u64);
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+    let agents = match args.agent {
+        Some(agent) => vec![agent],
+        None => Builtin::ALL.to_vec(),
+    };
+    println!(
+        "{} games of {} per agent, seeds {} to {}\n",
+        seeds.end - seeds.start,
+        preset.name(),
+        seeds.start,
+        seeds.end.saturating_sub(1)
+    );
+    println!(
+        "{:<12} {:>6} {:>6} {:>9} {:>10}",
+        "Agent", "Games", "Won", "Win rate", "Avg time"
+    );
+    for agent in agents {
+        let summary = agent::run_games(agent, preset.config(), seeds.clone(), threads);
+        println!(
+            "{:<12} {:>6} {:>6} {:>8.1}% {:>7.2} ms",
+            agent.key(),
+            summary.games,
+            summary.wins,
+            summary.win_rate() * 100.0,
+            summary.average_time().as_secs_f64() * 1000.0
+        );
+    }
+}
+
+/// Runs of each operation the bench times unless told otherwise.
+const DEFAULT_BENCH_RUNS: u32 = 100;
+
+/// Times the board operations that run on every click and prints the averages.
+fn run_bench(args: &Args) {
+    let runs = args.runs.unwrap_or(DEFAULT_BENCH_RUNS);
+    println!("Average of {} runs, setup left out\n", runs);
+    print!("{:<22}", "Operation");
+    for size in bench::SIZES {
+        print!(" {:>12}", format!("{}x{}", size, size));
+    }
+    println!();
+    for target in Target::ALL {
+        print!("{:<22}", target.key());
+        for size in bench::SIZES {
+            print!(" {:>12}", format!("{:.1?}", target.measure(size, runs)));
+        }
+        println!();
+    }
+}
+
+/// Mirrors a game played with `--spectators` at `addr`, until Esc.
+fn run_watch(addr: &str) -> Result<(), anyhow::Error> {
+    let frames = spectate::watch(addr).with_context(|| format!("failed to watch {}", addr))?;
     let mut stdout = stdout();
     setup_terminal(&stdout)?;
+    tui::set_styles(&stdout)?;
+    let result = (|| -> Result<(), anyhow::Error> {
+        let mut frame = None;
+        let mut closed = false;
+        loop {
+            // Only the latest frame is worth drawing
+            loop {
+                match frames.try_recv() {
+                    Ok(latest) => frame = latest,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        closed = true;
+                        break;
+                    }
+                }
+            }
+            if closed {
+                tui::render_message(
+                    &mut stdout,
+                    &["The game has closed", "", "Press [Esc] to leave."],
+                )?;
+            } else {
+                tui::render_frame(
+                    &mut stdout,
+                    frame.as_ref(),
+                    "Watching, read only │ [Esc] leave",
+                )?;
+            }
+            if event::poll(std::time::Duration::from_millis(100))? && should_exit(&event::read()?) {
+                return Ok(());
+            }
+        }
+    })();
+    cleanup_terminal(&stdout)?;
+    result
+}
 
+fn main() -> Result<(), anyhow::Error> {
+    let args = parse_args()?;
+    if args.headless {
+        return run_headless();
+    }
+    if args.arena {
+        run_arena(&args);
+        return Ok(());
+    }
+    if args.bench {
+        run_bench(&args);
+        return Ok(());
+    }
+    if args.serve {
+        let port = args.port.unwrap_or(server::DEFAULT_PORT);
+        let addr = args.bind.unwrap_or(server::DEFAULT_ADDR);
+        let server = Server::bind((addr, port))
+            .with_context(|| format!("failed to listen on {}:{}", addr, port))?;
+        println!("Serving on {}", server.local_addr()?);
+        server.run()?;
+        return Ok(());
+    }
+    if let Some((addr, room)) = &args.race {
+        let config = args.preset.unwrap_or(Preset::Beginner).config();
+        return run_online_race(addr, room, config);
+    }
+    if let Some(addr) = &args.watch {
+        return run_watch(addr);
+    }
+    let broadcast = if args.spectators {
+        let port = args.port.unwrap_or(spectate::DEFAULT_PORT);
+        Some(
+            Broadcast::bind(("127.0.0.1", port))
+                .with_context(|| format!("failed to let spectators in on port {}", port))?,
+        )
+    } else {
+        None
+    };
     let mut board = Board::new();
     let mut game_state = GameState::Menu;
+    if let Some(preset) = args.preset {
+        board = Board::new_with_config(preset.config());
+        game_state = GameState::Ongoing;
+    }
+    if let Some(path) = &args.board {
+        board = formats::load_board(path)
+            .with_context(|| format!("failed to load board from {}", path.display()))?;
+        game_state = GameState::Ongoing;
+    }
+    let mut playback = None;
+    if let Some(path) = &args.replay {
+        let replay = formats::load_replay(path)
+            .with_context(|| format!("failed to load replay from {}", path.display()))?;
+        let (started, replay_board) = Playback::start(replay)?;
+        playback = Some(started);
+        board = replay_board;
+        game_state = GameState::Ongoing;
+    }
+    // Whether the current finished game has been saved to the --record file and stats
+    let mut recorded = false;
+    // The split-screen race being played, dealt when the menu starts one
+    let mut race: Option<Race> = None;
+    let mut coop: Option<Coop> = None;
+    let mut demo: Option<Demo> = None;
+    // The demo starts when the menu is left alone for long enough
+    let mut last_input = Instant::now();
+
+    let mut stdout = stdout();
+    setup_terminal(&stdout)?;
 
     let main_menu = Menu::new_main_menu();
     let mut current_menu = Box::new(main_menu);
@@ -49,28 +533,156 @@ fn main() -> Result<(), anyhow::Error> {
                 render_game_board(&board, &mut stdout)?;
             }
             GameState::Won | GameState::Lost => {
+                if playback.is_none() && !recorded {
+                    if let Some(path) = &args.record
+                        && let Some(replay) = board.get_replay()
+                    {
+                        formats::save_replay(path, &replay)
+                            .with_context(|| format!("failed to record to {}", path.display()))?;
+                    }
+                    let outcome = if game_state == GameState::Won {
+                        Outcome::Won
+                    } else {
+                        Outcome::Lost
+                    };
+                    if let GameMode::Daily {
+                        date,
+                        official: true,
+                    } = board.mode
+                    {
+                        let time_ms = board.timer.get_elapsed().as_millis() as u32;
+                        DailyHistory::load()
+                            .and_then(|mut history| history.finish(date, outcome, time_ms))
+                            .context("failed to save the daily result")?;
+                        // Restarting replays the same board, as practice
+                        board.mode = GameMode::Daily {
+                            date,
+                            official: false,
+                        };
+                    } else {
+                        stats::record_game(&board, outcome)
+                            .context("failed to save the game result")?;
+                    }
+                    recorded = true;
+                }
                 render_game_board(&board, &mut stdout)?;
                 tui::overlay_ascii_art(&mut stdout, &board, game_state == GameState::Won)?;
             }
+            GameState::Race => {
+                let race = race.get_or_insert_with(|| {
+                    Race::new(
+                        board.get_config(),
+                        board.settings,
+                        rand::random(),
+                        &[("Player 1", race::WASD_KEYS), ("Player 2", race::IJKL_KEYS)],
+                    )
+                });
+                let footer = if race.winner().is_some() {
+                    "Press [m] for menu [r] for a rematch or [ctrl+c] to exit."
+                } else {
+                    "First to clear their board wins"
+                };
+                tui::render_race(&mut stdout, race, footer)?;
+            }
+            GameState::Coop => {
+                let coop = coop.get_or_insert_with(|| {
+                    Coop::new(
+                        board.get_config(),
+                        board.settings,
+                        rand::random(),
+                        &[("Player 1", race::WASD_KEYS), ("Player 2", race::IJKL_KEYS)],
+                    )
+                });
+                coop.check_deadline();
+                let footer = if coop.state == GameState::Ongoing {
+                    "Clear the board together: a mine hit by either of you ends it"
+                } else {
+                    "Press [m] for menu [r] to play again or [ctrl+c] to exit."
+                };
+                tui::render_coop(&mut stdout, coop, footer)?;
+            }
+            GameState::Demo => {
+                let demo = demo.get_or_insert_with(Demo::new);
+                demo.update();
+                render_game_board(&demo.board, &mut stdout)?;
+                if demo.state == GameState::Ongoing {
+                    tui::render_thinking(
+                        &mut stdout,
+                        &demo.board,
+                        demo.thinking(),
+                        "Demo │ press any key for the menu",
+                    )?;
+                } else {
+                    tui::overlay_ascii_art(&mut stdout, &demo.board, demo.state == GameState::Won)?;
+                }
+            }
             GameState::Exit => {
                 break 'game_loop;
             }
         }
+        if let Some(broadcast) = &broadcast {
+            broadcast.show(&board, game_state);
+        }
+        // The time limit runs out whether or not there is input
+        if game_state == GameState::Ongoing
+            && let Some(new_state) = board.check_deadline()
+        {
+            game_state = new_state;
+            continue;
+        }
         // Wait for event, but only up to 100ms
         if let Ok(false) = event::poll(std::time::Duration::from_millis(100)) {
-            if game_state == GameState::Ongoing {
-                render_game_board(&board, &mut stdout)?;
-                continue;
+            match game_state {
+                GameState::Ongoing => {
+                    if let Some(playback) = &mut playback
+                        && let Some(new_state) = playback.advance(&mut board)
+                    {
+                        game_state = new_state;
+                    }
+                    render_game_board(&board, &mut stdout)?;
+                    continue;
+                }
+                // Races, co-op games and the demo are redrawn at the top of the loop,
+                // clocks and all
+                GameState::Race | GameState::Coop | GameState::Demo => continue,
+                GameState::Menu => {
+                    if last_input.elapsed() >= demo::IDLE_TIMEOUT {
+                        game_state = GameState::Demo;
+                    }
+                    continue;
+                }
+                _ => {}
             }
         }
         let event = event::read()?;
+        last_input = Instant::now();
+        // Any key or click stops the demo, Esc included
+        if game_state == GameState::Demo
+            && matches!(
+                event,
+                Event::Key(_)
+                    | Event::Mouse(MouseEvent {
+                        kind: MouseEventKind::Down(_),
+                        ..
+                    })
+            )
+        {
+            game_state = GameState::Menu;
+            demo = None;
+            *current_menu = menu::Menu::new_main_menu();
+            continue;
+        }
         if should_exit(&event) {
             break 'game_loop;
         }
         match game_state {
             GameState::Menu => {
-                menu::handle_menu_event(&event, &mut *current_menu);
-                menu::process_menu_selection(&mut *current_menu, &mut board, &mut game_state);
+                menu::handle_menu_event(&event, &mut current_menu);
+                match menu::process_menu_selection(&mut current_menu, &mut board, &mut game_state) {
+                    Err(Error::Terminal(err)) => return Err(err.into()),
+                    Err(err) => current_menu.error = Some(err.to_string()),
+                    Ok(()) => {}
+                }
                 if game_state == GameState::Ongoing {
                     continue;
                 }
@@ -79,7 +691,12 @@ fn main() -> Result<(), anyhow::Error> {
                 }
             }
             GameState::Ongoing => {
-                if let Event::Mouse(mouse_event) = event {
+                if let Some(playback) = &mut playback {
+                    // Input doesn't affect a replay, except restarting it
+                    if should_restart(&event) {
+                        board = playback.restart()?;
+                    }
+                } else if let Event::Mouse(mouse_event) = event {
                     match mouse_event.kind {
                         event::MouseEventKind::Down(MouseButton::Left) => {
                             if let Some(new_state) = board.handle_mouse_left(mouse_event) {
@@ -87,22 +704,71 @@ fn main() -> Result<(), anyhow::Error> {
                             }
                         }
                         event::MouseEventKind::Down(MouseButton::Right) => {
-                            board.handle_mouse_right(mouse_event);
+                            if let Some(new_state) = board.handle_mouse_right(mouse_event) {
+                                game_state = new_state;
+                            }
+                        }
+                        event::MouseEventKind::Down(MouseButton::Middle) => {
+                            if let Some(new_state) = board.handle_mouse_middle(mouse_event) {
+                                game_state = new_state;
+                            }
+                        }
+                        event::MouseEventKind::Up(button) => {
+                            board.handle_mouse_up(button);
+                        }
+                        event::MouseEventKind::Moved => {
+                            board.handle_mouse_move(mouse_event);
                         }
                         _ => {}
                     }
+                } else if let Some((dx, dy)) = scroll_direction(&event) {
+                    board.scroll(dx, dy);
                 }
             }
             GameState::Won | GameState::Lost => {
                 board.reveal_all_mines();
                 if should_restart(&event) {
                     game_state = GameState::Ongoing;
-                    board.reset();
+                    recorded = false;
+                    match &mut playback {
+                        Some(playback) => board = playback.restart()?,
+                        None => board.reset(),
+                    }
                 } else if should_menu(&event) {
                     game_state = GameState::Menu;
-                    current_menu = Box::new(menu::Menu::new_main_menu());
+                    playback = None;
+                    recorded = false;
+                    *current_menu = menu::Menu::new_main_menu();
+                }
+            }
+            GameState::Race => {
+                let decided = race.as_ref().is_some_and(|race| race.winner().is_some());
+                if decided && should_restart(&event) {
+                    race = None;
+                } else if decided && should_menu(&event) {
+                    game_state = GameState::Menu;
+                    race = None;
+                    *current_menu = menu::Menu::new_main_menu();
+                } else if let (Some(race), Event::Key(key_event)) = (&mut race, &event) {
+                    race.handle_key(key_event.code);
+                }
+            }
+            GameState::Coop => {
+                let over = coop
+                    .as_ref()
+                    .is_some_and(|coop| coop.state != GameState::Ongoing);
+                if over && should_restart(&event) {
+                    coop = None;
+                } else if over && should_menu(&event) {
+                    game_state = GameState::Menu;
+                    coop = None;
+                    *current_menu = menu::Menu::new_main_menu();
+                } else if let (Some(coop), Event::Key(key_event)) = (&mut coop, &event) {
+                    coop.handle_key(key_event.code);
                 }
             }
+            // Mouse moves and resizes leave the demo playing
+            GameState::Demo => {}
             GameState::Exit => {
                 break 'game_loop;
             }
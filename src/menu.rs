@@ -1,5 +1,16 @@
+use crate::daily::{self, DailyHistory};
 use crate::game_logic::Board;
-use crate::types::{GameConfig, GameState, MenuItem, MenuItemType};
+use crate::race;
+use crate::shape::{Neighbourhood, Shape};
+use crate::stats::{self, Outcome, ResultLog};
+use crate::topology::Topology;
+use crate::types::{
+    ClickModel, FirstClick, GameConfig, GameState, LIVES, LIVES_NAMES, MINES_PER_CELL,
+    MINES_PER_CELL_NAMES, MenuItem, MenuItemType, Noise, Preset, Settings, StatusField,
+    TIME_BONUS_NAMES, TIME_BONUSES, TIME_LIMIT_NAMES, TIME_LIMITS,
+};
+use crate::{Error, Result};
+use chrono::NaiveDate;
 use crossterm::event;
 
 /// Represents a menu in the Minesweeper game.
@@ -13,6 +24,10 @@ pub struct Menu {
     pub hovered_index: usize,
     /// The currently selected menu item, if any.
     pub selected: Option<MenuItem>,
+    /// Lines of information shown below the items.
+    pub notes: Vec<String>,
+    /// Message shown below the items after a rejected selection.
+    pub error: Option<String>,
 }
 
 impl Menu {
@@ -22,12 +37,19 @@ impl Menu {
             items,
             hovered_index: 0,
             selected: None,
+            notes: Vec::new(),
+            error: None,
         }
     }
 
-    /// Creates the main menu with predefined items.
+    /// Creates the main menu with one item per preset followed by the predefined items.
     pub fn new_main_menu() -> Menu {
-        Menu::new(MAIN_MENU_ITEMS_LIST.to_vec())
+        let presets = Preset::ALL.into_iter().map(|preset| MenuItem::Main {
+            item_type: MenuItemType::Preset(preset),
+            name: preset.name(),
+            config: Some(preset.config()),
+        });
+        Menu::new(presets.chain(MAIN_MENU_ITEMS_LIST).collect())
     }
 
     /// Creates the custom configuration menu.
@@ -35,6 +57,295 @@ impl Menu {
         Menu::new(CUSTOM_MENU_ITEMS_LIST.to_vec())
     }
 
+    /// Creates the settings menu, showing the current values of `settings`.
+    pub fn new_settings_menu(settings: &Settings) -> Menu {
+        let click_model = ClickModel::ALL
+            .iter()
+            .position(|model| *model == settings.click_model)
+            .unwrap_or(0);
+        let first_click = FirstClick::ALL
+            .iter()
+            .position(|policy| *policy == settings.first_click)
+            .unwrap_or(0);
+        let time_limit = TIME_LIMITS
+            .iter()
+            .position(|limit| *limit == settings.time_limit)
+            .unwrap_or(0);
+        let time_bonus = TIME_BONUSES
+            .iter()
+            .position(|bonus| *bonus == settings.time_bonus)
+            .unwrap_or(0);
+        let lives = LIVES
+            .iter()
+            .position(|lives| *lives == settings.lives)
+            .unwrap_or(0);
+        let topology = Topology::ALL
+            .iter()
+            .position(|topology| *topology == settings.topology)
+            .unwrap_or(0);
+        let shape = Shape::ALL
+            .iter()
+            .position(|shape| *shape == settings.shape)
+            .unwrap_or(0);
+        let mines_per_cell = MINES_PER_CELL
+            .iter()
+            .position(|mines| *mines == settings.mines_per_cell)
+            .unwrap_or(0);
+        let noise = Noise::ALL
+            .iter()
+            .position(|noise| *noise == settings.noise)
+            .unwrap_or(0);
+        let neighbourhood = Neighbourhood::ALL
+            .iter()
+            .position(|neighbourhood| *neighbourhood == settings.neighbourhood)
+            .unwrap_or(0);
+        Menu::new(vec![
+            MenuItem::Choice {
+                item_type: MenuItemType::FirstClick,
+                name: "First click",
+                options: &FirstClick::NAMES,
+                selected: first_click,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::Shape,
+                name: "Cells",
+                options: &Shape::NAMES,
+                selected: shape,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::Neighbourhood,
+                name: "Numbers count",
+                options: &Neighbourhood::NAMES,
+                selected: neighbourhood,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::MinesPerCell,
+                name: "Mines per cell",
+                options: &MINES_PER_CELL_NAMES,
+                selected: mines_per_cell,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::Noise,
+                name: "Lying numbers",
+                options: &Noise::NAMES,
+                selected: noise,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::Topology,
+                name: "Edges",
+                options: &Topology::NAMES,
+                selected: topology,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::ClickModel,
+                name: "Chord",
+                options: &ClickModel::NAMES,
+                selected: click_model,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::FlagChord,
+                name: "Flag chord",
+                options: &ON_OFF,
+                selected: settings.flag_chord as usize,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::QuestionMarks,
+                name: "Question marks",
+                options: &ON_OFF,
+                selected: settings.question_marks as usize,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::QuestionBlocksChord,
+                name: "? blocks chord",
+                options: &ON_OFF,
+                selected: settings.question_blocks_chord as usize,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::TimeLimit,
+                name: "Time limit",
+                options: &TIME_LIMIT_NAMES,
+                selected: time_limit,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::TimeBonus,
+                name: "Opening bonus",
+                options: &TIME_BONUS_NAMES,
+                selected: time_bonus,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::Lives,
+                name: "Lives",
+                options: &LIVES_NAMES,
+                selected: lives,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::StatusBar,
+                name: "Status line",
+                options: &ON_OFF,
+                selected: settings.status_bar.enabled as usize,
+            },
+            MenuItem::Main {
+                item_type: MenuItemType::StatusFields,
+                name: "Status line fields",
+                config: None,
+            },
+            MenuItem::Choice {
+                item_type: MenuItemType::RevealForSpectators,
+                name: "Reveal for spectators",
+                options: &ON_OFF,
+                selected: settings.reveal_for_spectators as usize,
+            },
+            MenuItem::Main {
+                item_type: MenuItemType::Back,
+                name: "Back",
+                config: None,
+            },
+        ])
+    }
+
+    /// Creates the menu of status line fields, showing which of them `settings` shows.
+    ///
+    /// "Back" returns to the settings menu.
+    pub fn new_status_fields_menu(settings: &Settings) -> Menu {
+        let fields = StatusField::ALL
+            .into_iter()
+            .zip(StatusField::NAMES)
+            .map(|(field, name)| MenuItem::Choice {
+                item_type: MenuItemType::StatusField(field),
+                name,
+                options: &ON_OFF,
+                selected: settings.status_bar.shows(field) as usize,
+            });
+        Menu::new(
+            fields
+                .chain([MenuItem::Main {
+                    item_type: MenuItemType::Settings,
+                    name: "Back",
+                    config: None,
+                }])
+                .collect(),
+        )
+    }
+
+    /// Creates the race menu: one item per preset to race on, and the keys for each player.
+    pub fn new_race_menu() -> Menu {
+        let presets = Preset::ALL.into_iter().map(|preset| MenuItem::Main {
+            item_type: MenuItemType::RacePreset(preset),
+            name: preset.name(),
+            config: Some(preset.config()),
+        });
+        let mut menu = Menu::new(
+            presets
+                .chain([MenuItem::Main {
+                    item_type: MenuItemType::Back,
+                    name: "Back",
+                    config: None,
+                }])
+                .collect(),
+        );
+        menu.notes = vec![
+            "Two players, one keyboard, the same board".to_string(),
+            format!("Player 1: {}", race::WASD_KEYS.help),
+            format!("Player 2: {}", race::IJKL_KEYS.help),
+        ];
+        menu
+    }
+
+    /// Creates the co-op menu: one item per preset to play together, and the keys for
+    /// each player.
+    pub fn new_coop_menu() -> Menu {
+        let presets = Preset::ALL.into_iter().map(|preset| MenuItem::Main {
+            item_type: MenuItemType::CoopPreset(preset),
+            name: preset.name(),
+            config: Some(preset.config()),
+        });
+        let mut menu = Menu::new(
+            presets
+                .chain([MenuItem::Main {
+                    item_type: MenuItemType::Back,
+                    name: "Back",
+                    config: None,
+                }])
+                .collect(),
+        );
+        menu.notes = vec![
+            "Two players, one keyboard, one board".to_string(),
+            format!("Player 1: {}", race::WASD_KEYS.help),
+            format!("Player 2: {}", race::IJKL_KEYS.help),
+        ];
+        menu
+    }
+
+    /// Creates the daily challenge menu with the streak and the last week's results.
+    pub fn new_daily_menu(history: &DailyHistory, today: NaiveDate) -> Menu {
+        let play = if history.has_attempted(today) {
+            "Practice"
+        } else {
+            "Play"
+        };
+        let mut menu = Menu::new(vec![
+            MenuItem::Main {
+                item_type: MenuItemType::PlayDaily,
+                name: play,
+                config: Some(daily::DAILY_PRESET.config()),
+            },
+            MenuItem::Main {
+                item_type: MenuItemType::Back,
+                name: "Back",
+                config: None,
+            },
+        ]);
+        menu.notes.push(format!(
+            "{} {} │ streak: {}",
+            today,
+            daily::DAILY_PRESET.name(),
+            history.streak(today)
+        ));
+        for (date, entry) in history.recent(today, DAILY_HISTORY_DAYS) {
+            let result = match entry {
+                Some(entry) if entry.outcome == Outcome::Won => {
+                    format!("won {:>6.1}s", entry.time_ms as f64 / 1000.0)
+                }
+                Some(entry) => format!("{:<10}", entry.outcome.name()),
+                None => format!("{:<10}", "-"),
+            };
+            menu.notes
+                .push(format!("{} {}", date.format("%a %d %b"), result));
+        }
+        menu
+    }
+
+    /// Creates the stats menu, with a line per mode and board that has results.
+    pub fn new_stats_menu(logs: &[(&str, ResultLog)]) -> Menu {
+        let mut menu = Menu::new(vec![MenuItem::Main {
+            item_type: MenuItemType::Back,
+            name: "Back",
+            config: None,
+        }]);
+        for (label, log) in logs {
+            for (config, rules, summary) in log.summaries() {
+                let board = Preset::from_config(config).map_or_else(
+                    || format!("{}x{}/{}", config.width(), config.height(), config.mines()),
+                    |preset| preset.name().to_string(),
+                );
+                let best = summary.best_ms.map_or_else(
+                    || "-".to_string(),
+                    |best| format!("{:.1}s", best as f64 / 1000.0),
+                );
+                // Rules beyond classic play, e.g. " lives=3 time=60"
+                let rules: String = rules.tags().iter().map(|tag| format!(" {}", tag)).collect();
+                menu.notes.push(format!(
+                    "{:<11} {:<12} won {:>3}/{:<3} best {:>7}{}",
+                    label, board, summary.won, summary.played, best, rules
+                ));
+            }
+        }
+        if menu.notes.is_empty() {
+            menu.notes.push("No games played yet".to_string());
+        }
+        menu
+    }
+
     /// Returns a reference to the currently hovered menu item.
     pub fn get_hovered_item(&self) -> &MenuItem {
         &self.items[self.hovered_index]
@@ -42,7 +353,7 @@ impl Menu {
 
     /// Selects the currently hovered menu item.
     pub fn select(&mut self) {
-        let selected_item = self.get_hovered_item().clone();
+        let selected_item = *self.get_hovered_item();
         self.selected = Some(selected_item);
     }
 
@@ -65,32 +376,72 @@ impl Menu {
         self.items.len()
     }
 
-    /// Returns a custom game configuration if all values are set.
-    pub fn get_custom_config(&self) -> Option<GameConfig> {
-        let mut config = GameConfig {
-            width: 0,
-            height: 0,
-            mines: 0,
-        };
+    /// Returns true if the menu has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the custom game configuration the menu's values describe, checked
+    /// against the board limits.
+    pub fn get_custom_config(&self) -> Result<GameConfig> {
+        let (mut width, mut height, mut mines) = (0, 0, 0);
         for item in &self.items {
             if let MenuItem::Custom {
                 item_type, value, ..
             } = item
             {
                 match item_type {
-                    MenuItemType::Width => config.width = *value,
-                    MenuItemType::Height => config.height = *value,
-                    MenuItemType::Mines => config.mines = *value,
+                    MenuItemType::Width => width = *value,
+                    MenuItemType::Height => height = *value,
+                    MenuItemType::Mines => mines = *value,
                     _ => {}
                 }
             }
         }
-        // Check if all values were found
-        if config.width > 0 && config.height > 0 {
-            Some(config)
-        } else {
-            None
+        GameConfig::new(width, height, mines)
+    }
+
+    /// Returns `settings` updated with the choices made in a settings menu.
+    pub fn get_settings(&self, mut settings: Settings) -> Settings {
+        for item in &self.items {
+            if let MenuItem::Choice {
+                item_type,
+                selected,
+                ..
+            } = *item
+            {
+                match item_type {
+                    MenuItemType::FirstClick => settings.first_click = FirstClick::ALL[selected],
+                    MenuItemType::ClickModel => settings.click_model = ClickModel::ALL[selected],
+                    MenuItemType::FlagChord => settings.flag_chord = selected == 1,
+                    MenuItemType::QuestionMarks => settings.question_marks = selected == 1,
+                    MenuItemType::QuestionBlocksChord => {
+                        settings.question_blocks_chord = selected == 1
+                    }
+                    MenuItemType::TimeLimit => settings.time_limit = TIME_LIMITS[selected],
+                    MenuItemType::TimeBonus => settings.time_bonus = TIME_BONUSES[selected],
+                    MenuItemType::Lives => settings.lives = LIVES[selected],
+                    MenuItemType::Topology => settings.topology = Topology::ALL[selected],
+                    MenuItemType::Shape => settings.shape = Shape::ALL[selected],
+                    MenuItemType::MinesPerCell => {
+                        settings.mines_per_cell = MINES_PER_CELL[selected]
+                    }
+                    MenuItemType::Noise => settings.noise = Noise::ALL[selected],
+                    MenuItemType::Neighbourhood => {
+                        settings.neighbourhood = Neighbourhood::ALL[selected]
+                    }
+                    MenuItemType::StatusBar => settings.status_bar.enabled = selected == 1,
+                    MenuItemType::StatusField(field) => {
+                        settings.status_bar.set(field, selected == 1)
+                    }
+                    MenuItemType::RevealForSpectators => {
+                        settings.reveal_for_spectators = selected == 1
+                    }
+                    _ => {}
+                }
+            }
         }
+        settings
     }
 }
 
@@ -124,6 +475,15 @@ pub fn handle_menu_event(event: &event::Event, menu: &mut Menu) {
                         value: new_value,
                     };
                 }
+                // Cycle forward through the options of a settings item
+                if let MenuItem::Choice {
+                    options,
+                    ref mut selected,
+                    ..
+                } = menu.items[menu.hovered_index]
+                {
+                    *selected = (*selected + 1) % options.len();
+                }
             }
             event::KeyCode::Left => {
                 // Decrease value for custom menu item
@@ -140,6 +500,15 @@ pub fn handle_menu_event(event: &event::Event, menu: &mut Menu) {
                         value: new_value,
                     };
                 }
+                // Cycle backward through the options of a settings item
+                if let MenuItem::Choice {
+                    options,
+                    ref mut selected,
+                    ..
+                } = menu.items[menu.hovered_index]
+                {
+                    *selected = (*selected + options.len() - 1) % options.len();
+                }
             }
             _ => {}
         }
@@ -149,84 +518,161 @@ pub fn handle_menu_event(event: &event::Event, menu: &mut Menu) {
 /// Processes the selected menu item and updates the game state accordingly.
 ///
 /// Handles starting new games, switching to custom menu, exiting, and confirming custom configuration.
+/// Returns [`Error::InvalidConfig`] if the selected configuration can't be played; the
+/// selection is cleared so the menu stays usable.
 pub fn process_menu_selection(
     current_menu: &mut Menu,
     board: &mut Board,
     game_state: &mut GameState,
-) {
+) -> Result<()> {
     if let Some(item) = current_menu.selected {
         match item {
             MenuItem::Main {
                 item_type, config, ..
             } => match item_type {
-                MenuItemType::Beginnner | MenuItemType::Intermediate | MenuItemType::Expert => {
-                    *board = Board::new_with_config(config.unwrap());
-                    *game_state = GameState::Ongoing;
+                MenuItemType::Preset(_)
+                | MenuItemType::RacePreset(_)
+                | MenuItemType::CoopPreset(_) => {
+                    let settings = board.settings;
+                    let config = config.ok_or_else(|| {
+                        Error::InvalidConfig("menu item has no configuration".to_string())
+                    })?;
+                    *board = Board::new_with_config(config);
+                    board.settings = settings;
+                    // Races and co-op games are dealt from this board's size and settings
+                    *game_state = match item_type {
+                        MenuItemType::RacePreset(_) => GameState::Race,
+                        MenuItemType::CoopPreset(_) => GameState::Coop,
+                        _ => GameState::Ongoing,
+                    };
+                }
+                MenuItemType::Race => {
+                    *current_menu = Menu::new_race_menu();
+                }
+                MenuItemType::Coop => {
+                    *current_menu = Menu::new_coop_menu();
+                }
+                MenuItemType::Demo => {
+                    *game_state = GameState::Demo;
                 }
                 MenuItemType::Custom => {
                     *current_menu = Menu::new_custom_menu();
                 }
+                MenuItemType::Settings => {
+                    // Coming back from the status line fields keeps their changes
+                    board.settings = current_menu.get_settings(board.settings);
+                    *current_menu = Menu::new_settings_menu(&board.settings);
+                }
+                MenuItemType::StatusFields => {
+                    board.settings = current_menu.get_settings(board.settings);
+                    *current_menu = Menu::new_status_fields_menu(&board.settings);
+                }
+                MenuItemType::Daily => {
+                    current_menu.selected = None;
+                    let history = DailyHistory::load()?;
+                    *current_menu = Menu::new_daily_menu(&history, daily::today());
+                }
+                MenuItemType::Endless => {
+                    *board = Board::new_endless(rand::random(), board.settings);
+                    *game_state = GameState::Ongoing;
+                }
+                MenuItemType::Stats => {
+                    current_menu.selected = None;
+                    let logs = stats::RESULT_LOGS
+                        .iter()
+                        .map(|&(label, name)| Ok((label, ResultLog::open(name)?)))
+                        .collect::<Result<Vec<_>>>()?;
+                    *current_menu = Menu::new_stats_menu(&logs);
+                }
+                MenuItemType::PlayDaily => {
+                    current_menu.selected = None;
+                    let today = daily::today();
+                    let mut history = DailyHistory::load()?;
+                    let official = !history.has_attempted(today);
+                    if official {
+                        history.start(today)?;
+                    }
+                    *board = daily::new_board(today, official, board.settings);
+                    *game_state = GameState::Ongoing;
+                }
+                MenuItemType::Back => {
+                    board.settings = current_menu.get_settings(board.settings);
+                    *current_menu = Menu::new_main_menu();
+                }
                 MenuItemType::Exit => {
                     *game_state = GameState::Exit; // Or some exit state
                 }
                 MenuItemType::Confirm => {
-                    if let Some(config) = current_menu.get_custom_config() {
-                        if config.mines >= config.width * config.height {
-                            // Handle error: maybe reset menu to defaults
-                            // Or better yet, prevent this state in the key handlers!
-                            return;
+                    let checked = current_menu
+                        .get_custom_config()
+                        .and_then(|config| config.check_first_click(&board.settings));
+                    let config = match checked {
+                        Ok(config) => config,
+                        Err(err) => {
+                            current_menu.selected = None;
+                            return Err(err);
                         }
-                        *board = Board::new_with_config(Board::clamp_config(
-                            config.width,
-                            config.height,
-                            config.mines,
-                        ));
-                        *game_state = GameState::Ongoing;
-                    }
+                    };
+                    let settings = board.settings;
+                    *board = Board::new_with_config(config);
+                    board.settings = settings;
+                    *game_state = GameState::Ongoing;
                 }
                 _ => {}
             },
-            MenuItem::Custom { .. } => {
+            MenuItem::Custom { .. } | MenuItem::Choice { .. } => {
                 // Do nothing for now
             }
         }
     }
+    Ok(())
 }
 
-/// List of main menu items for the Minesweeper game.
-const MAIN_MENU_ITEMS_LIST: [MenuItem; 5] = [
+/// Number of days of results shown in the daily menu.
+const DAILY_HISTORY_DAYS: u64 = 7;
+
+/// Main menu items listed after the presets.
+const MAIN_MENU_ITEMS_LIST: [MenuItem; 9] = [
     MenuItem::Main {
-        item_type: MenuItemType::Beginnner,
-        name: "Beginner",
-        config: Some(GameConfig {
-            width: 9,
-            height: 9,
-            mines: 10,
-        }),
+        item_type: MenuItemType::Daily,
+        name: "Daily",
+        config: None,
     },
     MenuItem::Main {
-        item_type: MenuItemType::Intermediate,
-        name: "Intermediate",
-        config: Some(GameConfig {
-            width: 16,
-            height: 16,
-            mines: 40,
-        }),
+        item_type: MenuItemType::Endless,
+        name: "Endless",
+        config: None,
     },
     MenuItem::Main {
-        item_type: MenuItemType::Expert,
-        name: "Expert",
-        config: Some(GameConfig {
-            width: 30,
-            height: 16,
-            mines: 99,
-        }),
+        item_type: MenuItemType::Race,
+        name: "Race",
+        config: None,
+    },
+    MenuItem::Main {
+        item_type: MenuItemType::Coop,
+        name: "Co-op",
+        config: None,
+    },
+    MenuItem::Main {
+        item_type: MenuItemType::Demo,
+        name: "Demo",
+        config: None,
     },
     MenuItem::Main {
         item_type: MenuItemType::Custom,
         name: "Custom",
         config: None,
     },
+    MenuItem::Main {
+        item_type: MenuItemType::Settings,
+        name: "Settings",
+        config: None,
+    },
+    MenuItem::Main {
+        item_type: MenuItemType::Stats,
+        name: "Stats",
+        config: None,
+    },
     MenuItem::Main {
         item_type: MenuItemType::Exit,
         name: "Exit",
@@ -234,6 +680,9 @@ const MAIN_MENU_ITEMS_LIST: [MenuItem; 5] = [
     },
 ];
 
+/// Option labels for on/off settings, indexed by the setting's `bool` value.
+const ON_OFF: [&str; 2] = ["Off", "On"];
+
 /// List of custom configuration menu items for the Minesweeper game.
 const CUSTOM_MENU_ITEMS_LIST: [MenuItem; 4] = [
     MenuItem::Custom {
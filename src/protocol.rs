@@ -0,0 +1,425 @@
+//! JSON-lines protocol for bots playing the real engine.
+//!
+//! A bot sends one JSON object per line and gets one JSON object back per
+//! request. `termsweeper --headless` speaks it over stdin and stdout, starting
+//! with a `hello` line. Blank lines are ignored.
+//!
+//! # Requests
+//!
+//! Every request has a `cmd`. It may also carry an `id`, which is echoed back
+//! in the response, and a protocol version `v`, which must be [`VERSION`] if given.
+//!
+//! | `cmd`    | Fields                                               | Does                              |
+//! |----------|------------------------------------------------------|-----------------------------------|
+//! | `new`    | `preset`, or `width`, `height`, `mines`; opt. `seed` | Starts a game                     |
+//! | `reveal` | `x`, `y`                                             | Reveals a cell (first one is safe)|
+//! | `flag`   | `x`, `y`                                             | Toggles a flag                    |
+//! | `chord`  | `x`, `y`                                             | Chords on a revealed number       |
+//! | `state`  |                                                      | Reports the board without acting  |
+//!
+//! ```text
+//! {"cmd":"new","preset":"beginner","id":1}
+//! {"cmd":"reveal","x":4,"y":4}
+//! ```
+//!
+//! # Responses
+//!
+//! Every response has `v` (the protocol version) and `type`, in no particular key
+//! order. Successful requests get a `state` response:
+//!
+//! ```text
+//! {"v":1,"type":"state","id":1,"width":9,"height":9,"mines":10,"flags":0,
+//!  "revealed":0,"outcome":"playing","elapsed_ms":0,"board":["#########", ...]}
+//! ```
+//!
+//! `outcome` is `playing`, `won` or `lost`. `board` has one string per row and
+//! one character per cell: `#` hidden, `F` flagged, `?` question mark, `0`-`9`
+//! a revealed number (`+` above 9) and `X` the mine that ended the game. Only what the player
+//! could see is reported: hidden mines are never shown, even after the game
+//! ends, and neither is the seed of a game started without one.
+//!
+//! Bad requests get an `error` response with a machine-readable `code` and a
+//! human-readable `message`, and change nothing:
+//!
+//! ```text
+//! {"v":1,"type":"error","code":"out_of_bounds","message":"(12, 3) is outside the 9x9 board"}
+//! ```
+//!
+//! | `code`                | Meaning                                        |
+//! |-----------------------|------------------------------------------------|
+//! | `bad_json`            | The line is not valid JSON                     |
+//! | `bad_request`         | Not an object, or a field is missing or wrong  |
+//! | `unsupported_version` | `v` is not a version this engine speaks        |
+//! | `unknown_command`     | `cmd` is not one of the commands above         |
+//! | `invalid_config`      | The board size or mine count is out of range   |
+//! | `no_game`             | An action came before any `new`                |
+//! | `out_of_bounds`       | `x`, `y` is not on the board                   |
+//! | `game_over`           | An action came after the game was won or lost  |
+use crate::Error;
+use crate::coop::Crew;
+use crate::game_logic::Board;
+use crate::types::{Action, CellKind, CellState, DEFAULT_SETTINGS, GameConfig, GameState, Preset};
+use serde_json::{Map, Value, json};
+
+/// Version of the protocol, sent in every response.
+pub const VERSION: u64 = 1;
+/// Commands a bot can send, in the order they are documented.
+pub const COMMANDS: [&str; 5] = ["new", "reveal", "flag", "chord", "state"];
+
+/// One bot's game: parses request lines and answers them.
+pub struct Session {
+    board: Option<Board>,
+    state: GameState,
+    /// Who played what on the current board, for games shared by several players.
+    crew: Crew,
+    /// Requests that changed the game so far.
+    updates: u64,
+}
+
+/// A request that can't be carried out, reported as an `error` response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestError {
+    /// One of the error codes in the table above.
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl RequestError {
+    fn new(code: &'static str, message: impl Into<String>) -> RequestError {
+        RequestError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    /// Creates a session with no game started.
+    pub fn new() -> Session {
+        Session {
+            board: None,
+            state: GameState::Ongoing,
+            crew: Crew::new(),
+            updates: 0,
+        }
+    }
+
+    /// Returns the `hello` line sent before any request is read.
+    pub fn hello() -> String {
+        json!({
+            "v": VERSION,
+            "type": "hello",
+            "protocol": "termsweeper",
+            "commands": COMMANDS,
+        })
+        .to_string()
+    }
+
+    /// Returns the current game, if one was started.
+    pub fn board(&self) -> Option<&Board> {
+        self.board.as_ref()
+    }
+
+    /// Returns whether the current game is ongoing, won or lost.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Replaces the game with one started elsewhere, such as a race board.
+    pub fn set_board(&mut self, board: Board, state: GameState) {
+        self.board = Some(board);
+        self.state = state;
+        self.crew = Crew::new();
+        self.updates += 1;
+    }
+
+    /// Returns who played what on the current board.
+    pub fn crew(&self) -> &Crew {
+        &self.crew
+    }
+
+    /// Returns `playing`, `won` or `lost`.
+    pub fn outcome(&self) -> &'static str {
+        match self.state {
+            GameState::Won => "won",
+            GameState::Lost => "lost",
+            _ => "playing",
+        }
+    }
+
+    /// Returns how many requests have changed the game, so a server can tell
+    /// when to send the board to other players.
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    /// Returns a `state` line for the current game, if one was started.
+    pub fn state_line(&self) -> Option<String> {
+        let board = self.board.as_ref()?;
+        Some(state_response(board, self.outcome()).to_string())
+    }
+
+    /// Handles one request line and returns the response line, or None for a blank line.
+    pub fn handle_line(&mut self, line: &str) -> Option<String> {
+        self.handle_line_from(0, line)
+    }
+
+    /// Handles one request line from `player` of a shared game, crediting them with
+    /// what it does. Returns the response line, or None for a blank line.
+    pub fn handle_line_from(&mut self, player: usize, line: &str) -> Option<String> {
+        if line.trim().is_empty() {
+            return None;
+        }
+        let mut id = None;
+        let response = serde_json::from_str::<Value>(line)
+            .map_err(|err| RequestError::new("bad_json", err.to_string()))
+            .and_then(|request| {
+                let request = request.as_object().ok_or_else(|| {
+                    RequestError::new("bad_request", "request must be a JSON object")
+                })?;
+                id = request.get("id").cloned();
+                self.handle(player, request)
+            });
+        Some(match response {
+            Ok(mut state) => {
+                if let Some(id) = id {
+                    state["id"] = id;
+                }
+                state.to_string()
+            }
+            Err(err) => error_line(err.code, &err.message, id),
+        })
+    }
+
+    /// Carries out a parsed request and returns the `state` response.
+    fn handle(
+        &mut self,
+        player: usize,
+        request: &Map<String, Value>,
+    ) -> std::result::Result<Value, RequestError> {
+        if let Some(version) = request.get("v")
+            && version.as_u64() != Some(VERSION)
+        {
+            return Err(RequestError::new(
+                "unsupported_version",
+                format!("this engine speaks version {}, got {}", VERSION, version),
+            ));
+        }
+        let cmd = request
+            .get("cmd")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RequestError::new("bad_request", "missing string field 'cmd'"))?;
+        match cmd {
+            "new" => {
+                let board = new_board(request)?;
+                self.board = Some(board);
+                self.state = GameState::Ongoing;
+                self.crew = Crew::new();
+                self.updates += 1;
+            }
+            "reveal" | "flag" | "chord" => {
+                let board = self
+                    .board
+                    .as_mut()
+                    .ok_or_else(|| RequestError::new("no_game", "start a game with 'new' first"))?;
+                if self.state != GameState::Ongoing {
+                    return Err(RequestError::new(
+                        "game_over",
+                        "the game is over; start another with 'new'",
+                    ));
+                }
+                let x = coordinate(request, "x")?;
+                let y = coordinate(request, "y")?;
+                if board.get_cell(x, y).is_none() {
+                    return Err(RequestError::new(
+                        "out_of_bounds",
+                        format!(
+                            "({}, {}) is outside the {}x{} board",
+                            x, y, board.width, board.height
+                        ),
+                    ));
+                }
+                let action = match cmd {
+                    "reveal" => Action::Reveal { x, y },
+                    "flag" => Action::Flag { x, y },
+                    _ => Action::Chord { x, y },
+                };
+                if let Some(state) = self.crew.apply(board, player, action) {
+                    self.state = state;
+                }
+                self.updates += 1;
+            }
+            "state" => {}
+            other => {
+                return Err(RequestError::new(
+                    "unknown_command",
+                    format!("unknown command '{}'", other),
+                ));
+            }
+        }
+        let board = self
+            .board
+            .as_ref()
+            .ok_or_else(|| RequestError::new("no_game", "start a game with 'new' first"))?;
+        Ok(state_response(board, self.outcome()))
+    }
+}
+
+/// Returns an `error` line, echoing the request's `id` if it had one.
+pub fn error_line(code: &str, message: &str, id: Option<Value>) -> String {
+    let mut response = json!({
+        "v": VERSION,
+        "type": "error",
+        "code": code,
+        "message": message,
+    });
+    if let Some(id) = id {
+        response["id"] = id;
+    }
+    response.to_string()
+}
+
+/// Creates the board a `new` request asks for, with default settings.
+fn new_board(request: &Map<String, Value>) -> std::result::Result<Board, RequestError> {
+    let (config, seed) = parse_new(request)?;
+    let mut board = match seed {
+        Some(seed) => Board::new_with_seed(config, seed),
+        None => Board::new_with_config(config),
+    };
+    board.settings = DEFAULT_SETTINGS;
+    Ok(board)
+}
+
+/// Reads the board size and optional seed of a `new` request.
+///
+/// Missing or mistyped fields are a `bad_request`; sizes out of range are an
+/// `invalid_config`.
+pub fn parse_new(
+    request: &Map<String, Value>,
+) -> std::result::Result<(GameConfig, Option<u64>), RequestError> {
+    let integer = |name: &str| {
+        request.get(name).map(|value| {
+            value.as_u64().ok_or_else(|| {
+                RequestError::new(
+                    "bad_request",
+                    format!("'{}' must be a non-negative integer", name),
+                )
+            })
+        })
+    };
+    let config = match request.get("preset") {
+        Some(preset) => {
+            let key = preset.as_str().unwrap_or_default();
+            Preset::from_key(key)
+                .ok_or_else(|| {
+                    RequestError::new("invalid_config", format!("unknown preset {}", preset))
+                })?
+                .config()
+        }
+        None => {
+            let size = |name: &str| {
+                integer(name)
+                    .unwrap_or_else(|| {
+                        Err(RequestError::new(
+                            "bad_request",
+                            format!("missing integer field '{}'", name),
+                        ))
+                    })
+                    .map(|value| value as usize)
+            };
+            GameConfig::new(size("width")?, size("height")?, size("mines")?).map_err(|err| {
+                match err {
+                    Error::InvalidConfig(message) => RequestError::new("invalid_config", message),
+                    err => RequestError::new("bad_request", err.to_string()),
+                }
+            })?
+        }
+    };
+    let seed = integer("seed").transpose()?;
+    Ok((config, seed))
+}
+
+/// Reads a cell coordinate field.
+fn coordinate(
+    request: &Map<String, Value>,
+    name: &str,
+) -> std::result::Result<isize, RequestError> {
+    request
+        .get(name)
+        .and_then(Value::as_i64)
+        .map(|value| value as isize)
+        .ok_or_else(|| {
+            RequestError::new("bad_request", format!("missing integer field '{}'", name))
+        })
+}
+
+/// Builds a `state` response showing only what the player can see.
+fn state_response(board: &Board, outcome: &str) -> Value {
+    let rows = board_rows(board, false);
+    json!({
+        "v": VERSION,
+        "type": "state",
+        "width": board.width,
+        "height": board.height,
+        "mines": board.get_config().mines(),
+        "flags": board.get_flags_count(),
+        "revealed": board.get_revealed_count(),
+        "outcome": outcome,
+        "elapsed_ms": board.timer.get_elapsed().as_millis() as u64,
+        "board": rows,
+    })
+}
+
+/// Returns the board as the player sees it, one string per row in the characters of
+/// a `state` response. With `show_mines`, hidden mines are shown as `*`.
+///
+/// On endless boards the rows are the area on screen, from the viewport's corner.
+pub fn board_rows(board: &Board, show_mines: bool) -> Vec<String> {
+    let (left, top) = board.viewport;
+    (0..board.height as isize)
+        .map(|y| {
+            (0..board.width as isize)
+                .map(|x| visible_char(board, left + x, top + y, show_mines))
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the revealed numbers above 9 in the rows of [`board_rows`], written `+`
+/// there, as ((x, y), number) with (x, y) counted from the rows' corner.
+pub fn large_numbers(board: &Board) -> Vec<((usize, usize), u8)> {
+    let (left, top) = board.viewport;
+    (0..board.height)
+        .flat_map(|y| (0..board.width).map(move |x| (x, y)))
+        .filter_map(|(x, y)| {
+            let shown = board.get_shown_number(left + x as isize, top + y as isize)?;
+            (shown > 9).then_some(((x, y), shown))
+        })
+        .collect()
+}
+
+/// Returns the character for a cell as the player sees it.
+fn visible_char(board: &Board, x: isize, y: isize, show_mines: bool) -> char {
+    let Some(cell) = board.get_cell(x, y) else {
+        return '#';
+    };
+    match (cell.state, cell.kind) {
+        (CellState::Hidden, CellKind::Mine(_)) if show_mines => '*',
+        (CellState::Hidden, _) => '#',
+        (CellState::Flagged(_), _) => 'F',
+        (CellState::Question, _) => '?',
+        (CellState::Revealed, CellKind::Mine(_)) => 'X',
+        (CellState::Revealed, CellKind::Number(n)) => {
+            match board.get_shown_number(x, y).unwrap_or(n) {
+                shown @ 0..=9 => (b'0' + shown) as char,
+                _ => '+',
+            }
+        }
+    }
+}
@@ -0,0 +1,462 @@
+//! Head-to-head races on identical boards.
+//!
+//! Every board in a race is dealt from the same seed and starts with the same
+//! opening around the centre revealed, so all racers face the same mines wherever
+//! they click next. The first racer to clear their board wins, and so does the last
+//! one standing once the others have hit a mine.
+//!
+//! Races are played split-screen, each player moving a cursor with their own
+//! [`Keys`], or across terminals through a race room on the [`server`](crate::server),
+//! where opponents are seen by their [`Progress`].
+use crate::game_logic::Board;
+use crate::protocol::VERSION;
+use crate::types::{
+    Action, CellState, DEFAULT_SETTINGS, FirstClick, GameConfig, GameState, Settings,
+};
+use crate::{Error, Result};
+use crossterm::event::KeyCode;
+use serde_json::{Value, json};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// Keys one player moves their cursor and plays with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keys {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    /// Reveals the cell under the cursor, or chords on a revealed number.
+    pub reveal: KeyCode,
+    /// Toggles a flag on the cell under the cursor.
+    pub flag: KeyCode,
+    /// Summary shown above the player's board.
+    pub help: &'static str,
+}
+
+/// Keys for the player on the left of a split screen.
+pub const WASD_KEYS: Keys = Keys {
+    up: KeyCode::Char('w'),
+    down: KeyCode::Char('s'),
+    left: KeyCode::Char('a'),
+    right: KeyCode::Char('d'),
+    reveal: KeyCode::Char('e'),
+    flag: KeyCode::Char('q'),
+    help: "WASD move, E reveal, Q flag",
+};
+/// Keys for the player on the right of a split screen.
+pub const IJKL_KEYS: Keys = Keys {
+    up: KeyCode::Char('i'),
+    down: KeyCode::Char('k'),
+    left: KeyCode::Char('j'),
+    right: KeyCode::Char('l'),
+    reveal: KeyCode::Char('o'),
+    flag: KeyCode::Char('u'),
+    help: "IJKL move, O reveal, U flag",
+};
+/// Keys for a player racing alone at their terminal.
+pub const ARROW_KEYS: Keys = Keys {
+    up: KeyCode::Up,
+    down: KeyCode::Down,
+    left: KeyCode::Left,
+    right: KeyCode::Right,
+    reveal: KeyCode::Char(' '),
+    flag: KeyCode::Char('f'),
+    help: "Arrows move, Space reveal, F flag",
+};
+
+/// How far a racer has got, as their opponents see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Percentage of the safe cells revealed.
+    pub cleared: u8,
+    /// Flags placed.
+    pub flags: usize,
+    /// Ongoing, Won or Lost.
+    pub state: GameState,
+}
+
+impl Progress {
+    /// Returns the progress of a racer playing `board`.
+    pub fn of(board: &Board, state: GameState) -> Progress {
+        let safe = board.get_safe_cells_count().max(1);
+        Progress {
+            cleared: (board.get_revealed_count() * 100 / safe) as u8,
+            flags: board.get_flags_count(),
+            state,
+        }
+    }
+
+    /// Returns whether the racer hasn't hit a mine.
+    pub fn is_alive(&self) -> bool {
+        self.state != GameState::Lost
+    }
+}
+
+/// One player in a race.
+pub struct Racer {
+    pub name: String,
+    /// The racer's board, or None for an opponent playing on another terminal.
+    pub board: Option<Board>,
+    /// Keys the racer plays with, if they play here.
+    pub keys: Option<Keys>,
+    pub progress: Progress,
+}
+
+/// A race between players on boards dealt from the same seed.
+pub struct Race {
+    pub racers: Vec<Racer>,
+    pub seed: u64,
+    winner: Option<usize>,
+}
+
+/// Returns the cell every race board opens with: the centre.
+pub fn start_cell(config: GameConfig) -> (isize, isize) {
+    (
+        (config.width() / 2) as isize,
+        (config.height() / 2) as isize,
+    )
+}
+
+/// Deals a race board: mines placed from `seed`, with the opening around
+/// [`start_cell`] revealed and the cursor on it.
+///
+/// Returns the board and its state, which is Won if the opening cleared it.
+pub fn new_board(config: GameConfig, settings: Settings, seed: u64) -> (Board, GameState) {
+    let mut board = Board::new_with_seed(config, seed);
+    // The opening has to be the same on every board, so it can't depend on a click
+    board.settings = Settings {
+        first_click: FirstClick::Opening,
+        ..settings
+    };
+    let (x, y) = start_cell(config);
+    let state = board
+        .apply_action(Action::Reveal { x, y })
+        .unwrap_or(GameState::Ongoing);
+    board.cursor = Some((x, y));
+    (board, state)
+}
+
+/// Returns the winner of a race from each racer's state: whoever has cleared their
+/// board, or the last one standing once everyone else has lost.
+pub fn decide_winner(states: &[GameState]) -> Option<usize> {
+    if let Some(winner) = states.iter().position(|&state| state == GameState::Won) {
+        return Some(winner);
+    }
+    let mut alive = (0..states.len()).filter(|&racer| states[racer] != GameState::Lost);
+    match (alive.next(), alive.next()) {
+        (Some(winner), None) if states.len() > 1 => Some(winner),
+        _ => None,
+    }
+}
+
+impl Race {
+    /// Starts a split-screen race, one board per player, each played with its own keys.
+    pub fn new(
+        config: GameConfig,
+        settings: Settings,
+        seed: u64,
+        players: &[(&str, Keys)],
+    ) -> Race {
+        let racers = players
+            .iter()
+            .map(|&(name, keys)| {
+                let (board, state) = new_board(config, settings, seed);
+                Racer {
+                    name: name.to_string(),
+                    progress: Progress::of(&board, state),
+                    board: Some(board),
+                    keys: Some(keys),
+                }
+            })
+            .collect();
+        let mut race = Race {
+            racers,
+            seed,
+            winner: None,
+        };
+        race.decide();
+        race
+    }
+
+    /// Starts a race against opponents on other terminals, played with [`ARROW_KEYS`]
+    /// on the board a server race room deals. The local player is racer 0.
+    pub fn online(config: GameConfig, seed: u64, name: &str, opponents: &[String]) -> Race {
+        let (board, state) = new_board(config, DEFAULT_SETTINGS, seed);
+        // Everyone starts from the same opening
+        let progress = Progress::of(&board, state);
+        let mut racers = vec![Racer {
+            name: name.to_string(),
+            board: Some(board),
+            keys: Some(ARROW_KEYS),
+            progress,
+        }];
+        racers.extend(opponents.iter().map(|opponent| Racer {
+            name: opponent.clone(),
+            board: None,
+            keys: None,
+            progress,
+        }));
+        Race {
+            racers,
+            seed,
+            winner: None,
+        }
+    }
+
+    /// Returns the winner, once the race is decided.
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Returns the size of the race boards.
+    pub fn config(&self) -> Option<GameConfig> {
+        self.racers
+            .iter()
+            .find_map(|racer| racer.board.as_ref())
+            .map(Board::get_config)
+    }
+
+    /// Handles a key press for whichever local racer it belongs to, moving their cursor
+    /// or playing the cell under it.
+    ///
+    /// Returns the racer and the action they played, if any. Nothing is played once the
+    /// race is decided.
+    pub fn handle_key(&mut self, code: KeyCode) -> Option<(usize, Action)> {
+        if self.winner.is_some() {
+            return None;
+        }
+        let index = self.racers.iter().position(|racer| {
+            racer.keys.is_some_and(|keys| {
+                [
+                    keys.up,
+                    keys.down,
+                    keys.left,
+                    keys.right,
+                    keys.reveal,
+                    keys.flag,
+                ]
+                .contains(&code)
+            })
+        })?;
+        let racer = &mut self.racers[index];
+        let (Some(board), Some(keys)) = (racer.board.as_mut(), racer.keys) else {
+            return None;
+        };
+        if racer.progress.state != GameState::Ongoing {
+            return None;
+        }
+        let (x, y) = board.cursor.unwrap_or((0, 0));
+        let step = match code {
+            code if code == keys.up => Some((0, -1)),
+            code if code == keys.down => Some((0, 1)),
+            code if code == keys.left => Some((-1, 0)),
+            code if code == keys.right => Some((1, 0)),
+            _ => None,
+        };
+        if let Some((dx, dy)) = step {
+            board.cursor = Some((
+                (x + dx).clamp(0, board.width as isize - 1),
+                (y + dy).clamp(0, board.height as isize - 1),
+            ));
+            return None;
+        }
+        let action = if code == keys.flag {
+            Action::Flag { x, y }
+        } else if board.get_cell(x, y)?.state == CellState::Revealed {
+            Action::Chord { x, y }
+        } else {
+            Action::Reveal { x, y }
+        };
+        if let Some(state) = board.apply_action(action) {
+            racer.progress.state = state;
+        }
+        racer.progress = Progress::of(board, racer.progress.state);
+        self.decide();
+        Some((index, action))
+    }
+
+    /// Settles the winner once it is known. Races with opponents elsewhere are
+    /// decided by the server instead.
+    fn decide(&mut self) {
+        if self.winner.is_none() && self.racers.iter().all(|racer| racer.board.is_some()) {
+            let states: Vec<GameState> = self
+                .racers
+                .iter()
+                .map(|racer| racer.progress.state)
+                .collect();
+            self.winner = decide_winner(&states);
+        }
+    }
+}
+
+/// How long to wait for the server to answer while joining a room.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connection to a race room on a termsweeper server.
+pub struct RaceClient {
+    writer: TcpStream,
+    /// Lines from the server, read on a background thread.
+    lines: Receiver<Value>,
+    name: String,
+}
+
+impl RaceClient {
+    /// Connects to a server and joins race room `room` as `name`, hosting it if
+    /// nobody has yet.
+    ///
+    /// Returns the client and whether it joined someone else's room.
+    pub fn connect(addr: impl ToSocketAddrs, room: &str, name: &str) -> Result<(RaceClient, bool)> {
+        let stream = TcpStream::connect(addr).map_err(Error::Network)?;
+        let reader = stream.try_clone().map_err(Error::Network)?;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if let Ok(value) = serde_json::from_str(&line)
+                    && sender.send(value).is_err()
+                {
+                    break;
+                }
+            }
+        });
+        let mut client = RaceClient {
+            writer: stream,
+            lines,
+            name: name.to_string(),
+        };
+        client.recv()?;
+        let join = json!({ "v": VERSION, "cmd": "join", "room": room, "name": name });
+        let mut response = client.request(&join)?;
+        let joined = response["code"] != "no_room";
+        if !joined {
+            let host =
+                json!({ "v": VERSION, "cmd": "host", "room": room, "name": name, "race": true });
+            response = client.request(&host)?;
+        }
+        if response["type"] == "error" {
+            return Err(refused(response["message"].as_str().unwrap_or_default()));
+        }
+        if response["race"] != true {
+            return Err(refused(&format!("room '{}' is not a race", room)));
+        }
+        // The server tells the room apart from other players by this name
+        if let Some(name) = response["name"].as_str() {
+            client.name = name.to_string();
+        }
+        Ok((client, joined))
+    }
+
+    /// Asks the server to deal a new race to everyone in the room.
+    pub fn start(&mut self, config: GameConfig) -> Result<()> {
+        self.send(&json!({
+            "v": VERSION,
+            "cmd": "new",
+            "width": config.width(),
+            "height": config.height(),
+            "mines": config.mines(),
+        }))
+    }
+
+    /// Sends an action played on the local board.
+    pub fn play(&mut self, action: Action) -> Result<()> {
+        let (cmd, x, y) = match action {
+            Action::Reveal { x, y } => ("reveal", x, y),
+            Action::Flag { x, y } => ("flag", x, y),
+            Action::Chord { x, y } | Action::FlagChord { x, y } => ("chord", x, y),
+        };
+        self.send(&json!({ "v": VERSION, "cmd": cmd, "x": x, "y": y }))
+    }
+
+    /// Applies what the server has sent since the last call: a new race, opponents'
+    /// progress and the result.
+    ///
+    /// Returns an error once the server has gone away.
+    pub fn update(&mut self, race: &mut Option<Race>) -> Result<()> {
+        loop {
+            let message = match self.lines.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => {
+                    return Err(Error::Network(io::ErrorKind::ConnectionAborted.into()));
+                }
+            };
+            match message["type"].as_str() {
+                Some("race") => {
+                    let size = |name: &str| message[name].as_u64().unwrap_or_default() as usize;
+                    let config = GameConfig::new(size("width"), size("height"), size("mines"))?;
+                    let opponents: Vec<String> = message["opponents"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|name| Some(name.as_str()?.to_string()))
+                        .collect();
+                    let seed = message["seed"].as_u64().unwrap_or_default();
+                    *race = Some(Race::online(config, seed, &self.name, &opponents));
+                }
+                Some("progress") => {
+                    if let Some(race) = race
+                        && let Some(racer) = race
+                            .racers
+                            .iter_mut()
+                            .skip(1)
+                            .find(|racer| message["player"] == racer.name.as_str())
+                    {
+                        racer.progress = Progress {
+                            cleared: message["cleared"].as_u64().unwrap_or_default() as u8,
+                            flags: message["flags"].as_u64().unwrap_or_default() as usize,
+                            state: match message["outcome"].as_str() {
+                                Some("won") => GameState::Won,
+                                Some("lost") => GameState::Lost,
+                                _ => GameState::Ongoing,
+                            },
+                        };
+                    }
+                }
+                Some("result") => {
+                    if let Some(race) = race {
+                        race.winner = race
+                            .racers
+                            .iter()
+                            .position(|racer| message["winner"] == racer.name.as_str());
+                    }
+                }
+                // Responses to our own actions: the local board already shows them
+                _ => {}
+            }
+        }
+    }
+
+    /// Sends one request line.
+    fn send(&mut self, request: &Value) -> Result<()> {
+        writeln!(self.writer, "{}", request)
+            .and_then(|()| self.writer.flush())
+            .map_err(Error::Network)
+    }
+
+    /// Sends a request and waits for its response.
+    fn request(&mut self, request: &Value) -> Result<Value> {
+        self.send(request)?;
+        self.recv()
+    }
+
+    /// Waits for the next line from the server.
+    fn recv(&mut self) -> Result<Value> {
+        self.lines.recv_timeout(JOIN_TIMEOUT).map_err(|err| {
+            Error::Network(match err {
+                RecvTimeoutError::Timeout => io::ErrorKind::TimedOut.into(),
+                RecvTimeoutError::Disconnected => io::ErrorKind::ConnectionAborted.into(),
+            })
+        })
+    }
+}
+
+/// Returns the error for a server refusing to let the client race.
+fn refused(message: &str) -> Error {
+    Error::Network(io::Error::other(message.to_string()))
+}
@@ -0,0 +1,216 @@
+//! Recorded games and their playback.
+//!
+//! A [`Replay`] holds the mine positions and the timed [`Action`]s of one game.
+//! Mouse-based recordings from other programs are converted to actions with
+//! [`actions_from_mouse`], and back with [`mouse_from_actions`].
+use crate::Result;
+use crate::game_logic::Board;
+use crate::shape::{Neighbourhood, Shape};
+use crate::topology::Topology;
+use crate::types::{Action, GameState};
+use std::time::Instant;
+
+/// An action and the time it happened, in milliseconds from the start of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayEvent {
+    pub time_ms: u32,
+    pub action: Action,
+}
+
+/// A recorded game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub width: usize,
+    pub height: usize,
+    /// Mine positions as (x, y).
+    pub mines: Vec<(usize, usize)>,
+    pub topology: Topology,
+    pub shape: Shape,
+    pub neighbourhood: Neighbourhood,
+    /// Most mines a cell could hold, which sets how far right click steps a flag.
+    pub mines_per_cell: u8,
+    /// Numbers that lied, as ((x, y), shown number).
+    pub lies: Vec<((usize, usize), u8)>,
+    /// Whether right click cycled through "?" marks.
+    pub question_marks: bool,
+    /// Whether the board was cleared.
+    pub completed: bool,
+    /// The board's 3BV, if the recording stores it.
+    pub bbbv: Option<usize>,
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// Creates a fresh board with the replay's mines and settings.
+    pub fn to_board(&self) -> Result<Board> {
+        let mut board = Board::from_mines(self.width, self.height, &self.mines)?;
+        board.set_topology(self.topology);
+        board.set_shape(self.shape);
+        board.set_neighbourhood(self.neighbourhood);
+        board.settings.mines_per_cell = board.settings.mines_per_cell.max(self.mines_per_cell);
+        board.set_lies(&self.lies);
+        board.settings.question_marks = self.question_marks;
+        Ok(board)
+    }
+
+    /// Returns the time of the last action in milliseconds.
+    pub fn duration_ms(&self) -> u32 {
+        self.events.last().map_or(0, |event| event.time_ms)
+    }
+}
+
+/// A mouse input in a pixel-based recording.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseInput {
+    Move,
+    LeftDown,
+    LeftUp,
+    RightDown,
+    RightUp,
+    MiddleDown,
+    MiddleUp,
+}
+
+/// A timed mouse input at pixel (x, y) relative to the top-left of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MouseRecord {
+    pub time_ms: u32,
+    pub input: MouseInput,
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Converts a mouse recording into actions, using classic Minesweeper semantics.
+///
+/// Releasing left reveals, pressing right flags, pressing both buttons or
+/// releasing middle chords. A left release that ends a two-button chord does not reveal.
+pub fn actions_from_mouse(records: &[MouseRecord], square_size: u16) -> Vec<ReplayEvent> {
+    let square_size = square_size.max(1);
+    let mut events = Vec::new();
+    let (mut left, mut right, mut chorded) = (false, false, false);
+    for record in records {
+        let x = (record.x / square_size) as isize;
+        let y = (record.y / square_size) as isize;
+        let mut push = |action| {
+            events.push(ReplayEvent {
+                time_ms: record.time_ms,
+                action,
+            })
+        };
+        match record.input {
+            MouseInput::Move => {}
+            MouseInput::LeftDown => {
+                left = true;
+                if right {
+                    chorded = true;
+                    push(Action::Chord { x, y });
+                }
+            }
+            MouseInput::RightDown => {
+                right = true;
+                if left {
+                    chorded = true;
+                    push(Action::Chord { x, y });
+                } else {
+                    push(Action::Flag { x, y });
+                }
+            }
+            MouseInput::LeftUp => {
+                if !chorded && !right {
+                    push(Action::Reveal { x, y });
+                }
+                left = false;
+                chorded &= right;
+            }
+            MouseInput::RightUp => {
+                right = false;
+                chorded &= left;
+            }
+            MouseInput::MiddleDown => {}
+            MouseInput::MiddleUp => push(Action::Chord { x, y }),
+        }
+    }
+    events
+}
+
+/// Converts actions into mouse inputs at the centre of each cell.
+///
+/// Produces the inverse of [`actions_from_mouse`]. Flag chords have no mouse
+/// equivalent and are skipped; recorded games store them as single flags.
+pub fn mouse_from_actions(events: &[ReplayEvent], square_size: u16) -> Vec<MouseRecord> {
+    let mut records = Vec::new();
+    for event in events {
+        let (inputs, x, y): (&[MouseInput], isize, isize) = match event.action {
+            Action::Reveal { x, y } => (&[MouseInput::LeftDown, MouseInput::LeftUp], x, y),
+            Action::Flag { x, y } => (&[MouseInput::RightDown, MouseInput::RightUp], x, y),
+            Action::Chord { x, y } => (
+                &[
+                    MouseInput::LeftDown,
+                    MouseInput::RightDown,
+                    MouseInput::RightUp,
+                    MouseInput::LeftUp,
+                ],
+                x,
+                y,
+            ),
+            Action::FlagChord { .. } => continue,
+        };
+        let to_pixel = |cell: isize| (cell.max(0) as u16) * square_size + square_size / 2;
+        records.extend(inputs.iter().map(|&input| MouseRecord {
+            time_ms: event.time_ms,
+            input,
+            x: to_pixel(x),
+            y: to_pixel(y),
+        }));
+    }
+    records
+}
+
+/// Plays a replay back in real time on a board.
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+    started: Instant,
+}
+
+impl Playback {
+    /// Starts playing `replay`, returning the playback and the board to play it on.
+    pub fn start(replay: Replay) -> Result<(Playback, Board)> {
+        let board = replay.to_board()?;
+        let playback = Playback {
+            replay,
+            next: 0,
+            started: Instant::now(),
+        };
+        Ok((playback, board))
+    }
+
+    /// Applies every action that is due by now.
+    ///
+    /// Returns Some(GameState) if the game state changes (win/loss).
+    pub fn advance(&mut self, board: &mut Board) -> Option<GameState> {
+        let elapsed = self.started.elapsed().as_millis();
+        while let Some(event) = self.replay.events.get(self.next) {
+            if event.time_ms as u128 > elapsed {
+                break;
+            }
+            self.next += 1;
+            if let Some(state) = board.apply_action(event.action) {
+                return Some(state);
+            }
+        }
+        None
+    }
+
+    /// Returns true once every action has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.events.len()
+    }
+
+    /// Restarts playback from the first action on a fresh board.
+    pub fn restart(&mut self) -> Result<Board> {
+        self.next = 0;
+        self.started = Instant::now();
+        self.replay.to_board()
+    }
+}
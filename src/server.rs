@@ -0,0 +1,616 @@
+//! Local multiplayer server speaking the bot protocol over TCP.
+//!
+//! `termsweeper serve` listens on a local port. Each connection gets a `hello`
+//! line and then speaks the [`protocol`](crate::protocol) JSON lines, plus lobby
+//! commands to find and share games. A client is in at most one room, and game
+//! commands act on that room's game.
+//!
+//! | `cmd`   | Fields                              | Does                            |
+//! |---------|-------------------------------------|---------------------------------|
+//! | `list`  |                                     | Lists the rooms                 |
+//! | `host`  | `room`; opt. `name`, `race`         | Creates a room and joins it     |
+//! | `join`  | `room`; opt. `name`                 | Joins a room, leaving any other |
+//! | `leave` |                                     | Leaves the current room         |
+//!
+//! `list` is answered with a `lobby` line, and the others with a `room` line naming
+//! the room the client is now in (`null` after `leave`), how many players it has,
+//! whether it is a race, and the client's name in it. Names default to `player <n>`
+//! and are made unique within a room by a number.
+//!
+//! ```text
+//! {"v":1,"type":"lobby","rooms":[{"room":"friday","players":2,"race":false,"width":9,"height":9,"mines":10,"outcome":"playing"}]}
+//! {"v":1,"type":"room","room":"friday","players":2,"race":false,"name":"player 2"}
+//! ```
+//!
+//! # Shared rooms
+//!
+//! Everyone in a room plays the same board, cooperatively. Requests are applied one at
+//! a time in the order they arrive, see [`coop`](crate::coop), and a mine hit by
+//! anyone ends the game for the whole room. The client sending a game command gets
+//! the usual response, and every other player is sent the new `state` line. State
+//! lines in shared rooms also carry each player's contribution, with `flags` counting
+//! the flags on the board they placed:
+//!
+//! ```text
+//! {"v":1,"type":"state",...,"players":[{"name":"ana","revealed":31,"flags":2,"chords":1,"mines_hit":0}]}
+//! ```
+//!
+//! # Race rooms
+//!
+//! Hosting with `"race":true` gives each player their own board. `new` deals every
+//! player in the room a board from the same seed (a random one if none is given)
+//! with the opening around the centre revealed, see [`race`](crate::race). Each
+//! player is sent a `race` line with the seed and their opponents' names, so
+//! clients can show the board themselves, and then their `state`:
+//!
+//! ```text
+//! {"v":1,"type":"race","seed":42,"width":9,"height":9,"mines":10,"opponents":["ana"]}
+//! ```
+//!
+//! After each of a player's moves, the others are sent their `progress`, and once
+//! someone has cleared their board or everyone else has hit a mine, every player
+//! is sent the `result`:
+//!
+//! ```text
+//! {"v":1,"type":"progress","player":"ana","cleared":42,"flags":3,"alive":true,"outcome":"playing"}
+//! {"v":1,"type":"result","winner":"ana","won":false}
+//! ```
+//!
+//! # Errors
+//!
+//! Besides the protocol's error codes, the server answers `room_exists` when hosting
+//! a taken name, `no_room` when joining a room that doesn't exist, and `not_in_room`
+//! for game commands and `leave` outside a room. A room closes when its last player
+//! leaves or disconnects. A client sending a line longer than [`MAX_LINE`] bytes is
+//! disconnected.
+use crate::protocol::{self, COMMANDS, Session, VERSION};
+use crate::race::{self, Progress};
+use crate::types::DEFAULT_SETTINGS;
+use crate::{Error, Result};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// Port `termsweeper serve` listens on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7878;
+/// Address `termsweeper serve` listens on unless told otherwise: this machine only.
+pub const DEFAULT_ADDR: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+/// Commands for finding and sharing games, on top of the game [`COMMANDS`].
+pub const LOBBY_COMMANDS: [&str; 4] = ["list", "host", "join", "leave"];
+/// Longest request line a client may send, in bytes, newline included.
+pub const MAX_LINE: usize = 64 * 1024;
+/// How long a write to a client may stall before the client is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// A TCP server hosting rooms of shared games and races.
+pub struct Server {
+    listener: TcpListener,
+    lobby: Arc<Mutex<Lobby>>,
+}
+
+/// Rooms and connected clients, shared by all client threads.
+#[derive(Default)]
+struct Lobby {
+    rooms: BTreeMap<String, Room>,
+    /// Lines waiting for each client's writer thread.
+    clients: HashMap<usize, Sender<String>>,
+    /// Room each client is in.
+    joined: HashMap<usize, String>,
+    /// Name each client goes by in its room.
+    names: HashMap<usize, String>,
+    next_client: usize,
+}
+
+/// A game and the clients playing it.
+struct Room {
+    game: Game,
+    players: Vec<usize>,
+}
+
+/// The game played in a room.
+enum Game {
+    /// One board everyone plays.
+    Shared(Box<Session>),
+    /// A board per player, all dealt from the same seed.
+    Race {
+        boards: HashMap<usize, Session>,
+        /// Whoever won the current race, once it is decided.
+        winner: Option<usize>,
+    },
+}
+
+/// Lines to send, and who to send them to.
+type Outbox = Vec<(usize, String)>;
+
+impl Server {
+    /// Listens on the given address. Port 0 picks a free port.
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Server> {
+        Ok(Server {
+            listener: TcpListener::bind(addr).map_err(Error::Network)?,
+            lobby: Arc::default(),
+        })
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.listener.local_addr().map_err(Error::Network)
+    }
+
+    /// Accepts clients until the listener fails, serving each on its own thread.
+    pub fn run(&self) -> Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream.map_err(Error::Network)?;
+            let lobby = Arc::clone(&self.lobby);
+            thread::spawn(move || serve_client(&lobby, stream));
+        }
+        Ok(())
+    }
+}
+
+/// Answers one client's requests until it disconnects, then takes it out of its room.
+fn serve_client(lobby: &Mutex<Lobby>, stream: TcpStream) {
+    let Ok(writer) = stream.try_clone() else {
+        return;
+    };
+    if writer.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+        return;
+    }
+    let client = lock(lobby).connect(writer);
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        // Reading one byte past the limit tells a full line from one cut short
+        match (&mut reader).take(MAX_LINE as u64 + 1).read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(read) if read > MAX_LINE => break,
+            Ok(_) => {}
+        }
+        lock(lobby).handle_line(client, line.trim_end_matches(['\r', '\n']));
+    }
+    lock(lobby).disconnect(client);
+}
+
+/// Writes a client's lines in order until its channel closes, so that no one waits
+/// on a slow client while holding the lobby. A client that went away or doesn't
+/// keep up is shut down, which ends its reading thread and takes it out of its room.
+fn write_lines(mut writer: TcpStream, lines: Receiver<String>) {
+    for line in lines {
+        if writeln!(writer, "{}", line)
+            .and_then(|()| writer.flush())
+            .is_err()
+        {
+            let _ = writer.shutdown(Shutdown::Both);
+            return;
+        }
+    }
+}
+
+/// Locks the lobby, carrying on if a client thread panicked while holding it.
+fn lock(lobby: &Mutex<Lobby>) -> MutexGuard<'_, Lobby> {
+    lobby
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Lobby {
+    /// Registers a client and greets it, returning its id.
+    fn connect(&mut self, writer: TcpStream) -> usize {
+        let client = self.next_client;
+        self.next_client += 1;
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || write_lines(writer, lines));
+        self.clients.insert(client, sender);
+        self.names.insert(client, format!("player {}", client + 1));
+        let commands: Vec<&str> = COMMANDS.iter().chain(&LOBBY_COMMANDS).copied().collect();
+        let hello = json!({
+            "v": VERSION,
+            "type": "hello",
+            "protocol": "termsweeper",
+            "commands": commands,
+        });
+        self.send(client, &hello.to_string());
+        client
+    }
+
+    /// Forgets a client, leaving its room.
+    fn disconnect(&mut self, client: usize) {
+        self.leave(client);
+        self.clients.remove(&client);
+        self.names.remove(&client);
+    }
+
+    /// Answers one request line, and tells the room's other players what changed.
+    fn handle_line(&mut self, client: usize, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        let request = match serde_json::from_str(line) {
+            Ok(Value::Object(request)) => Some(request),
+            _ => None,
+        };
+        let id = request
+            .as_ref()
+            .and_then(|request| request.get("id").cloned());
+        let cmd = request
+            .as_ref()
+            .and_then(|request| request.get("cmd"))
+            .and_then(Value::as_str);
+        if let Some(version) = request.as_ref().and_then(|request| request.get("v"))
+            && version.as_u64() != Some(VERSION)
+        {
+            let message = format!("this engine speaks version {}, got {}", VERSION, version);
+            self.send(
+                client,
+                &protocol::error_line("unsupported_version", &message, id),
+            );
+            return;
+        }
+        if let (Some(request), Some(cmd)) = (&request, cmd)
+            && LOBBY_COMMANDS.contains(&cmd)
+        {
+            let response = match self.lobby_command(client, cmd, request) {
+                Ok(mut response) => {
+                    if let Some(id) = id {
+                        response["id"] = id;
+                    }
+                    response.to_string()
+                }
+                Err((code, message)) => protocol::error_line(code, &message, id),
+            };
+            self.send(client, &response);
+            return;
+        }
+        let Some(name) = self.joined.get(&client).cloned() else {
+            let response = match cmd {
+                Some(cmd) if COMMANDS.contains(&cmd) => {
+                    protocol::error_line("not_in_room", "host or join a room first", id)
+                }
+                // Anything else is an error whatever room it's sent to
+                _ => Session::new().handle_line(line).unwrap_or_default(),
+            };
+            self.send(client, &response);
+            return;
+        };
+        let outbox = match (cmd, &request) {
+            (Some("new"), Some(request)) if self.is_race(&name) => {
+                self.start_race(client, &name, request, id)
+            }
+            _ => self.play(client, &name, line),
+        };
+        for (player, line) in outbox {
+            self.send(player, &line);
+        }
+    }
+
+    /// Returns whether a room is a race room.
+    fn is_race(&self, name: &str) -> bool {
+        self.rooms
+            .get(name)
+            .is_some_and(|room| matches!(room.game, Game::Race { .. }))
+    }
+
+    /// Passes a game command to the client's board in its room, and tells the other
+    /// players about any change: the board in a shared room, or progress and the
+    /// result in a race.
+    fn play(&mut self, client: usize, name: &str, line: &str) -> Outbox {
+        let names = &self.names;
+        let Some(room) = self.rooms.get_mut(name) else {
+            return Vec::new();
+        };
+        let others: Vec<usize> = room
+            .players
+            .iter()
+            .copied()
+            .filter(|&player| player != client)
+            .collect();
+        let mut outbox = Vec::new();
+        match &mut room.game {
+            Game::Shared(session) => {
+                let updates = session.updates();
+                let response = session.handle_line_from(client, line).unwrap_or_default();
+                outbox.push((
+                    client,
+                    with_players(response, session, &room.players, names),
+                ));
+                if session.updates() != updates
+                    && let Some(update) = session.state_line()
+                {
+                    let update = with_players(update, session, &room.players, names);
+                    outbox.extend(others.iter().map(|&player| (player, update.clone())));
+                }
+            }
+            Game::Race { boards, winner } => {
+                let session = boards.entry(client).or_default();
+                let updates = session.updates();
+                outbox.push((client, session.handle_line(line).unwrap_or_default()));
+                if session.updates() == updates {
+                    return outbox;
+                }
+                if let Some(board) = session.board() {
+                    let progress = Progress::of(board, session.state());
+                    let update = json!({
+                        "v": VERSION,
+                        "type": "progress",
+                        "player": names.get(&client),
+                        "cleared": progress.cleared,
+                        "flags": progress.flags,
+                        "alive": progress.is_alive(),
+                        "outcome": session.outcome(),
+                    })
+                    .to_string();
+                    outbox.extend(others.iter().map(|&player| (player, update.clone())));
+                }
+                if winner.is_none() {
+                    // Players who joined after the deal sit the race out
+                    let racers: Vec<usize> = room
+                        .players
+                        .iter()
+                        .copied()
+                        .filter(|player| boards.get(player).is_some_and(|s| s.board().is_some()))
+                        .collect();
+                    let states: Vec<_> =
+                        racers.iter().map(|player| boards[player].state()).collect();
+                    *winner = race::decide_winner(&states).map(|index| racers[index]);
+                    if let Some(winner) = *winner {
+                        outbox.extend(room.players.iter().map(|&player| {
+                            let result = json!({
+                                "v": VERSION,
+                                "type": "result",
+                                "winner": names.get(&winner),
+                                "won": player == winner,
+                            });
+                            (player, result.to_string())
+                        }));
+                    }
+                }
+            }
+        }
+        outbox
+    }
+
+    /// Deals every player in a race room a new board from the same seed.
+    fn start_race(
+        &mut self,
+        client: usize,
+        name: &str,
+        request: &Map<String, Value>,
+        id: Option<Value>,
+    ) -> Outbox {
+        let (config, seed) = match protocol::parse_new(request) {
+            Ok(parsed) => parsed,
+            Err(err) => return vec![(client, protocol::error_line(err.code, &err.message, id))],
+        };
+        let seed = seed.unwrap_or_else(rand::random);
+        let names = &self.names;
+        let Some(Room {
+            game: Game::Race { boards, winner },
+            players,
+        }) = self.rooms.get_mut(name)
+        else {
+            return Vec::new();
+        };
+        *winner = None;
+        let mut outbox = Vec::new();
+        for &player in players.iter() {
+            let (board, state) = race::new_board(config, DEFAULT_SETTINGS, seed);
+            let session = boards.entry(player).or_default();
+            session.set_board(board, state);
+            let opponents: Vec<&String> = players
+                .iter()
+                .filter(|&&other| other != player)
+                .filter_map(|other| names.get(other))
+                .collect();
+            let start = json!({
+                "v": VERSION,
+                "type": "race",
+                "seed": seed,
+                "width": config.width(),
+                "height": config.height(),
+                "mines": config.mines(),
+                "opponents": opponents,
+            });
+            let mut state: Value = session
+                .state_line()
+                .and_then(|line| serde_json::from_str(&line).ok())
+                .unwrap_or_default();
+            if player == client
+                && let Some(id) = &id
+            {
+                state["id"] = id.clone();
+            }
+            outbox.push((player, start.to_string()));
+            outbox.push((player, state.to_string()));
+        }
+        outbox
+    }
+
+    /// Carries out a lobby command, returning its response or an error code and message.
+    fn lobby_command(
+        &mut self,
+        client: usize,
+        cmd: &str,
+        request: &Map<String, Value>,
+    ) -> std::result::Result<Value, (&'static str, String)> {
+        let room_name = || {
+            request
+                .get("room")
+                .and_then(Value::as_str)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .ok_or(("bad_request", "missing room name".to_string()))
+        };
+        let player_name = request
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty());
+        match cmd {
+            "list" => {
+                let rooms: Vec<Value> = self
+                    .rooms
+                    .iter()
+                    .map(|(name, room)| {
+                        let mut listing = json!({
+                            "room": name,
+                            "players": room.players.len(),
+                            "race": matches!(room.game, Game::Race { .. }),
+                        });
+                        let board = match &room.game {
+                            Game::Shared(session) => {
+                                listing["outcome"] = json!(session.outcome());
+                                session.board()
+                            }
+                            Game::Race { boards, winner } => {
+                                listing["winner"] =
+                                    json!(winner.and_then(|winner| self.names.get(&winner)));
+                                boards.values().find_map(Session::board)
+                            }
+                        };
+                        if let Some(board) = board {
+                            listing["width"] = json!(board.width);
+                            listing["height"] = json!(board.height);
+                            listing["mines"] = json!(board.get_config().mines());
+                        }
+                        listing
+                    })
+                    .collect();
+                return Ok(json!({ "v": VERSION, "type": "lobby", "rooms": rooms }));
+            }
+            "host" => {
+                let name = room_name()?;
+                if self.rooms.contains_key(&name) {
+                    return Err(("room_exists", format!("room '{}' already exists", name)));
+                }
+                self.leave(client);
+                let game = if request.get("race") == Some(&Value::Bool(true)) {
+                    Game::Race {
+                        boards: HashMap::new(),
+                        winner: None,
+                    }
+                } else {
+                    Game::Shared(Box::default())
+                };
+                self.rooms.insert(
+                    name.clone(),
+                    Room {
+                        game,
+                        players: Vec::new(),
+                    },
+                );
+                self.enter(client, &name, player_name);
+            }
+            "join" => {
+                let name = room_name()?;
+                if !self.rooms.contains_key(&name) {
+                    return Err(("no_room", format!("no room named '{}'", name)));
+                }
+                if self.joined.get(&client) != Some(&name) {
+                    self.leave(client);
+                    self.enter(client, &name, player_name);
+                }
+            }
+            _ => {
+                if !self.leave(client) {
+                    return Err(("not_in_room", "not in a room".to_string()));
+                }
+            }
+        }
+        let name = self.joined.get(&client);
+        let room = name.and_then(|name| self.rooms.get(name));
+        Ok(json!({
+            "v": VERSION,
+            "type": "room",
+            "room": name,
+            "players": room.map_or(0, |room| room.players.len()),
+            "race": room.is_some_and(|room| matches!(room.game, Game::Race { .. })),
+            "name": self.names.get(&client),
+        }))
+    }
+
+    /// Puts a client in a room under a name no other player there has.
+    fn enter(&mut self, client: usize, name: &str, player_name: Option<&str>) {
+        let Some(room) = self.rooms.get_mut(name) else {
+            return;
+        };
+        let wanted = player_name
+            .map(str::to_string)
+            .or_else(|| self.names.get(&client).cloned())
+            .unwrap_or_default();
+        let taken = |candidate: &str| {
+            room.players
+                .iter()
+                .any(|player| self.names.get(player).is_some_and(|name| name == candidate))
+        };
+        let mut unique = wanted.clone();
+        let mut number = 2;
+        while taken(&unique) {
+            unique = format!("{} {}", wanted, number);
+            number += 1;
+        }
+        room.players.push(client);
+        self.names.insert(client, unique);
+        self.joined.insert(client, name.to_string());
+    }
+
+    /// Takes a client out of its room, closing the room if it was the last player.
+    /// Returns whether the client was in a room.
+    fn leave(&mut self, client: usize) -> bool {
+        let Some(name) = self.joined.remove(&client) else {
+            return false;
+        };
+        if let Some(room) = self.rooms.get_mut(&name) {
+            room.players.retain(|&player| player != client);
+            if let Game::Race { boards, .. } = &mut room.game {
+                boards.remove(&client);
+            }
+            if room.players.is_empty() {
+                self.rooms.remove(&name);
+            }
+        }
+        true
+    }
+
+    /// Queues a line for a client's writer thread.
+    fn send(&self, client: usize, line: &str) {
+        if let Some(sender) = self.clients.get(&client) {
+            // A client whose writer gave up is cleaned up by its own thread
+            let _ = sender.send(line.to_string());
+        }
+    }
+}
+
+/// Adds what each player in a shared room has done to a `state` line. Other lines
+/// are returned as they are.
+fn with_players(
+    line: String,
+    session: &Session,
+    players: &[usize],
+    names: &HashMap<usize, String>,
+) -> String {
+    let Ok(mut state) = serde_json::from_str::<Value>(&line) else {
+        return line;
+    };
+    if state["type"] != "state" {
+        return line;
+    }
+    let crew = session.crew();
+    state["players"] = players
+        .iter()
+        .map(|&player| {
+            let contribution = crew.contribution(player);
+            json!({
+                "name": names.get(&player),
+                "revealed": contribution.revealed,
+                "flags": crew.flags_of(player),
+                "chords": contribution.chords,
+                "mines_hit": contribution.mines_hit,
+            })
+        })
+        .collect();
+    state.to_string()
+}
@@ -0,0 +1,167 @@
+//! Cell shapes and neighbourhoods: which cells a number counts mines over.
+//!
+//! Cells are squares with 8 neighbours or hexagons with 6. Hex boards are stored
+//! row-major like square ones, with every odd row drawn half a cell to the right
+//! ("odd-r" offset rows), so a hex cell's neighbours depend on whether its row is
+//! even or odd. Square boards can also count over a [`Neighbourhood`] other than
+//! the 8 touching cells.
+
+/// The shape of the cells on a board.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Shape {
+    /// Square cells touching 8 others.
+    #[default]
+    Square,
+    /// Hexagonal cells touching 6 others, in offset rows.
+    Hex,
+}
+
+/// Neighbour offsets of a square cell.
+const SQUARE_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+/// Neighbour offsets of the orthogonal cross.
+const CROSS_OFFSETS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+/// Neighbour offsets one knight's move away.
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [
+    (-1, -2),
+    (1, -2),
+    (-2, -1),
+    (2, -1),
+    (-2, 1),
+    (2, 1),
+    (-1, 2),
+    (1, 2),
+];
+/// Neighbour offsets within two cells in each direction.
+const RADIUS_2_OFFSETS: [(isize, isize); 24] = {
+    let mut offsets = [(0, 0); 24];
+    let mut index = 0;
+    let mut dy = -2;
+    while dy <= 2 {
+        let mut dx = -2;
+        while dx <= 2 {
+            if dx != 0 || dy != 0 {
+                offsets[index] = (dx, dy);
+                index += 1;
+            }
+            dx += 1;
+        }
+        dy += 1;
+    }
+    offsets
+};
+/// Neighbour offsets of a hex cell on an even row, which is not shifted.
+const HEX_EVEN_OFFSETS: [(isize, isize); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+/// Neighbour offsets of a hex cell on an odd row, shifted half a cell right.
+const HEX_ODD_OFFSETS: [(isize, isize); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+impl Shape {
+    /// All shapes, in settings menu order.
+    pub const ALL: [Shape; 2] = [Shape::Square, Shape::Hex];
+    /// Display names, matching the order of [`Shape::ALL`].
+    pub const NAMES: [&'static str; 2] = ["Square", "Hex"];
+
+    /// Returns the name used in layout files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Shape::Square => "square",
+            Shape::Hex => "hex",
+        }
+    }
+
+    /// Returns the shape with the given layout file name.
+    pub fn from_key(key: &str) -> Option<Shape> {
+        Shape::ALL.into_iter().find(|shape| shape.key() == key)
+    }
+
+    /// Returns the (dx, dy) offsets of the neighbours of a cell on row `y`.
+    pub fn offsets(self, y: isize) -> &'static [(isize, isize)] {
+        match self {
+            Shape::Square => &SQUARE_OFFSETS,
+            Shape::Hex if y.rem_euclid(2) == 0 => &HEX_EVEN_OFFSETS,
+            Shape::Hex => &HEX_ODD_OFFSETS,
+        }
+    }
+
+    /// Returns how many terminal columns row `y` is shifted right when drawn.
+    ///
+    /// Cells are two columns wide, so a hex row is shifted by half a cell.
+    pub fn indent(self, y: isize) -> u16 {
+        match self {
+            Shape::Hex if y.rem_euclid(2) == 1 => 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns the extra terminal columns a board needs for its shifted rows.
+    pub fn extra_width(self) -> u16 {
+        match self {
+            Shape::Square => 0,
+            Shape::Hex => 1,
+        }
+    }
+}
+
+/// The cells a number counts mines over, relative to its own.
+///
+/// The alternatives to [`Neighbourhood::Touching`] are defined on square cells;
+/// hex boards always count their 6 touching cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The cells sharing an edge or corner.
+    #[default]
+    Touching,
+    /// The 4 cells sharing an edge.
+    Cross,
+    /// The 8 cells a knight's move away.
+    Knight,
+    /// The 24 cells within two rows and columns.
+    Radius2,
+}
+
+impl Neighbourhood {
+    /// All neighbourhoods, in settings menu order.
+    pub const ALL: [Neighbourhood; 4] = [
+        Neighbourhood::Touching,
+        Neighbourhood::Cross,
+        Neighbourhood::Knight,
+        Neighbourhood::Radius2,
+    ];
+    /// Display names, matching the order of [`Neighbourhood::ALL`].
+    pub const NAMES: [&'static str; 4] = ["Touching", "Cross", "Knight", "Radius 2"];
+
+    /// Returns the name used in layout files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Neighbourhood::Touching => "touching",
+            Neighbourhood::Cross => "cross",
+            Neighbourhood::Knight => "knight",
+            Neighbourhood::Radius2 => "radius2",
+        }
+    }
+
+    /// Returns the neighbourhood with the given layout file name.
+    pub fn from_key(key: &str) -> Option<Neighbourhood> {
+        Neighbourhood::ALL
+            .into_iter()
+            .find(|neighbourhood| neighbourhood.key() == key)
+    }
+
+    /// Returns the (dx, dy) offsets of the neighbours of a cell on row `y`.
+    pub fn offsets(self, shape: Shape, y: isize) -> &'static [(isize, isize)] {
+        match (shape, self) {
+            (Shape::Hex, _) | (Shape::Square, Neighbourhood::Touching) => shape.offsets(y),
+            (Shape::Square, Neighbourhood::Cross) => &CROSS_OFFSETS,
+            (Shape::Square, Neighbourhood::Knight) => &KNIGHT_OFFSETS,
+            (Shape::Square, Neighbourhood::Radius2) => &RADIUS_2_OFFSETS,
+        }
+    }
+}
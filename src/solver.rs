@@ -0,0 +1,384 @@
+//! Exact mine probabilities for the cells a player can't see.
+//!
+//! Every revealed number constrains its hidden neighbours. The hidden cells next to
+//! numbers are split into groups that share no number, and every way of placing
+//! mines in each group that satisfies its numbers is counted, by how many mines it
+//! uses. Cells away from the numbers are only constrained by the mine count, so an
+//! arrangement leaving `r` mines for `o` such cells stands for `C(o, r)` boards.
+//! Weighing the arrangements this way gives the chance of a mine in each cell over
+//! every board consistent with what the player can see.
+//!
+//! Flags and revealed mines are taken to be right, and each cell is taken to hold at
+//! most one mine.
+use crate::agent::{Seen, View};
+
+/// Most arrangements tried for one group of cells before giving up on it and
+/// treating its cells as if no number touched them.
+const SEARCH_LIMIT: usize = 2_000_000;
+
+/// The chance of a mine in each cell of a [`View`].
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    /// Per cell: the chance of a mine, or None for a cell that isn't hidden.
+    probabilities: Vec<Option<f64>>,
+    /// Whether every arrangement was counted.
+    exact: bool,
+}
+
+/// A revealed number and the hidden cells it counts.
+struct Constraint {
+    cells: Vec<usize>,
+    mines: usize,
+}
+
+/// How one group of cells can hold its mines: for each number of mines, the
+/// arrangements using that many and how many of them put a mine in each cell.
+struct Group {
+    cells: Vec<usize>,
+    arrangements: Vec<f64>,
+    mines_in: Vec<Vec<f64>>,
+    exact: bool,
+}
+
+impl Analysis {
+    /// Works out the chance of a mine in every hidden cell of `view`.
+    pub fn of(view: &View) -> Analysis {
+        let cells = view.width * view.height;
+        let mut probabilities = vec![None; cells];
+        let hidden: Vec<usize> = (0..cells)
+            .filter(|&index| view.seen(index) == Seen::Hidden)
+            .collect();
+        let known_mines = (0..cells)
+            .filter(|&index| matches!(view.seen(index), Seen::Flagged | Seen::Mine))
+            .count();
+        let remaining = view.mines.saturating_sub(known_mines);
+
+        let constraints = constraints(view);
+        // A partly searched group could make any of its cells look safe or mined
+        let (groups, unsearched): (Vec<Group>, Vec<Group>) = split(&constraints, cells)
+            .into_iter()
+            .map(|(group_cells, group_constraints)| count(group_cells, &group_constraints))
+            .partition(|group| group.exact);
+        let exact = unsearched.is_empty();
+        let frontier: usize = groups.iter().map(|group| group.cells.len()).sum();
+        let others = hidden.len() - frontier;
+        // ln C(others, remaining - k) for each number k of mines next to numbers
+        let weights = |mines: usize| -> f64 {
+            if mines > remaining || remaining - mines > others {
+                return f64::NEG_INFINITY;
+            }
+            ln_choose(others, remaining - mines)
+        };
+
+        let all = convolve(groups.iter().map(|group| group.arrangements.as_slice()));
+        let max_weight = (0..all.len())
+            .filter(|&k| all[k] > 0.0)
+            .map(weights)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if max_weight == f64::NEG_INFINITY {
+            // Nothing fits what is on the board, so every hidden cell is as likely
+            let chance = remaining as f64 / hidden.len().max(1) as f64;
+            for &index in &hidden {
+                probabilities[index] = Some(chance.min(1.0));
+            }
+            return Analysis {
+                probabilities,
+                exact: false,
+            };
+        }
+        let weight = |mines: usize| (weights(mines) - max_weight).exp();
+        let total: f64 = (0..all.len()).map(|k| all[k] * weight(k)).sum();
+
+        for &index in &hidden {
+            probabilities[index] = Some(0.0);
+        }
+        // Cells away from the numbers share the mines the numbers leave over
+        if others > 0 {
+            let expected: f64 = (0..all.len())
+                .map(|k| all[k] * weight(k) * remaining.saturating_sub(k) as f64)
+                .sum();
+            let chance = expected / total / others as f64;
+            for &index in &hidden {
+                probabilities[index] = Some(chance);
+            }
+        }
+        for (i, group) in groups.iter().enumerate() {
+            let rest = convolve(
+                groups
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, group)| group.arrangements.as_slice()),
+            );
+            // Weight of the other groups and cells, given this group holds k mines
+            let with: Vec<f64> = (0..group.arrangements.len())
+                .map(|k| {
+                    (0..rest.len())
+                        .map(|other| rest[other] * weight(k + other))
+                        .sum()
+                })
+                .collect();
+            for (c, &index) in group.cells.iter().enumerate() {
+                let mined: f64 = (0..group.arrangements.len())
+                    .map(|k| group.mines_in[k][c] * with[k])
+                    .sum();
+                probabilities[index] = Some(mined / total);
+            }
+        }
+        Analysis {
+            probabilities,
+            exact,
+        }
+    }
+
+    /// Returns the chance of a mine at (x, y), or None if the cell isn't hidden.
+    pub fn probability(&self, view: &View, x: isize, y: isize) -> Option<f64> {
+        self.probabilities[view.index(x, y)?]
+    }
+
+    /// Returns whether every arrangement was counted. Very large groups of
+    /// undecided cells are given up on and read as if no number touched them.
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    /// Returns the hidden cells that can't hold a mine.
+    ///
+    /// None are certain when the analysis isn't exact, since an arrangement left
+    /// out of the search may have a mine there.
+    pub fn safe_cells(&self, view: &View) -> Vec<(isize, isize)> {
+        self.cells_where(view, |chance| chance <= 0.0)
+    }
+
+    /// Returns the hidden cells that must hold a mine, or none if the analysis
+    /// isn't exact.
+    pub fn mines(&self, view: &View) -> Vec<(isize, isize)> {
+        self.cells_where(view, |chance| chance >= 1.0 - 1e-9)
+    }
+
+    /// Returns the hidden cell least likely to hold a mine, and the chance it does.
+    pub fn best_guess(&self, view: &View) -> Option<((isize, isize), f64)> {
+        self.probabilities
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chance)| Some((index, (*chance)?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, chance)| (view.coords(index), chance))
+    }
+
+    /// Returns the hidden cells whose chance of a mine passes `keep`, if the
+    /// analysis is exact.
+    fn cells_where(&self, view: &View, keep: impl Fn(f64) -> bool) -> Vec<(isize, isize)> {
+        if !self.exact {
+            return Vec::new();
+        }
+        self.probabilities
+            .iter()
+            .enumerate()
+            .filter(|(_, chance)| chance.is_some_and(&keep))
+            .map(|(index, _)| view.coords(index))
+            .collect()
+    }
+}
+
+/// Returns what each revealed number says about its hidden neighbours.
+fn constraints(view: &View) -> Vec<Constraint> {
+    (0..view.width * view.height)
+        .filter_map(|index| {
+            let Seen::Revealed(number) = view.seen(index) else {
+                return None;
+            };
+            let neighbours = view.neighbours_of(index);
+            let cells: Vec<usize> = neighbours
+                .iter()
+                .copied()
+                .filter(|&other| view.seen(other) == Seen::Hidden)
+                .collect();
+            if cells.is_empty() {
+                return None;
+            }
+            let known = neighbours
+                .iter()
+                .filter(|&&other| matches!(view.seen(other), Seen::Flagged | Seen::Mine))
+                .count();
+            Some(Constraint {
+                cells,
+                mines: (number as usize).saturating_sub(known),
+            })
+        })
+        .collect()
+}
+
+/// Splits the constrained cells into groups sharing no constraint.
+fn split(constraints: &[Constraint], cells: usize) -> Vec<(Vec<usize>, Vec<&Constraint>)> {
+    let mut parent: Vec<usize> = (0..cells).collect();
+    fn root(parent: &mut [usize], mut cell: usize) -> usize {
+        while parent[cell] != cell {
+            parent[cell] = parent[parent[cell]];
+            cell = parent[cell];
+        }
+        cell
+    }
+    for constraint in constraints {
+        let first = root(&mut parent, constraint.cells[0]);
+        for &cell in &constraint.cells[1..] {
+            let other = root(&mut parent, cell);
+            parent[other] = first;
+        }
+    }
+    let mut groups: Vec<(usize, Vec<usize>, Vec<&Constraint>)> = Vec::new();
+    let mut seen = vec![false; cells];
+    for constraint in constraints {
+        let group_root = root(&mut parent, constraint.cells[0]);
+        let position = match groups.iter().position(|group| group.0 == group_root) {
+            Some(position) => position,
+            None => {
+                groups.push((group_root, Vec::new(), Vec::new()));
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[position];
+        group.2.push(constraint);
+        // Cells in the order the numbers reach them, so the search fails early
+        for &cell in &constraint.cells {
+            if !seen[cell] {
+                seen[cell] = true;
+                group.1.push(cell);
+            }
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, cells, constraints)| (cells, constraints))
+        .collect()
+}
+
+/// Counts the arrangements of mines in a group that satisfy all its constraints.
+fn count(cells: Vec<usize>, constraints: &[&Constraint]) -> Group {
+    let position = |cell: usize| cells.iter().position(|&other| other == cell);
+    let members: Vec<Vec<usize>> = constraints
+        .iter()
+        .map(|constraint| {
+            constraint
+                .cells
+                .iter()
+                .filter_map(|&c| position(c))
+                .collect()
+        })
+        .collect();
+    // Constraints each cell is in
+    let mut touching = vec![Vec::new(); cells.len()];
+    for (i, cells) in members.iter().enumerate() {
+        for &cell in cells {
+            touching[cell].push(i);
+        }
+    }
+    let mut search = Search {
+        needed: constraints
+            .iter()
+            .map(|constraint| constraint.mines)
+            .collect(),
+        open: members.iter().map(Vec::len).collect(),
+        touching,
+        assignment: vec![false; cells.len()],
+        arrangements: vec![0.0; cells.len() + 1],
+        mines_in: vec![vec![0.0; cells.len()]; cells.len() + 1],
+        steps: 0,
+    };
+    search.place(0, 0);
+    Group {
+        exact: search.steps <= SEARCH_LIMIT,
+        cells,
+        arrangements: search.arrangements,
+        mines_in: search.mines_in,
+    }
+}
+
+/// Backtracking over a group's cells, mine or not, one at a time.
+struct Search {
+    /// Mines each constraint still needs.
+    needed: Vec<usize>,
+    /// Cells each constraint still has undecided.
+    open: Vec<usize>,
+    touching: Vec<Vec<usize>>,
+    assignment: Vec<bool>,
+    arrangements: Vec<f64>,
+    mines_in: Vec<Vec<f64>>,
+    steps: usize,
+}
+
+impl Search {
+    fn place(&mut self, cell: usize, mines: usize) {
+        self.steps += 1;
+        if self.steps > SEARCH_LIMIT {
+            return;
+        }
+        if cell == self.assignment.len() {
+            if self.needed.iter().any(|&needed| needed > 0) {
+                return;
+            }
+            self.arrangements[mines] += 1.0;
+            for (c, &mine) in self.assignment.iter().enumerate() {
+                if mine {
+                    self.mines_in[mines][c] += 1.0;
+                }
+            }
+            return;
+        }
+        for mine in [false, true] {
+            let touching = std::mem::take(&mut self.touching[cell]);
+            let fits = touching.iter().all(|&i| {
+                let needed = self.needed[i];
+                if mine {
+                    needed > 0
+                } else {
+                    // The other open cells can still make up the count
+                    needed < self.open[i]
+                }
+            });
+            if fits {
+                for &i in &touching {
+                    self.open[i] -= 1;
+                    if mine {
+                        self.needed[i] -= 1;
+                    }
+                }
+                self.assignment[cell] = mine;
+                self.place(cell + 1, mines + mine as usize);
+                for &i in &touching {
+                    self.open[i] += 1;
+                    if mine {
+                        self.needed[i] += 1;
+                    }
+                }
+            }
+            self.touching[cell] = touching;
+        }
+        self.assignment[cell] = false;
+    }
+}
+
+/// Returns how many arrangements of all the groups together use each number of
+/// mines, given each group's counts.
+fn convolve<'a>(groups: impl Iterator<Item = &'a [f64]>) -> Vec<f64> {
+    groups.fold(vec![1.0], |total, group| {
+        let mut combined = vec![0.0; total.len() + group.len() - 1];
+        for (a, &x) in total.iter().enumerate() {
+            if x == 0.0 {
+                continue;
+            }
+            for (b, &y) in group.iter().enumerate() {
+                combined[a + b] += x * y;
+            }
+        }
+        combined
+    })
+}
+
+/// Returns ln C(n, k).
+fn ln_choose(n: usize, k: usize) -> f64 {
+    let k = k.min(n - k);
+    (0..k)
+        .map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln())
+        .sum()
+}
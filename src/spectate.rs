@@ -0,0 +1,265 @@
+//! Read-only mirrors of a running game.
+//!
+//! `termsweeper --spectators` plays as usual while sending spectators a
+//! [`Frame`] of the board whenever it changes, cursor and timer included.
+//! `termsweeper watch <address>` shows them in another terminal. Spectators can't
+//! play, and are only sent what the player can see, unless the player turns on
+//! the "Reveal for spectators" setting to show them the hidden mines for coaching.
+//!
+//! Frames are JSON lines, with the board in the characters of the bot
+//! [`protocol`](crate::protocol) and `*` for a hidden mine shown to spectators.
+//! Numbers above 9 are `+` in the board and listed as `[x, y, number]` in
+//! `numbers`. The board is the area on the player's screen: `origin` is the cell
+//! of its top-left corner, which moves as an endless board scrolls, and `cursor`
+//! is counted from there. `shape` is the cells' shape, as in layout files.
+//! While no game is on screen, such as in the menu, spectators are sent an `idle`
+//! line:
+//!
+//! ```text
+//! {"v":1,"type":"frame","width":9,"height":9,"origin":[0,0],"shape":"square",
+//!  "mines":10,"flags":1,"cursor":[4,2],"elapsed_ms":5120,"time_left_ms":null,
+//!  "outcome":"playing","coaching":false,"board":[...],"numbers":[]}
+//! {"v":1,"type":"idle"}
+//! ```
+use crate::game_logic::Board;
+use crate::protocol::{self, VERSION};
+use crate::shape::Shape;
+use crate::types::GameState;
+use crate::{Error, Result};
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Port spectators are let in on unless told otherwise.
+pub const DEFAULT_PORT: u16 = 7879;
+/// How long a spectator may hold up the game before being dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// The game as spectators see it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    /// The board cell shown in the top-left corner of `rows`.
+    pub origin: (isize, isize),
+    pub shape: Shape,
+    pub mines: usize,
+    pub flags: usize,
+    /// The cell the player is pointing at, if any, counted from `origin`.
+    pub cursor: Option<(isize, isize)>,
+    pub elapsed: Duration,
+    /// Time left before the time limit runs out, if there is one.
+    pub time_left: Option<Duration>,
+    /// Ongoing, Won or Lost.
+    pub state: GameState,
+    /// Whether hidden mines are shown.
+    pub coaching: bool,
+    /// One string per row, one character per cell.
+    pub rows: Vec<String>,
+    /// The numbers written `+` in `rows`, as ((x, y), number).
+    pub numbers: Vec<((usize, usize), u8)>,
+}
+
+impl Frame {
+    /// Returns what spectators are shown of `board`, or None while no game is on
+    /// screen.
+    pub fn of(board: &Board, state: GameState) -> Option<Frame> {
+        if !matches!(state, GameState::Ongoing | GameState::Won | GameState::Lost) {
+            return None;
+        }
+        let coaching = board.settings.reveal_for_spectators;
+        let (left, top) = board.viewport;
+        Some(Frame {
+            width: board.width,
+            height: board.height,
+            origin: board.viewport,
+            shape: board.settings.shape,
+            mines: board.get_config().mines(),
+            flags: board.get_flags_count(),
+            cursor: board.cursor.map(|(x, y)| (x - left, y - top)),
+            elapsed: board.timer.get_elapsed(),
+            time_left: board.get_time_left(),
+            state,
+            coaching,
+            rows: protocol::board_rows(board, coaching),
+            numbers: protocol::large_numbers(board),
+        })
+    }
+
+    /// Returns the `frame` line for this frame.
+    pub fn to_line(&self) -> String {
+        json!({
+            "v": VERSION,
+            "type": "frame",
+            "width": self.width,
+            "height": self.height,
+            "origin": [self.origin.0, self.origin.1],
+            "shape": self.shape.key(),
+            "mines": self.mines,
+            "flags": self.flags,
+            "cursor": self.cursor.map(|(x, y)| [x, y]),
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+            "time_left_ms": self.time_left.map(|left| left.as_millis() as u64),
+            "outcome": match self.state {
+                GameState::Won => "won",
+                GameState::Lost => "lost",
+                _ => "playing",
+            },
+            "coaching": self.coaching,
+            "board": self.rows,
+            "numbers": self
+                .numbers
+                .iter()
+                .map(|&((x, y), n)| [x, y, n as usize])
+                .collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    /// Reads a `frame` line. Returns None for an `idle` line or anything else.
+    pub fn from_line(line: &str) -> Option<Frame> {
+        let value: Value = serde_json::from_str(line).ok()?;
+        if value["type"] != "frame" {
+            return None;
+        }
+        let size = |name: &str| value[name].as_u64().map(|value| value as usize);
+        let millis = |name: &str| value[name].as_u64().map(Duration::from_millis);
+        let cell = |value: &Value| match value.as_array().map(Vec::as_slice) {
+            Some([x, y]) => Some((x.as_i64()? as isize, y.as_i64()? as isize)),
+            _ => None,
+        };
+        let numbers = value["numbers"]
+            .as_array()
+            .map_or(&[][..], Vec::as_slice)
+            .iter()
+            .map(|number| match number.as_array().map(Vec::as_slice) {
+                Some([x, y, n]) => Some((
+                    (x.as_u64()? as usize, y.as_u64()? as usize),
+                    u8::try_from(n.as_u64()?).ok()?,
+                )),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+        Some(Frame {
+            width: size("width")?,
+            height: size("height")?,
+            origin: cell(&value["origin"]).unwrap_or_default(),
+            shape: value["shape"]
+                .as_str()
+                .and_then(Shape::from_key)
+                .unwrap_or_default(),
+            mines: size("mines")?,
+            flags: size("flags")?,
+            cursor: cell(&value["cursor"]),
+            elapsed: millis("elapsed_ms")?,
+            time_left: millis("time_left_ms"),
+            state: match value["outcome"].as_str()? {
+                "won" => GameState::Won,
+                "lost" => GameState::Lost,
+                _ => GameState::Ongoing,
+            },
+            coaching: value["coaching"].as_bool().unwrap_or(false),
+            rows: value["board"]
+                .as_array()?
+                .iter()
+                .map(|row| Some(row.as_str()?.to_string()))
+                .collect::<Option<_>>()?,
+            numbers,
+        })
+    }
+}
+
+/// Returns the line spectators are sent for `frame`.
+fn line_for(frame: Option<&Frame>) -> String {
+    match frame {
+        Some(frame) => frame.to_line(),
+        None => json!({ "v": VERSION, "type": "idle" }).to_string(),
+    }
+}
+
+/// Sends the game being played to any spectators who connect.
+pub struct Broadcast {
+    addr: SocketAddr,
+    audience: Arc<Mutex<Audience>>,
+}
+
+/// Connected spectators and the last line they were sent.
+struct Audience {
+    spectators: Vec<TcpStream>,
+    last: String,
+}
+
+impl Broadcast {
+    /// Starts letting spectators in on `addr`, on a background thread. Port 0 picks a
+    /// free port.
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Broadcast> {
+        let listener = TcpListener::bind(addr).map_err(Error::Network)?;
+        let addr = listener.local_addr().map_err(Error::Network)?;
+        let audience = Arc::new(Mutex::new(Audience {
+            spectators: Vec::new(),
+            last: line_for(None),
+        }));
+        let shared = Arc::clone(&audience);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut audience = shared.lock().unwrap_or_else(|err| err.into_inner());
+                // Newcomers are shown the game as it is now
+                if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok()
+                    && writeln!(stream, "{}", audience.last).is_ok()
+                {
+                    audience.spectators.push(stream);
+                }
+            }
+        });
+        Ok(Broadcast { addr, audience })
+    }
+
+    /// Returns the address spectators connect to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Sends spectators the game on screen, if it changed since the last call.
+    pub fn show(&self, board: &Board, state: GameState) {
+        let line = line_for(Frame::of(board, state).as_ref());
+        let mut audience = self.lock();
+        if line == audience.last {
+            return;
+        }
+        // Spectators who went away or fell behind are dropped
+        audience
+            .spectators
+            .retain_mut(|stream| writeln!(stream, "{}", line).is_ok());
+        audience.last = line;
+    }
+
+    /// Locks the audience.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Audience> {
+        // Spectators are only a view, so a panic mid-send leaves nothing to repair
+        self.audience.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Connects to a game at `addr` as a spectator.
+///
+/// Returns the frames as they arrive, read on a background thread: None while no
+/// game is on screen. The channel closes when the game does.
+pub fn watch(addr: impl ToSocketAddrs) -> Result<Receiver<Option<Frame>>> {
+    let stream = TcpStream::connect(addr).map_err(Error::Network)?;
+    let (sender, frames) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if sender.send(Frame::from_line(&line)).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(frames)
+}
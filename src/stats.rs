@@ -0,0 +1,411 @@
+//! Persistent game results.
+//!
+//! Results are kept in small text files in the data directory, one per kind of
+//! game. Each result also records the rules it was played under, so games with
+//! different rules are never compared even when they share a file.
+use crate::daily;
+use crate::game_logic::Board;
+use crate::shape::{Neighbourhood, Shape};
+use crate::topology::Topology;
+use crate::types::{DEFAULT_SETTINGS, GameConfig, GameMode, Noise, Preset, Settings};
+use crate::{Error, Result};
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Result log for classic games.
+pub const CLASSIC_LOG: &str = "classic.txt";
+/// Result log for games with a time limit.
+pub const TIME_ATTACK_LOG: &str = "time_attack.txt";
+/// Result log for games with more than one life.
+pub const LIVES_LOG: &str = "lives.txt";
+/// Result log for games where numbers may lie.
+pub const LIAR_LOG: &str = "liar.txt";
+/// Result log for games on boards whose edges wrap around.
+pub const TORUS_LOG: &str = "torus.txt";
+/// Result log for games on hexagonal cells.
+pub const HEX_LOG: &str = "hex.txt";
+/// Result log for games counting the 4 cells sharing an edge.
+pub const CROSS_LOG: &str = "cross.txt";
+/// Result log for games counting the cells a knight's move away.
+pub const KNIGHT_LOG: &str = "knight.txt";
+/// Result log for games counting the cells within two rows and columns.
+pub const RADIUS2_LOG: &str = "radius2.txt";
+/// Result log for games with up to 2 mines per cell.
+pub const TWO_PER_CELL_LOG: &str = "two_per_cell.txt";
+/// Result log for games with up to 3 mines per cell.
+pub const THREE_PER_CELL_LOG: &str = "three_per_cell.txt";
+/// Every result log with its label, in stats menu order.
+pub const RESULT_LOGS: [(&str, &str); 12] = [
+    ("Classic", CLASSIC_LOG),
+    ("Time attack", TIME_ATTACK_LOG),
+    ("Lives", LIVES_LOG),
+    ("Liar", LIAR_LOG),
+    ("Wrap around", TORUS_LOG),
+    ("Hex", HEX_LOG),
+    ("Cross", CROSS_LOG),
+    ("Knight", KNIGHT_LOG),
+    ("Radius 2", RADIUS2_LOG),
+    ("2 per cell", TWO_PER_CELL_LOG),
+    ("3 per cell", THREE_PER_CELL_LOG),
+    ("Daily", daily::DAILY_LOG),
+];
+
+/// Returns the directory results are stored in.
+///
+/// Uses `$TERMSWEEPER_DATA_DIR` if set, then the platform data directory
+/// (`$XDG_DATA_HOME`, `~/.local/share` or `%APPDATA%`) with a `termsweeper` subdirectory.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("TERMSWEEPER_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("termsweeper"))
+}
+
+/// How a recorded game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Won,
+    Lost,
+    /// Started but never finished, e.g. the game was closed.
+    Abandoned,
+}
+
+impl Outcome {
+    /// Returns the word used for this outcome in result files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Won => "won",
+            Outcome::Lost => "lost",
+            Outcome::Abandoned => "abandoned",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Outcome> {
+        [Outcome::Won, Outcome::Lost, Outcome::Abandoned]
+            .into_iter()
+            .find(|outcome| outcome.name() == name)
+    }
+}
+
+/// The rules a recorded game was played under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub noise: Noise,
+    pub mines_per_cell: u8,
+    pub shape: Shape,
+    pub neighbourhood: Neighbourhood,
+    pub topology: Topology,
+    pub lives: u8,
+    /// Time limit in seconds.
+    pub time_limit: Option<u32>,
+}
+
+impl Default for Rules {
+    /// Classic rules.
+    fn default() -> Rules {
+        Rules::of(&DEFAULT_SETTINGS)
+    }
+}
+
+impl Rules {
+    /// Returns the rules a game with `settings` is played under.
+    pub fn of(settings: &Settings) -> Rules {
+        Rules {
+            noise: settings.noise,
+            mines_per_cell: settings.mines_per_cell,
+            shape: settings.shape,
+            // Hex boards always count their touching cells
+            neighbourhood: match settings.shape {
+                Shape::Hex => Neighbourhood::Touching,
+                Shape::Square => settings.neighbourhood,
+            },
+            topology: settings.topology,
+            lives: settings.lives,
+            time_limit: settings.time_limit,
+        }
+    }
+
+    /// Returns the rules that differ from classic play, as written in result
+    /// files, e.g. `hex,lives=3,time=60`. Classic rules have none.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        match self.noise {
+            Noise::Off => {}
+            Noise::Noisy(percent) => tags.push(format!("noise={}", percent)),
+            Noise::OnePerRegion => tags.push("noise=region".to_string()),
+        }
+        if self.mines_per_cell > 1 {
+            tags.push(format!("per-cell={}", self.mines_per_cell));
+        }
+        if self.shape == Shape::Hex {
+            tags.push("hex".to_string());
+        }
+        match self.neighbourhood {
+            Neighbourhood::Touching => {}
+            Neighbourhood::Cross => tags.push("cross".to_string()),
+            Neighbourhood::Knight => tags.push("knight".to_string()),
+            Neighbourhood::Radius2 => tags.push("radius2".to_string()),
+        }
+        if self.topology == Topology::Torus {
+            tags.push("torus".to_string());
+        }
+        if self.lives > 1 {
+            tags.push(format!("lives={}", self.lives));
+        }
+        if let Some(limit) = self.time_limit {
+            tags.push(format!("time={}", limit));
+        }
+        tags
+    }
+
+    /// Parses the `tags` of [`Rules::tags`], or `None` if one isn't known.
+    fn from_tags(tags: &str) -> Option<Rules> {
+        let mut rules = Rules::default();
+        for tag in tags.split(',').filter(|tag| !tag.is_empty()) {
+            match tag.split_once('=') {
+                None => match tag {
+                    "hex" => rules.shape = Shape::Hex,
+                    "cross" => rules.neighbourhood = Neighbourhood::Cross,
+                    "knight" => rules.neighbourhood = Neighbourhood::Knight,
+                    "radius2" => rules.neighbourhood = Neighbourhood::Radius2,
+                    "torus" => rules.topology = Topology::Torus,
+                    _ => return None,
+                },
+                Some(("noise", "region")) => rules.noise = Noise::OnePerRegion,
+                Some(("noise", percent)) => rules.noise = Noise::Noisy(percent.parse().ok()?),
+                Some(("per-cell", count)) => rules.mines_per_cell = count.parse().ok()?,
+                Some(("lives", lives)) => rules.lives = lives.parse().ok()?,
+                Some(("time", limit)) => rules.time_limit = Some(limit.parse().ok()?),
+                Some(_) => return None,
+            }
+        }
+        Some(rules)
+    }
+}
+
+/// One recorded game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResultEntry {
+    pub date: NaiveDate,
+    pub outcome: Outcome,
+    /// Time spent playing, in milliseconds.
+    pub time_ms: u32,
+    pub config: GameConfig,
+    pub rules: Rules,
+}
+
+/// Totals over the games in a [`ResultLog`] with the same configuration and rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub played: usize,
+    pub won: usize,
+    /// Fastest win in milliseconds.
+    pub best_ms: Option<u32>,
+}
+
+/// A list of results backed by a text file with one `date outcome time_ms WxH/mines [rules]`
+/// line per game. The rules are the comma separated [`Rules::tags`], left out for classic rules.
+#[derive(Clone, Debug)]
+pub struct ResultLog {
+    path: PathBuf,
+    pub entries: Vec<ResultEntry>,
+}
+
+impl ResultLog {
+    /// Loads the log named `name` from the [`data_dir`].
+    pub fn open(name: &str) -> Result<ResultLog> {
+        let dir = data_dir().ok_or_else(|| Error::Persistence {
+            path: PathBuf::from(name),
+            source: io::Error::new(
+                io::ErrorKind::NotFound,
+                "no data directory; set TERMSWEEPER_DATA_DIR",
+            ),
+        })?;
+        ResultLog::load(dir.join(name))
+    }
+
+    /// Loads a log from `path`; a missing file is an empty log.
+    pub fn load(path: PathBuf) -> Result<ResultLog> {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(source) => return Err(Error::Persistence { path, source }),
+        };
+        let entries = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| parse_entry(index + 1, line))
+            .collect::<Result<_>>()?;
+        Ok(ResultLog { path, entries })
+    }
+
+    /// Writes the log back to its file, creating the directory if needed.
+    pub fn save(&self) -> Result<()> {
+        let persistence = |source| Error::Persistence {
+            path: self.path.clone(),
+            source,
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(persistence)?;
+        }
+        let text: String = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let config = entry.config;
+                let tags = entry.rules.tags();
+                format!(
+                    "{} {} {} {}x{}/{}{}{}\n",
+                    entry.date,
+                    entry.outcome.name(),
+                    entry.time_ms,
+                    config.width(),
+                    config.height(),
+                    config.mines(),
+                    if tags.is_empty() { "" } else { " " },
+                    tags.join(",")
+                )
+            })
+            .collect();
+        fs::write(&self.path, text).map_err(persistence)
+    }
+
+    /// Adds a result and saves the log.
+    pub fn push(&mut self, entry: ResultEntry) -> Result<()> {
+        self.entries.push(entry);
+        self.save()
+    }
+
+    /// Returns the totals per board configuration and rules, presets first in menu order.
+    pub fn summaries(&self) -> Vec<(GameConfig, Rules, Summary)> {
+        let mut summaries: Vec<(GameConfig, Rules, Summary)> = Vec::new();
+        for entry in &self.entries {
+            let index = match summaries
+                .iter()
+                .position(|(config, rules, _)| *config == entry.config && *rules == entry.rules)
+            {
+                Some(index) => index,
+                None => {
+                    summaries.push((entry.config, entry.rules, Summary::default()));
+                    summaries.len() - 1
+                }
+            };
+            let summary = &mut summaries[index].2;
+            summary.played += 1;
+            if entry.outcome == Outcome::Won {
+                summary.won += 1;
+                summary.best_ms = Some(
+                    summary
+                        .best_ms
+                        .map_or(entry.time_ms, |best| best.min(entry.time_ms)),
+                );
+            }
+        }
+        summaries.sort_by_key(|(config, _, _)| {
+            Preset::ALL
+                .iter()
+                .position(|preset| preset.config() == *config)
+                .unwrap_or(Preset::ALL.len())
+        });
+        summaries
+    }
+}
+
+/// Returns the result log a finished game belongs in.
+///
+/// The log is picked by the game's most distinctive rule; games in one log are
+/// still told apart by their [`Rules`]. Daily games have their own history, endless games are scored rather than
+/// timed, and boards loaded from a layout or file aren't random games, so none
+/// of them go in a log here.
+pub fn log_for(board: &Board) -> Option<&'static str> {
+    if board.mode != GameMode::Classic || !board.is_generated() {
+        None
+    } else if board.settings.noise != Noise::Off {
+        Some(LIAR_LOG)
+    } else if board.settings.mines_per_cell > 1 {
+        Some(match board.settings.mines_per_cell {
+            2 => TWO_PER_CELL_LOG,
+            _ => THREE_PER_CELL_LOG,
+        })
+    } else if board.settings.shape == Shape::Hex {
+        // Hex boards always count their touching cells
+        Some(HEX_LOG)
+    } else if board.settings.neighbourhood != Neighbourhood::Touching {
+        Some(match board.settings.neighbourhood {
+            Neighbourhood::Cross => CROSS_LOG,
+            Neighbourhood::Knight => KNIGHT_LOG,
+            _ => RADIUS2_LOG,
+        })
+    } else if board.settings.topology == Topology::Torus {
+        Some(TORUS_LOG)
+    } else if board.settings.lives > 1 {
+        Some(LIVES_LOG)
+    } else if board.settings.time_limit.is_some() {
+        Some(TIME_ATTACK_LOG)
+    } else {
+        Some(CLASSIC_LOG)
+    }
+}
+
+/// Records a finished game in its result log, see [`log_for`].
+pub fn record_game(board: &Board, outcome: Outcome) -> Result<()> {
+    let Some(name) = log_for(board) else {
+        return Ok(());
+    };
+    ResultLog::open(name)?.push(ResultEntry {
+        date: daily::today(),
+        outcome,
+        time_ms: board.timer.get_elapsed().as_millis() as u32,
+        config: board.get_config(),
+        rules: Rules::of(&board.settings),
+    })
+}
+
+fn parse_entry(line: usize, text: &str) -> Result<ResultEntry> {
+    let fields: Vec<&str> = text.split_whitespace().collect();
+    // 1-based column of a field within the line
+    let column = |field: &str| field.as_ptr() as usize - text.as_ptr() as usize + 1;
+    let error = |column: usize, message: String| Error::Parse {
+        line,
+        column,
+        message,
+    };
+    let (date, outcome, time_ms, config, tags) = match fields[..] {
+        [date, outcome, time_ms, config] => (date, outcome, time_ms, config, None),
+        [date, outcome, time_ms, config, tags] => (date, outcome, time_ms, config, Some(tags)),
+        _ => {
+            return Err(error(
+                1,
+                "expected 'date outcome time_ms WxH/mines [rules]'".to_string(),
+            ));
+        }
+    };
+    let invalid_config = || error(column(config), format!("invalid board '{}'", config));
+    let (size, mines) = config.split_once('/').ok_or_else(invalid_config)?;
+    let (width, height) = size.split_once('x').ok_or_else(invalid_config)?;
+    let number = |text: &str| text.parse::<usize>().map_err(|_| invalid_config());
+    Ok(ResultEntry {
+        date: date
+            .parse()
+            .map_err(|_| error(column(date), format!("invalid date '{}'", date)))?,
+        outcome: Outcome::from_name(outcome)
+            .ok_or_else(|| error(column(outcome), format!("invalid outcome '{}'", outcome)))?,
+        time_ms: time_ms
+            .parse()
+            .map_err(|_| error(column(time_ms), format!("invalid time '{}'", time_ms)))?,
+        config: GameConfig::new(number(width)?, number(height)?, number(mines)?)
+            .map_err(|_| invalid_config())?,
+        rules: match tags {
+            Some(tags) => Rules::from_tags(tags)
+                .ok_or_else(|| error(column(tags), format!("invalid rules '{}'", tags)))?,
+            None => Rules::default(),
+        },
+    })
+}
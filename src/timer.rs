@@ -1,10 +1,19 @@
 use std::time::{Duration, Instant};
 
 /// A simple timer for tracking elapsed time in the game.
+///
+/// An optional deadline turns it into a countdown: see [`Timer::get_remaining`].
 pub struct Timer {
     start_time: Option<Instant>,
     elapsed: Duration,
     running: bool,
+    deadline: Option<Duration>,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Timer {
@@ -14,6 +23,7 @@ impl Timer {
             start_time: None,
             elapsed: Duration::new(0, 0),
             running: false,
+            deadline: None,
         }
     }
 
@@ -40,21 +50,51 @@ impl Timer {
         }
     }
 
-    /// Resets the timer to zero and stops it.
+    /// Resets the timer to zero, stops it and clears the deadline.
     pub fn reset(&mut self) {
         self.start_time = None;
         self.elapsed = Duration::new(0, 0);
         self.running = false;
+        self.deadline = None;
+    }
+
+    /// Returns true if the timer is running.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Sets the elapsed time at which the timer expires, or removes the deadline.
+    pub fn set_deadline(&mut self, deadline: Option<Duration>) {
+        self.deadline = deadline;
+    }
+
+    /// Moves the deadline later by `extra`. Does nothing without a deadline.
+    pub fn extend_deadline(&mut self, extra: Duration) {
+        if let Some(deadline) = &mut self.deadline {
+            *deadline += extra;
+        }
+    }
+
+    /// Returns the time left before the deadline, or None without a deadline.
+    pub fn get_remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_sub(self.get_elapsed()))
+    }
+
+    /// Returns true if there is a deadline and it has passed.
+    pub fn is_expired(&self) -> bool {
+        self.get_remaining()
+            .is_some_and(|remaining| remaining.is_zero())
     }
 
     /// Returns the total elapsed time as a `Duration`.
     ///
     /// If the timer is running, includes the time since it was started.
     pub fn get_elapsed(&self) -> Duration {
-        if self.running {
-            if let Some(start) = self.start_time {
-                return self.elapsed + start.elapsed();
-            }
+        if self.running
+            && let Some(start) = self.start_time
+        {
+            return self.elapsed + start.elapsed();
         }
         self.elapsed
     }
@@ -0,0 +1,102 @@
+//! Board topologies: which cells count as neighbours.
+//!
+//! Every neighbour loop goes through [`Topology::neighbours`], so mine counts,
+//! openings, chords and solvers agree on the shape of the board.
+use crate::shape::{Neighbourhood, Shape};
+use crate::types::{CellBox, CellKind};
+
+/// How the edges of a fixed-size board behave.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// Edges are walls: cells along them have fewer neighbours.
+    #[default]
+    Bounded,
+    /// Opposite edges are joined, so every cell has a full set of neighbours.
+    Torus,
+}
+
+impl Topology {
+    /// All topologies, in settings menu order.
+    pub const ALL: [Topology; 2] = [Topology::Bounded, Topology::Torus];
+    /// Display names, matching the order of [`Topology::ALL`].
+    pub const NAMES: [&'static str; 2] = ["Bounded", "Wrap around"];
+
+    /// Returns the name used in layout files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Topology::Bounded => "bounded",
+            Topology::Torus => "torus",
+        }
+    }
+
+    /// Returns the topology with the given layout file name.
+    pub fn from_key(key: &str) -> Option<Topology> {
+        Topology::ALL
+            .into_iter()
+            .find(|topology| topology.key() == key)
+    }
+
+    /// Maps (x, y) onto a `width` x `height` board, or None if it is off a bounded board.
+    pub fn wrap(self, x: isize, y: isize, width: usize, height: usize) -> Option<(isize, isize)> {
+        let (width, height) = (width as isize, height as isize);
+        match self {
+            Topology::Bounded => {
+                ((0..width).contains(&x) && (0..height).contains(&y)).then_some((x, y))
+            }
+            Topology::Torus if width > 0 && height > 0 => {
+                Some((x.rem_euclid(width), y.rem_euclid(height)))
+            }
+            Topology::Torus => None,
+        }
+    }
+
+    /// Returns the distinct neighbours of (x, y) with cells of the given shape,
+    /// counted over the given neighbourhood, not including the cell itself.
+    ///
+    /// Hex tori only line up across the top and bottom edges when the height is even.
+    pub fn neighbours(
+        self,
+        shape: Shape,
+        neighbourhood: Neighbourhood,
+        x: isize,
+        y: isize,
+        width: usize,
+        height: usize,
+    ) -> Vec<(isize, isize)> {
+        let offsets = neighbourhood.offsets(shape, y);
+        let mut neighbours = Vec::with_capacity(offsets.len());
+        for &(dx, dy) in offsets {
+            // Small tori can reach the same cell, or the cell itself, in several ways
+            if let Some(cell) = self.wrap(x + dx, y + dy, width, height)
+                && cell != (x, y)
+                && !neighbours.contains(&cell)
+            {
+                neighbours.push(cell);
+            }
+        }
+        neighbours
+    }
+
+    /// Sets every non-mine cell of a row-major board to the number of mines around it.
+    pub(crate) fn count_numbers(
+        self,
+        shape: Shape,
+        neighbourhood: Neighbourhood,
+        cells: &mut [CellBox],
+        width: usize,
+        height: usize,
+    ) {
+        for index in 0..cells.len() {
+            if cells[index].kind.is_mine() {
+                continue;
+            }
+            let (x, y) = ((index % width) as isize, (index / width) as isize);
+            let count: usize = self
+                .neighbours(shape, neighbourhood, x, y, width, height)
+                .into_iter()
+                .map(|(nx, ny)| cells[ny as usize * width + nx as usize].kind.mines())
+                .sum();
+            cells[index].kind = CellKind::Number(count as u8);
+        }
+    }
+}
@@ -2,9 +2,18 @@
 //!
 //! This module contains functions for rendering the Minesweeper game board,
 //! menus, and handling terminal setup/cleanup using `crossterm`.
+use crate::agent::Thinking;
+use crate::coop::Coop;
 use crate::game_logic::Board;
 use crate::menu::Menu;
-use crate::types::{COLOR_CONFIG, CellKind, CellState, MenuItem};
+use crate::race::{Race, Racer};
+use crate::spectate::Frame;
+use crate::topology::Topology;
+use crate::types::{
+    Action, COLOR_CONFIG, CellKind, CellState, GameMode, GameState, LossReason, MAX_NUMBER,
+    MenuItem,
+};
+use crate::{Error, Result};
 use crossterm::{
     cursor::{MoveTo, RestorePosition},
     event::{self, DisableMouseCapture, EnableMouseCapture},
@@ -15,26 +24,27 @@ use crossterm::{
 use std::io::{Stdout, Write};
 
 /// Set up and clean up section
-pub fn setup_terminal(mut stdout: &Stdout) -> Result<(), std::io::Error> {
-    terminal::enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    return Ok(());
+pub fn setup_terminal(mut stdout: &Stdout) -> Result<()> {
+    terminal::enable_raw_mode().map_err(Error::Terminal)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture).map_err(Error::Terminal)?;
+    Ok(())
 }
 
 /// set styles
-pub fn set_styles(mut stdout: &Stdout) -> Result<(), std::io::Error> {
+pub fn set_styles(mut stdout: &Stdout) -> Result<()> {
     execute!(
         stdout,
         SetBackgroundColor(COLOR_CONFIG.background),
         SetForegroundColor(Color::Black),
         Clear(terminal::ClearType::All)
-    )?;
-    return Ok(());
+    )
+    .map_err(Error::Terminal)?;
+    Ok(())
 }
 
 /// Restore terminal to original state
-pub fn cleanup_terminal(mut stdout: &Stdout) -> Result<(), std::io::Error> {
-    while event::poll(std::time::Duration::from_millis(1))? {
+pub fn cleanup_terminal(mut stdout: &Stdout) -> Result<()> {
+    while event::poll(std::time::Duration::from_millis(1)).map_err(Error::Terminal)? {
         let _ = event::read(); // Clear any pending events
     }
     execute!(
@@ -43,14 +53,15 @@ pub fn cleanup_terminal(mut stdout: &Stdout) -> Result<(), std::io::Error> {
         LeaveAlternateScreen,
         ResetColor,
         RestorePosition,
-    )?;
-    terminal::disable_raw_mode()?;
-    return Ok(());
+    )
+    .map_err(Error::Terminal)?;
+    terminal::disable_raw_mode().map_err(Error::Terminal)?;
+    Ok(())
 }
 
 /// Overlay ASCII art above the finished game board for win/lose screens.
 /// If there is room, place it above the board; otherwise, center in terminal.
-pub fn overlay_ascii_art(stdout: &mut Stdout, board: &Board, win: bool) -> anyhow::Result<()> {
+pub fn overlay_ascii_art(stdout: &mut Stdout, board: &Board, win: bool) -> Result<()> {
     let win_art = [
         "   __     __          __          ___       ",
         "   \\ \\   / /          \\ \\        / (_)      ",
@@ -74,18 +85,19 @@ pub fn overlay_ascii_art(stdout: &mut Stdout, board: &Board, win: bool) -> anyho
     let art = if win { &win_art } else { &lose_art };
     let color = if win { Color::Green } else { Color::Red };
 
-    let (cols, rows) = crossterm::terminal::size().expect("Failed to get terminal size");
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
     let art_width = art[0].len() as u16;
     let art_height = art.len() as u16;
     let art_x = (cols.saturating_sub(art_width)) / 2;
 
-    let (_, board_start_y) = board.get_board_start_pos();
-    let art_y = if board_start_y >= art_height + 1 {
+    let (_, board_start_y) = board.get_board_start_pos()?;
+    // Leaves a row under the art for the message below
+    let art_y = if board_start_y >= art_height + 3 {
         board_start_y - art_height - 3
     } else {
         (rows.saturating_sub(art_height)) / 2
     };
-    queue!(stdout, SetAttribute(Attribute::Bold))?;
+    queue!(stdout, SetAttribute(Attribute::Bold)).map_err(Error::Terminal)?;
     for (i, line) in art.iter().enumerate() {
         for (j, ch) in line.chars().enumerate() {
             if ch != ' ' {
@@ -94,54 +106,76 @@ pub fn overlay_ascii_art(stdout: &mut Stdout, board: &Board, win: bool) -> anyho
                     SetForegroundColor(color),
                     MoveTo(art_x + j as u16, art_y + i as u16),
                     Print(ch),
-                )?;
+                )
+                .map_err(Error::Terminal)?;
             }
         }
     }
+    // Say why the game ended when it wasn't a mine, or the score of an endless game
+    let message = if board.mode == GameMode::Endless {
+        Some(format!(
+            "Score: {} cells cleared",
+            board.get_revealed_count()
+        ))
+    } else if board.get_loss_reason() == Some(LossReason::TimeUp) {
+        Some("Time's up!".to_string())
+    } else {
+        None
+    };
+    if let Some(message) = message {
+        queue!(
+            stdout,
+            SetForegroundColor(color),
+            MoveTo(
+                (cols.saturating_sub(message.len() as u16)) / 2,
+                art_y + art_height
+            ),
+            Print(&message),
+        )
+        .map_err(Error::Terminal)?;
+    }
     queue!(
         stdout,
         SetBackgroundColor(COLOR_CONFIG.background),
         // SetAttribute(Attribute::NoBold)
-    )?;
-    stdout.flush()?;
+    )
+    .map_err(Error::Terminal)?;
+    stdout.flush().map_err(Error::Terminal)?;
     Ok(())
 }
 
+/// Returns the color a number is drawn in.
+fn number_color(n: u8) -> Color {
+    COLOR_CONFIG.number[(n.min(MAX_NUMBER) as usize).saturating_sub(1)]
+}
+
 // put into seperate function to avoid code duplication and make more readable
 /// Render the game board to the terminal using crossterm.
 /// Handles terminal resizing and displays a warning if the terminal is too small.
-pub fn render_game_board(board: &Board, stdout: &mut Stdout) -> anyhow::Result<()> {
-    let (cols, rows) = crossterm::terminal::size()?;
-    let required_width = 2 + board.width * 2;
+pub fn render_game_board(board: &Board, stdout: &mut Stdout) -> Result<()> {
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+    let extra_width = board.settings.shape.extra_width();
+    let required_width = 2 + board.width * 2 + extra_width as usize;
     let required_height = 2 + board.height;
 
     if cols < required_width as u16 || rows < required_height as u16 {
-        // Terminal is too small, show warning message
-        let msg = "Terminal too small! Resize and try again.";
-        let x = (cols.saturating_sub(msg.len() as u16)) / 2;
-        let y = rows / 2;
-        queue!(
-            stdout,
-            Clear(terminal::ClearType::All),
-            MoveTo(x, y),
-            SetForegroundColor(Color::Red),
-            Print(msg),
-            ResetColor
-        )?;
-        stdout.flush()?;
-        return Ok(());
+        return render_too_small(stdout, cols, rows);
     }
 
-    let (board_start_x, board_start_y) = board.get_board_start_pos();
+    let (board_start_x, board_start_y) = board.get_board_start_pos()?;
     queue!(
         stdout,
         SetBackgroundColor(COLOR_CONFIG.background),
         Clear(terminal::ClearType::All),
         MoveTo(board_start_x, board_start_y)
-    )?;
-    // Draw bombs counter
-    let bombs_left = board.get_remaining_mines();
-    let bombs_left_str = format!("💣: {}", bombs_left);
+    )
+    .map_err(Error::Terminal)?;
+    // Draw bombs counter, or the score on endless boards
+    let bombs_left_str = if board.mode == GameMode::Endless {
+        format!("Score: {}", board.get_revealed_count())
+    } else {
+        format!("💣: {}", board.get_remaining_mines())
+    };
     let counter_box = format_box_with_value(&bombs_left_str);
     for (i, line) in counter_box.iter().enumerate() {
         queue!(
@@ -149,55 +183,195 @@ pub fn render_game_board(board: &Board, stdout: &mut Stdout) -> anyhow::Result<(
             SetForegroundColor(COLOR_CONFIG.counter),
             MoveTo(board_start_x, board_start_y - 3 + i as u16),
             Print(line),
-        )?;
+        )
+        .map_err(Error::Terminal)?;
+    }
+    // Draw lives between the counters in lives mode
+    if board.settings.lives > 1 {
+        let lives_str = format!("♥ {}", board.get_lives_left());
+        let lives_box = format_box_with_value(&lives_str);
+        let lives_x =
+            board_start_x + (required_width as u16).saturating_sub(lives_str.len() as u16 + 2) / 2;
+        for (i, line) in lives_box.iter().enumerate() {
+            queue!(
+                stdout,
+                SetForegroundColor(COLOR_CONFIG.mine),
+                MoveTo(lives_x, board_start_y - 3 + i as u16),
+                Print(line),
+            )
+            .map_err(Error::Terminal)?;
+        }
     }
-    // Draw timer
-    let elapsed_seconds = board.timer.get_elapsed_seconds();
-    let timer_str = format!("⏰: {:02}", elapsed_seconds);
+    // Draw timer, counting down to the time limit if there is one
+    let (timer_str, timer_color) = match board.get_time_left() {
+        Some(remaining) => {
+            // Round up, so the clock reads 0:00 only when time is up
+            let seconds = remaining.as_millis().div_ceil(1000);
+            let color = if seconds <= 10 {
+                COLOR_CONFIG.mine
+            } else {
+                COLOR_CONFIG.counter
+            };
+            (format!("⏳: {}:{:02}", seconds / 60, seconds % 60), color)
+        }
+        None => (
+            format!("⏰: {:02}", board.timer.get_elapsed_seconds()),
+            COLOR_CONFIG.counter,
+        ),
+    };
     let timer_box = format_box_with_value(&timer_str);
     for (i, line) in timer_box.iter().enumerate() {
         queue!(
             stdout,
-            SetForegroundColor(COLOR_CONFIG.counter),
+            SetForegroundColor(timer_color),
             MoveTo(
                 board_start_x + required_width as u16 - timer_str.len() as u16 - 2,
                 board_start_y - 3 + i as u16
             ),
             Print(line),
-        )?;
+        )
+        .map_err(Error::Terminal)?;
+    }
+    draw_grid(stdout, board, board_start_x, board_start_y, &[], &|_, _| {
+        None
+    })?;
+
+    // Draw status line under the board, if there is a row left for it
+    let status_y = board_start_y + 2 + board.height as u16;
+    if board.settings.status_bar.enabled && status_y < rows {
+        let status = format_status_line(board, cols as usize);
+        let status_x = (cols.saturating_sub(status.chars().count() as u16)) / 2;
+        queue!(
+            stdout,
+            SetForegroundColor(COLOR_CONFIG.status),
+            MoveTo(status_x, status_y),
+            Print(status),
+        )
+        .map_err(Error::Terminal)?;
+    }
+    // Lies too wide to strike through in their cell are spelled out under the board
+    let lies_y = status_y + board.settings.status_bar.enabled as u16;
+    if board.is_finished() && lies_y < rows {
+        let lies = format_wide_lies(board, cols as usize);
+        let lies_x = (cols.saturating_sub(lies.chars().count() as u16)) / 2;
+        queue!(
+            stdout,
+            SetForegroundColor(COLOR_CONFIG.question_cell),
+            MoveTo(lies_x, lies_y),
+            Print(lies),
+        )
+        .map_err(Error::Terminal)?;
     }
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Draw the board's cells inside a border whose top-left corner is at (start_x, start_y),
+/// highlighting the cell under each of `cursors` in its colour. Flags are drawn in
+/// the colour `flag_color` gives them, if any.
+fn draw_grid(
+    stdout: &mut Stdout,
+    board: &Board,
+    start_x: u16,
+    start_y: u16,
+    cursors: &[((isize, isize), Color)],
+    flag_color: &dyn Fn(isize, isize) -> Option<Color>,
+) -> Result<()> {
+    let extra_width = board.settings.shape.extra_width();
+    // Wrap-around boards get dotted edges, since the board continues past them
+    let (horizontal, vertical) = if board.settings.topology == Topology::Torus {
+        ("┄┄", "┆")
+    } else {
+        ("──", "│")
+    };
     // Draw top border
     queue!(
         stdout,
         SetForegroundColor(COLOR_CONFIG.border),
-        MoveTo(board_start_x, board_start_y),
+        MoveTo(start_x, start_y),
         Print("┌"),
-    )?;
+    )
+    .map_err(Error::Terminal)?;
     for _ in 0..board.width {
-        queue!(stdout, Print("──"))?;
+        queue!(stdout, Print(horizontal)).map_err(Error::Terminal)?;
     }
-    queue!(stdout, Print("─┐"))?;
+    for _ in 0..=extra_width {
+        queue!(stdout, Print(&horizontal[..3])).map_err(Error::Terminal)?;
+    }
+    queue!(stdout, Print("┐")).map_err(Error::Terminal)?;
 
-    // Draw board rows with left/right borders
+    // Lying numbers show their true value once the game is over
+    let finished = board.is_finished();
+    // Draw board rows with left/right borders, shifting odd rows of hex boards
     for y in 0..board.height {
+        let (left, top) = board.viewport;
+        let indent = board.settings.shape.indent(top + y as isize);
         queue!(
             stdout,
-            MoveTo(board_start_x, board_start_y + 1 + y as u16),
-            Print("│ "),
-        )?;
+            MoveTo(start_x, start_y + 1 + y as u16),
+            Print(vertical),
+            Print(" ".repeat(1 + indent as usize)),
+        )
+        .map_err(Error::Terminal)?;
         for x in 0..board.width {
             // Render cells with offset for border
-            if let Some(cell) = board.get_cell(x as isize, y as isize) {
+            let (cell_x, cell_y) = (left + x as isize, top + y as isize);
+            let highlight = cursors
+                .iter()
+                .rev()
+                .find(|(cursor, _)| *cursor == (cell_x, cell_y))
+                .map_or(COLOR_CONFIG.background, |&(_, color)| color);
+            queue!(stdout, SetBackgroundColor(highlight)).map_err(Error::Terminal)?;
+            if let Some(cell) = board.get_cell(cell_x, cell_y) {
+                let shown = board.get_shown_number(cell_x, cell_y);
+                // A lie is struck through next to the truth when both fit in the cell,
+                // otherwise the truth is drawn in the lie colour and the pair listed
+                // under the board
+                if finished
+                    && let (Some(shown), CellKind::Number(n)) = (shown, cell.kind)
+                    && shown != n
+                {
+                    if shown >= 10 || n >= 10 {
+                        queue!(
+                            stdout,
+                            SetForegroundColor(COLOR_CONFIG.question_cell),
+                            Print(format!("{:^2}", n)),
+                        )
+                        .map_err(Error::Terminal)?;
+                        continue;
+                    }
+                    queue!(
+                        stdout,
+                        SetForegroundColor(COLOR_CONFIG.question_cell),
+                        SetAttribute(Attribute::CrossedOut),
+                        Print(shown),
+                        SetAttribute(Attribute::NotCrossedOut),
+                        SetForegroundColor(number_color(n)),
+                        Print(n),
+                    )
+                    .map_err(Error::Terminal)?;
+                    continue;
+                }
+                // Cells holding or flagged with several mines show the count
                 let (symbol, color) = match cell.state {
-                    CellState::Hidden => ("■", COLOR_CONFIG.hidden_cell),
-                    CellState::Flagged => ("⚑", COLOR_CONFIG.flagged_cell),
+                    CellState::Hidden => ("■".to_string(), COLOR_CONFIG.hidden_cell),
+                    CellState::Flagged(n) => {
+                        let color = flag_color(cell_x, cell_y).unwrap_or(COLOR_CONFIG.flagged_cell);
+                        if n == 1 {
+                            ("⚑".to_string(), color)
+                        } else {
+                            (format!("⚑{}", n), color)
+                        }
+                    }
+                    CellState::Question => ("?".to_string(), COLOR_CONFIG.question_cell),
                     CellState::Revealed => match cell.kind {
-                        CellKind::Mine => ("💣", COLOR_CONFIG.mine),
-                        CellKind::Number(0) => ("  ", COLOR_CONFIG.empty_cell),
-                        CellKind::Number(n) => (
-                            &n.to_string()[..],
-                            COLOR_CONFIG.number[(n as usize).saturating_sub(1).min(7)],
-                        ),
+                        CellKind::Mine(1) => ("💣".to_string(), COLOR_CONFIG.mine),
+                        CellKind::Mine(n) => (format!("*{}", n), COLOR_CONFIG.mine),
+                        CellKind::Number(0) => ("  ".to_string(), COLOR_CONFIG.empty_cell),
+                        CellKind::Number(n) => {
+                            let n = if finished { n } else { shown.unwrap_or(n) };
+                            (n.to_string(), number_color(n))
+                        }
                     },
                 };
                 let symbol = if symbol == "💣" {
@@ -205,32 +379,616 @@ pub fn render_game_board(board: &Board, stdout: &mut Stdout) -> anyhow::Result<(
                 } else {
                     format!("{:^2}", symbol)
                 };
-                queue!(stdout, SetForegroundColor(color), Print(symbol))?;
+                queue!(stdout, SetForegroundColor(color), Print(symbol))
+                    .map_err(Error::Terminal)?;
             }
         }
-        queue!(stdout, SetForegroundColor(COLOR_CONFIG.border), Print("│"))?;
+        queue!(
+            stdout,
+            SetBackgroundColor(COLOR_CONFIG.background),
+            SetForegroundColor(COLOR_CONFIG.border),
+            Print(" ".repeat((extra_width - indent) as usize)),
+            Print(vertical)
+        )
+        .map_err(Error::Terminal)?;
     }
 
     // Draw bottom border
     queue!(
         stdout,
         SetForegroundColor(COLOR_CONFIG.border),
-        MoveTo(board_start_x, board_start_y + 1 + board.height as u16),
+        MoveTo(start_x, start_y + 1 + board.height as u16),
         Print("└"),
-    )?;
+    )
+    .map_err(Error::Terminal)?;
     for _ in 0..board.width {
-        queue!(stdout, Print("──"))?;
+        queue!(stdout, Print(horizontal)).map_err(Error::Terminal)?;
+    }
+    for _ in 0..=extra_width {
+        queue!(stdout, Print(&horizontal[..3])).map_err(Error::Terminal)?;
     }
-    queue!(stdout, Print("─┘"))?;
-    stdout.flush()?;
+    queue!(stdout, Print("┘")).map_err(Error::Terminal)?;
     Ok(())
 }
 
-fn format_box_with_value(value: &str) -> Vec<String> {
-    let mut len = value.len();
-    if value.contains("⏰") {
-        len += 1;
+/// Show a warning instead of a board that doesn't fit the terminal.
+fn render_too_small(stdout: &mut Stdout, cols: u16, rows: u16) -> Result<()> {
+    let msg = "Terminal too small! Resize and try again.";
+    let x = (cols.saturating_sub(msg.len() as u16)) / 2;
+    let y = rows / 2;
+    queue!(
+        stdout,
+        Clear(terminal::ClearType::All),
+        MoveTo(x, y),
+        SetForegroundColor(Color::Red),
+        Print(msg),
+        ResetColor
+    )
+    .map_err(Error::Terminal)?;
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Width of the panel standing in for an opponent on another terminal.
+const OPPONENT_PANEL_WIDTH: u16 = 24;
+/// Columns between the racers' panels.
+const RACE_GAP: u16 = 4;
+
+/// Render a race side by side: each local racer's board with their cursor, and a
+/// progress bar for each opponent on another terminal, under a header with their
+/// name, progress and keys. Announces the winner once the race is decided, above
+/// the `footer`.
+pub fn render_race(stdout: &mut Stdout, race: &Race, footer: &str) -> Result<()> {
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+    let Some(config) = race.config() else {
+        return Ok(());
+    };
+    let panel_width = |racer: &Racer| match &racer.board {
+        Some(board) => 3 + board.width as u16 * 2 + board.settings.shape.extra_width(),
+        None => OPPONENT_PANEL_WIDTH,
+    };
+    let total_width = race.racers.iter().map(panel_width).sum::<u16>()
+        + RACE_GAP * (race.racers.len() as u16).saturating_sub(1);
+    // Three header lines, the bordered board, then the result and the footer
+    let total_height = 3 + config.height() as u16 + 2 + 3;
+    if cols < total_width || rows < total_height {
+        return render_too_small(stdout, cols, rows);
+    }
+    let top = (rows - total_height) / 2 + 3;
+    queue!(
+        stdout,
+        SetBackgroundColor(COLOR_CONFIG.background),
+        Clear(terminal::ClearType::All),
+    )
+    .map_err(Error::Terminal)?;
+    let mut x = (cols - total_width) / 2;
+    for (i, racer) in race.racers.iter().enumerate() {
+        let player_color = COLOR_CONFIG.players[i % COLOR_CONFIG.players.len()];
+        let progress = racer.progress;
+        let (status, color) = match progress.state {
+            GameState::Won => ("cleared", Color::Green),
+            GameState::Lost => ("hit a mine", COLOR_CONFIG.mine),
+            _ => ("racing", COLOR_CONFIG.status),
+        };
+        let keys = racer.keys.map_or("on another terminal", |keys| keys.help);
+        queue!(
+            stdout,
+            MoveTo(x, top - 3),
+            SetForegroundColor(player_color),
+            SetAttribute(Attribute::Bold),
+            Print(&racer.name),
+            SetAttribute(Attribute::NormalIntensity),
+            MoveTo(x, top - 2),
+            SetForegroundColor(color),
+            Print(format!(
+                "{}% │ ⚑ {} │ {}",
+                progress.cleared, progress.flags, status
+            )),
+            MoveTo(x, top - 1),
+            SetForegroundColor(Color::DarkGrey),
+            Print(keys),
+        )
+        .map_err(Error::Terminal)?;
+        match &racer.board {
+            Some(board) => {
+                let cursors: Vec<_> = board
+                    .cursor
+                    .map(|cursor| (cursor, player_color))
+                    .into_iter()
+                    .collect();
+                draw_grid(stdout, board, x, top, &cursors, &|_, _| None)?
+            }
+            None => {
+                let inner = OPPONENT_PANEL_WIDTH as usize - 2;
+                let filled = inner * progress.cleared.min(100) as usize / 100;
+                queue!(
+                    stdout,
+                    SetForegroundColor(COLOR_CONFIG.border),
+                    MoveTo(x, top),
+                    Print(format!("┌{}┐", "─".repeat(inner))),
+                    MoveTo(x, top + 1),
+                    Print("│"),
+                    SetForegroundColor(color),
+                    Print("█".repeat(filled)),
+                    Print("░".repeat(inner - filled)),
+                    SetForegroundColor(COLOR_CONFIG.border),
+                    Print("│"),
+                    MoveTo(x, top + 2),
+                    Print(format!("└{}┘", "─".repeat(inner))),
+                )
+                .map_err(Error::Terminal)?;
+            }
+        }
+        x += panel_width(racer) + RACE_GAP;
+    }
+    let result_y = top + config.height() as u16 + 3;
+    if let Some(winner) = race.winner() {
+        let message = format!("{} wins!", race.racers[winner].name);
+        queue!(
+            stdout,
+            MoveTo(
+                (cols.saturating_sub(message.chars().count() as u16)) / 2,
+                result_y
+            ),
+            SetForegroundColor(Color::Green),
+            SetAttribute(Attribute::Bold),
+            Print(message),
+            SetAttribute(Attribute::NormalIntensity),
+        )
+        .map_err(Error::Terminal)?;
+    }
+    queue!(
+        stdout,
+        MoveTo(
+            (cols.saturating_sub(footer.chars().count() as u16)) / 2,
+            result_y + 1
+        ),
+        SetForegroundColor(Color::DarkGrey),
+        Print(footer),
+    )
+    .map_err(Error::Terminal)?;
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Render a co-op game: a line per player with their name in their colour, keys and
+/// contribution, then the shared board with every player's cursor and flags in their
+/// colour. Says how the game ended once it is over, above the `footer`.
+pub fn render_coop(stdout: &mut Stdout, coop: &Coop, footer: &str) -> Result<()> {
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+    let board = &coop.board;
+    let board_width = 3 + board.width as u16 * 2 + board.settings.shape.extra_width();
+    let lines: Vec<String> = (0..coop.players.len())
+        .map(|i| {
+            let player = &coop.players[i];
+            let contribution = coop.crew.contribution(i);
+            format!(
+                "{}: {} cleared │ ⚑ {} │ {} chords │ {}",
+                player.name,
+                contribution.revealed,
+                coop.crew.flags_of(i),
+                contribution.chords,
+                player.keys.help
+            )
+        })
+        .collect();
+    let total_width = lines
+        .iter()
+        .map(|line| line.chars().count() as u16)
+        .max()
+        .unwrap_or(0)
+        .max(board_width);
+    // A line per player and a gap, the bordered board, then the result and the footer
+    let header_height = lines.len() as u16 + 1;
+    let total_height = header_height + board.height as u16 + 2 + 3;
+    if cols < total_width || rows < total_height {
+        return render_too_small(stdout, cols, rows);
+    }
+    let top = (rows - total_height) / 2 + header_height;
+    queue!(
+        stdout,
+        SetBackgroundColor(COLOR_CONFIG.background),
+        Clear(terminal::ClearType::All),
+    )
+    .map_err(Error::Terminal)?;
+    let left = (cols - total_width) / 2;
+    for (i, (player, line)) in coop.players.iter().zip(&lines).enumerate() {
+        queue!(
+            stdout,
+            MoveTo(left, top - header_height + i as u16),
+            SetForegroundColor(player.color),
+            SetAttribute(Attribute::Bold),
+            Print(line),
+            SetAttribute(Attribute::NormalIntensity),
+        )
+        .map_err(Error::Terminal)?;
     }
+    let cursors: Vec<_> = coop
+        .players
+        .iter()
+        .map(|player| (player.cursor, player.color))
+        .collect();
+    let flag_color = |x, y| Some(coop.players[coop.crew.flag_owner(x, y)?].color);
+    draw_grid(
+        stdout,
+        board,
+        (cols - board_width) / 2,
+        top,
+        &cursors,
+        &flag_color,
+    )?;
+    let result_y = top + board.height as u16 + 3;
+    let result = match (coop.state, coop.culprit()) {
+        (GameState::Won, _) => Some(("Cleared together!".to_string(), Color::Green)),
+        (GameState::Lost, Some(culprit)) => Some((
+            format!("{} hit a mine", coop.players[culprit].name),
+            COLOR_CONFIG.mine,
+        )),
+        (GameState::Lost, None) => Some(("Time's up".to_string(), COLOR_CONFIG.mine)),
+        _ => None,
+    };
+    if let Some((message, color)) = result {
+        queue!(
+            stdout,
+            MoveTo(
+                (cols.saturating_sub(message.chars().count() as u16)) / 2,
+                result_y
+            ),
+            SetForegroundColor(color),
+            SetAttribute(Attribute::Bold),
+            Print(message),
+            SetAttribute(Attribute::NormalIntensity),
+        )
+        .map_err(Error::Terminal)?;
+    }
+    queue!(
+        stdout,
+        MoveTo(
+            (cols.saturating_sub(footer.chars().count() as u16)) / 2,
+            result_y + 1
+        ),
+        SetForegroundColor(Color::DarkGrey),
+        Print(footer),
+    )
+    .map_err(Error::Terminal)?;
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Render a spectator's view of a game: a line with the clock, flags and how the game
+/// stands, then the board as sent by the player, with their cursor highlighted and
+/// hidden mines marked when coaching. Waits for a game while there is no `frame`.
+pub fn render_frame(stdout: &mut Stdout, frame: Option<&Frame>, footer: &str) -> Result<()> {
+    let Some(frame) = frame else {
+        return render_message(
+            stdout,
+            &["Waiting for the player to start a game", "", footer],
+        );
+    };
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+    let extra_width = frame.shape.extra_width();
+    let board_width = 3 + frame.width as u16 * 2 + extra_width;
+    // A header line and a gap, the bordered board, then the footer
+    let total_height = 2 + frame.height as u16 + 2 + 2;
+    if cols < board_width || rows < total_height {
+        return render_too_small(stdout, cols, rows);
+    }
+    let top = (rows - total_height) / 2 + 2;
+    let left = (cols - board_width) / 2;
+    let clock = match frame.time_left {
+        Some(left) => {
+            let seconds = left.as_secs();
+            format!("⏳ {}:{:02}", seconds / 60, seconds % 60)
+        }
+        None => format!("⏰ {:02}", frame.elapsed.as_secs()),
+    };
+    let standing = match frame.state {
+        GameState::Won => "won",
+        GameState::Lost => "lost",
+        _ if frame.coaching => "playing, mines shown",
+        _ => "playing",
+    };
+    let header = format!(
+        "{} │ ⚑ {}/{} │ {}",
+        clock, frame.flags, frame.mines, standing
+    );
+    queue!(
+        stdout,
+        SetBackgroundColor(COLOR_CONFIG.background),
+        Clear(terminal::ClearType::All),
+        MoveTo(
+            cols.saturating_sub(header.chars().count() as u16) / 2,
+            top - 2
+        ),
+        SetForegroundColor(COLOR_CONFIG.counter),
+        Print(header),
+        SetForegroundColor(COLOR_CONFIG.border),
+        MoveTo(left, top),
+        Print(format!(
+            "┌{}{}─┐",
+            "──".repeat(frame.width),
+            "─".repeat(extra_width as usize)
+        )),
+    )
+    .map_err(Error::Terminal)?;
+    for (y, row) in frame.rows.iter().enumerate() {
+        // Hex rows shift by where they are on the board, not on screen
+        let indent = frame.shape.indent(frame.origin.1 + y as isize);
+        queue!(
+            stdout,
+            MoveTo(left, top + 1 + y as u16),
+            SetForegroundColor(COLOR_CONFIG.border),
+            Print("│ "),
+            Print(" ".repeat(indent as usize)),
+        )
+        .map_err(Error::Terminal)?;
+        for (x, cell) in row.chars().enumerate() {
+            let highlight = if frame.cursor == Some((x as isize, y as isize)) {
+                COLOR_CONFIG.players[0]
+            } else {
+                COLOR_CONFIG.background
+            };
+            let (symbol, color) = match cell {
+                '#' => ("■ ".to_string(), COLOR_CONFIG.hidden_cell),
+                'F' => ("⚑ ".to_string(), COLOR_CONFIG.flagged_cell),
+                '?' => ("? ".to_string(), COLOR_CONFIG.question_cell),
+                'X' => ("💣".to_string(), COLOR_CONFIG.mine),
+                '*' => ("* ".to_string(), COLOR_CONFIG.flagged_cell),
+                '0' => ("  ".to_string(), COLOR_CONFIG.empty_cell),
+                '+' => {
+                    let n = frame
+                        .numbers
+                        .iter()
+                        .find(|(cell, _)| *cell == (x, y))
+                        .map_or(0, |&(_, n)| n);
+                    (format!("{:<2}", n), number_color(n))
+                }
+                digit => {
+                    let n = digit.to_digit(10).unwrap_or_default() as u8;
+                    (format!("{} ", n), number_color(n))
+                }
+            };
+            queue!(
+                stdout,
+                SetBackgroundColor(highlight),
+                SetForegroundColor(color),
+                Print(symbol),
+            )
+            .map_err(Error::Terminal)?;
+        }
+        queue!(
+            stdout,
+            SetBackgroundColor(COLOR_CONFIG.background),
+            SetForegroundColor(COLOR_CONFIG.border),
+            Print(" ".repeat((extra_width - indent) as usize)),
+            Print("│"),
+        )
+        .map_err(Error::Terminal)?;
+    }
+    queue!(
+        stdout,
+        MoveTo(left, top + 1 + frame.height as u16),
+        Print(format!(
+            "└{}{}─┘",
+            "──".repeat(frame.width),
+            "─".repeat(extra_width as usize)
+        )),
+        MoveTo(
+            cols.saturating_sub(footer.chars().count() as u16) / 2,
+            top + 3 + frame.height as u16
+        ),
+        SetForegroundColor(Color::DarkGrey),
+        Print(footer),
+    )
+    .map_err(Error::Terminal)?;
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Draw what an agent is thinking over a board drawn by [`render_game_board`]: the
+/// hidden cells it knows are safe in green and those it knows are mines in red, the
+/// cell it chose highlighted, and a line under the board explaining the choice, above
+/// the `footer`.
+pub fn render_thinking(
+    stdout: &mut Stdout,
+    board: &Board,
+    thinking: Option<&Thinking>,
+    footer: &str,
+) -> Result<()> {
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+    let required_width = 2 + board.width * 2 + board.settings.shape.extra_width() as usize;
+    if cols < required_width as u16 || rows < 2 + board.height as u16 {
+        return Ok(());
+    }
+    let (start_x, start_y) = board.get_board_start_pos()?;
+    let cell_at = |(x, y): (isize, isize)| {
+        let indent = board.settings.shape.indent(y);
+        MoveTo(start_x + 2 + indent + 2 * x as u16, start_y + 1 + y as u16)
+    };
+    let hidden = |&(x, y): &(isize, isize)| {
+        board
+            .get_cell(x, y)
+            .is_some_and(|cell| matches!(cell.state, CellState::Hidden | CellState::Question))
+    };
+    let mut explanation = String::new();
+    if let Some(thinking) = thinking {
+        for (cells, color) in [
+            (&thinking.safe, Color::Green),
+            (&thinking.mines, Color::Red),
+        ] {
+            for &cell in cells.iter().filter(|cell| hidden(cell)) {
+                queue!(stdout, cell_at(cell), SetForegroundColor(color), Print("■"),)
+                    .map_err(Error::Terminal)?;
+            }
+        }
+        if let Some(
+            Action::Reveal { x, y }
+            | Action::Flag { x, y }
+            | Action::Chord { x, y }
+            | Action::FlagChord { x, y },
+        ) = thinking.action
+        {
+            let color = if thinking.chance == Some(0.0) {
+                Color::Green
+            } else {
+                COLOR_CONFIG.hidden_cell
+            };
+            queue!(
+                stdout,
+                cell_at((x, y)),
+                SetBackgroundColor(COLOR_CONFIG.players[0]),
+                SetForegroundColor(color),
+                Print("■ "),
+                SetBackgroundColor(COLOR_CONFIG.background),
+            )
+            .map_err(Error::Terminal)?;
+            let choice = match thinking.chance {
+                Some(chance) if chance <= 0.0 => format!("revealing ({}, {}), known safe", x, y),
+                Some(chance) => format!(
+                    "guessing ({}, {}), {:.0}% chance of a mine",
+                    x,
+                    y,
+                    chance * 100.0
+                ),
+                None => format!("guessing ({}, {}) at random", x, y),
+            };
+            explanation = format!(
+                "{} safe, {} mines found │ {}",
+                thinking.safe.iter().filter(|cell| hidden(cell)).count(),
+                thinking.mines.iter().filter(|cell| hidden(cell)).count(),
+                choice
+            );
+        }
+    }
+    // Below the status line
+    let text_y = start_y + 3 + board.height as u16;
+    for (i, (line, color)) in [
+        (explanation.as_str(), Color::Black),
+        (footer, Color::DarkGrey),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if text_y + (i as u16) < rows {
+            queue!(
+                stdout,
+                MoveTo(
+                    cols.saturating_sub(line.chars().count() as u16) / 2,
+                    text_y + i as u16
+                ),
+                SetForegroundColor(color),
+                Print(line),
+            )
+            .map_err(Error::Terminal)?;
+        }
+    }
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Render lines of text in the middle of the screen, such as while waiting for a race.
+pub fn render_message(stdout: &mut Stdout, lines: &[&str]) -> Result<()> {
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
+    let top = rows.saturating_sub(lines.len() as u16) / 2;
+    queue!(
+        stdout,
+        SetBackgroundColor(COLOR_CONFIG.background),
+        Clear(terminal::ClearType::All),
+        SetForegroundColor(Color::Black),
+    )
+    .map_err(Error::Terminal)?;
+    for (i, line) in lines.iter().enumerate() {
+        let x = cols.saturating_sub(line.chars().count() as u16) / 2;
+        queue!(stdout, MoveTo(x, top + i as u16), Print(line)).map_err(Error::Terminal)?;
+    }
+    stdout.flush().map_err(Error::Terminal)?;
+    Ok(())
+}
+
+/// Build the status line text, dropping the lowest priority fields until it fits `max_width`.
+///
+/// Fields keep their display order; priority follows the field order in `StatusBarConfig`.
+fn format_status_line(board: &Board, max_width: usize) -> String {
+    let config = board.settings.status_bar;
+    let revealed = board.get_revealed_count();
+    let safe = board.get_safe_cells_count().max(1);
+    let endless = board.mode == GameMode::Endless;
+    let mut fields: Vec<String> = Vec::new();
+    if config.progress && endless {
+        fields.push(format!("{} cleared", revealed));
+    } else if config.progress {
+        fields.push(format!("{}/{}", revealed, safe));
+    }
+    if config.percent && !endless {
+        fields.push(format!("{}%", revealed * 100 / safe));
+    }
+    if config.bbbv
+        && let Some((solved, total)) = board.get_3bv_progress()
+    {
+        fields.push(format!("3BV {}/{}", solved, total));
+    }
+    if config.flags {
+        fields.push(format!("⚑ {}", board.get_flags_count()));
+    }
+    if config.difficulty {
+        fields.push(board.get_difficulty_name().to_string());
+    }
+    if config.modifiers {
+        fields.extend(board.get_modifiers().iter().map(|m| m.to_string()));
+    }
+    if config.cursor {
+        match board.cursor {
+            Some((x, y)) => fields.push(format!("({}, {})", x + 1, y + 1)),
+            None => fields.push("(-, -)".to_string()),
+        }
+    }
+    if config.seed {
+        fields.push(format!("seed {:016x}", board.get_seed()));
+    }
+    while !fields.is_empty() && fields.join(" │ ").chars().count() > max_width {
+        fields.pop();
+    }
+    fields.join(" │ ")
+}
+
+/// Build the line listing lies whose shown and true numbers don't both fit in the
+/// cell, dropping lies from the end until it fits `max_width`. Empty when there are none.
+fn format_wide_lies(board: &Board, max_width: usize) -> String {
+    let mut lies: Vec<String> = board
+        .get_lies()
+        .into_iter()
+        .filter_map(|((x, y), shown)| {
+            let CellKind::Number(n) = board.get_cell(x as isize, y as isize)?.kind else {
+                return None;
+            };
+            (shown >= 10 || n >= 10)
+                .then(|| format!("({}, {}) showed {}, was {}", x + 1, y + 1, shown, n))
+        })
+        .collect();
+    let line = |lies: &[String]| format!("Lies: {}", lies.join(" │ "));
+    while !lies.is_empty() && line(&lies).chars().count() > max_width {
+        lies.pop();
+    }
+    if lies.is_empty() {
+        String::new()
+    } else {
+        line(&lies)
+    }
+}
+
+fn format_box_with_value(value: &str) -> Vec<String> {
+    // Emoji take two columns, and there is a space on each side of the value
+    let len = value
+        .chars()
+        .map(|ch| {
+            if matches!(ch, '💣' | '⏰' | '⏳') {
+                2
+            } else {
+                1
+            }
+        })
+        .sum::<usize>()
+        + 2;
     let top_bottom = format!("┌{}┐\n", "─".repeat(len));
     let middle = format!("│ {} │\n", value);
     let bottom = format!("└{}┘", "─".repeat(len));
@@ -239,7 +997,7 @@ fn format_box_with_value(value: &str) -> Vec<String> {
 
 /// Render the main game menu with ASCII art title and menu items.
 /// Centers the menu in the terminal and highlights the hovered item.
-pub fn render_game_menu(stdout: &mut Stdout, menu: &Menu) -> anyhow::Result<()> {
+pub fn render_game_menu(stdout: &mut Stdout, menu: &Menu) -> Result<()> {
     let title_art = [
         "  _____ _____ ____  __  __ ______        _______ _____ ____  _____ ____  ",
         " |_   _| ____|  _ \\|  \\/  / ___\\ \\      / / ____| ____|  _ \\| ____|  _ \\ ",
@@ -248,7 +1006,7 @@ pub fn render_game_menu(stdout: &mut Stdout, menu: &Menu) -> anyhow::Result<()>
         "   |_| |_____|_| \\_\\_|  |_|____/  \\_/\\_/  |_____|_____|_|   |_____|_| \\_\\",
     ];
 
-    let (cols, rows) = crossterm::terminal::size()?;
+    let (cols, rows) = crossterm::terminal::size().map_err(Error::Terminal)?;
     let art_width = title_art[0].len() as u16;
     let art_height = title_art.len() as u16;
     let art_y = (rows.saturating_sub(art_height + menu.len() as u16 + 2)) / 2;
@@ -258,10 +1016,11 @@ pub fn render_game_menu(stdout: &mut Stdout, menu: &Menu) -> anyhow::Result<()>
         stdout,
         Clear(terminal::ClearType::All),
         SetForegroundColor(Color::Black),
-    )?;
+    )
+    .map_err(Error::Terminal)?;
     // Draw ASCII art title
     for (i, line) in title_art.iter().enumerate() {
-        queue!(stdout, MoveTo(art_x, art_y + i as u16), Print(line),)?;
+        queue!(stdout, MoveTo(art_x, art_y + i as u16), Print(line),).map_err(Error::Terminal)?;
     }
     render_menu(stdout, menu, cols, art_height, art_y)?;
     Ok(())
@@ -275,13 +1034,17 @@ pub fn render_menu(
     cols: u16,
     art_height: u16,
     art_y: u16,
-) -> Result<(), anyhow::Error> {
+) -> Result<()> {
     for (i, item) in menu.items.iter().enumerate() {
         let (label, is_adjustable) = match item {
             MenuItem::Main { name, .. } => (name.to_string(), false),
-            MenuItem::Custom { name, value, .. } => {
-                (format!("{}: {}", name, value), true)
-            }
+            MenuItem::Custom { name, value, .. } => (format!("{}: {}", name, value), true),
+            MenuItem::Choice {
+                name,
+                options,
+                selected,
+                ..
+            } => (format!("{}: {}", name, options[*selected]), true),
         };
         let menu_y = art_y + art_height + 1 + i as u16;
         let menu_x = (cols.saturating_sub(label.len() as u16)) / 2;
@@ -291,17 +1054,12 @@ pub fn render_menu(
                 stdout,
                 MoveTo(menu_x - 2, menu_y),
                 SetForegroundColor(Color::Yellow),
-            )?;
+            )
+            .map_err(Error::Terminal)?;
             if is_adjustable {
-                queue!(stdout,
-                    Print("< "),
-                    Print(label),
-                    Print(" >")
-                )?;
+                queue!(stdout, Print("< "), Print(label), Print(" >")).map_err(Error::Terminal)?;
             } else {
-                queue!(stdout,
-                    Print("➤ "),
-                    Print(label),)?;
+                queue!(stdout, Print("➤ "), Print(label),).map_err(Error::Terminal)?;
             }
         } else {
             queue!(
@@ -311,9 +1069,33 @@ pub fn render_menu(
                 Print("  "),
                 SetForegroundColor(Color::Black),
                 Print(label),
-            )?;
+            )
+            .map_err(Error::Terminal)?;
         }
     }
-    stdout.flush()?;
+    // Draw the notes, then the last error, below the items
+    let notes_y = art_y + art_height + 2 + menu.len() as u16;
+    for (i, note) in menu.notes.iter().enumerate() {
+        let note_x = (cols.saturating_sub(note.chars().count() as u16)) / 2;
+        queue!(
+            stdout,
+            MoveTo(note_x, notes_y + i as u16),
+            SetForegroundColor(Color::DarkGrey),
+            Print(note),
+        )
+        .map_err(Error::Terminal)?;
+    }
+    if let Some(error) = &menu.error {
+        let error_y = notes_y + menu.notes.len() as u16 + (!menu.notes.is_empty()) as u16;
+        let error_x = (cols.saturating_sub(error.len() as u16)) / 2;
+        queue!(
+            stdout,
+            MoveTo(error_x, error_y),
+            SetForegroundColor(Color::Red),
+            Print(error),
+        )
+        .map_err(Error::Terminal)?;
+    }
+    stdout.flush().map_err(Error::Terminal)?;
     Ok(())
 }
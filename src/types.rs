@@ -1,9 +1,12 @@
+//! Types and configuration for termsweeper.
+//!
+//! This module defines the core types used for game state, board cells, menu items,
+//! and color configuration for the terminal UI.
+use crate::shape::{Neighbourhood, Shape};
+use crate::topology::Topology;
+use crate::{Error, Result};
 use ::crossterm::style::Color;
-
-///! Types and configuration for termsweeper.
-///!
-///! This module defines the core types used for game state, board cells, menu items,
-///! and color configuration for the terminal UI.
+use chrono::NaiveDate;
 
 /// Represents the overall state of the game.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -16,6 +19,12 @@ pub enum GameState {
     Won,
     /// The player has lost the game.
     Lost,
+    /// Two players are racing split-screen.
+    Race,
+    /// Two players are sharing one board.
+    Coop,
+    /// A built-in agent is playing for show.
+    Demo,
     /// The game is exiting.
     Exit,
 }
@@ -25,34 +34,104 @@ pub enum GameState {
 pub enum CellState {
     Hidden,
     Revealed,
-    Flagged,
+    /// Flagged as holding this many mines, 1 unless cells can hold several.
+    Flagged(u8),
+    /// Marked with a "?" as uncertain. Not counted as a flag.
+    Question,
+}
+
+impl CellState {
+    /// Returns the number of mines the player has flagged on the cell.
+    pub fn flags(self) -> usize {
+        match self {
+            CellState::Flagged(flags) => flags as usize,
+            _ => 0,
+        }
+    }
 }
 
 /// Represents the kind of a cell (mine or number).
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CellKind {
-    Mine,
+    /// Holds this many mines, 1 unless cells can hold several.
+    Mine(u8),
+    /// Safe, showing the number of mines around it.
     Number(u8),
 }
 
+impl CellKind {
+    /// Returns true if the cell holds at least one mine.
+    pub fn is_mine(self) -> bool {
+        matches!(self, CellKind::Mine(_))
+    }
+
+    /// Returns the number of mines in the cell.
+    pub fn mines(self) -> usize {
+        match self {
+            CellKind::Mine(mines) => mines as usize,
+            CellKind::Number(_) => 0,
+        }
+    }
+}
+
+/// A player action on the cell at (x, y), independent of the input device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Reveal a hidden cell.
+    Reveal { x: isize, y: isize },
+    /// Toggle the flag on a cell (or cycle through "?" when enabled).
+    Flag { x: isize, y: isize },
+    /// Reveal the unflagged neighbours of a number whose flags match it.
+    Chord { x: isize, y: isize },
+    /// Flag the hidden neighbours of a number when they must all be mines.
+    FlagChord { x: isize, y: isize },
+}
+
 /// Represents the type of a menu item.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MenuItemType {
-    Beginnner,
-    Intermediate,
-    Expert,
+    Preset(Preset),
     Custom,
     Exit,
     Width,
     Height,
     Mines,
     Confirm,
+    Settings,
+    ClickModel,
+    FlagChord,
+    StatusBar,
+    /// Opens the menu of status line fields.
+    StatusFields,
+    StatusField(StatusField),
+    QuestionMarks,
+    QuestionBlocksChord,
+    FirstClick,
+    Back,
+    Daily,
+    PlayDaily,
+    TimeLimit,
+    TimeBonus,
+    Lives,
+    Stats,
+    Endless,
+    Topology,
+    Shape,
+    Neighbourhood,
+    MinesPerCell,
+    Noise,
+    Race,
+    RacePreset(Preset),
+    Coop,
+    CoopPreset(Preset),
+    RevealForSpectators,
+    Demo,
 }
 
 /// Represents a menu item in the UI.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MenuItem {
-    /// Main menu items (Beginner, Itermediate, Expert, Custom, Exit)
+    /// Main menu items (presets, Custom, Settings, Exit)
     Main {
         item_type: MenuItemType,
         name: &'static str,
@@ -64,6 +143,13 @@ pub enum MenuItem {
         name: &'static str,
         value: usize,
     },
+    /// Settings menu item cycling through a fixed list of options.
+    Choice {
+        item_type: MenuItemType,
+        name: &'static str,
+        options: &'static [&'static str],
+        selected: usize,
+    },
 }
 
 /// Represents a cell on the board.
@@ -76,11 +162,465 @@ pub struct CellBox {
 }
 
 /// Configuration for a Minesweeper game.
+///
+/// Configurations are always checked: build them with [`GameConfig::new`],
+/// [`GameConfig::with_density`] or [`GameConfig::fixed`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct GameConfig {
-    pub width: usize,
-    pub height: usize,
-    pub mines: usize,
+    width: usize,
+    height: usize,
+    mines: usize,
+}
+
+impl GameConfig {
+    /// Creates a configuration, checking it against the board size and mine limits.
+    ///
+    /// At least one cell must stay free of mines.
+    pub fn new(width: usize, height: usize, mines: usize) -> Result<GameConfig> {
+        if !(MIN_WIDTH..=MAX_WIDTH).contains(&width) {
+            return Err(Error::InvalidConfig(format!(
+                "width must be between {} and {}, got {}",
+                MIN_WIDTH, MAX_WIDTH, width
+            )));
+        }
+        if !(MIN_HEIGHT..=MAX_HEIGHT).contains(&height) {
+            return Err(Error::InvalidConfig(format!(
+                "height must be between {} and {}, got {}",
+                MIN_HEIGHT, MAX_HEIGHT, height
+            )));
+        }
+        let max_mines = width * height - 1;
+        if !(MIN_MINES..=max_mines).contains(&mines) {
+            return Err(Error::InvalidConfig(format!(
+                "mines must be between {} and {} on a {}x{} board, got {}",
+                MIN_MINES, max_mines, width, height, mines
+            )));
+        }
+        Ok(GameConfig {
+            width,
+            height,
+            mines,
+        })
+    }
+
+    /// Creates a configuration with the given fraction of cells holding mines,
+    /// e.g. `0.206` for Expert density.
+    pub fn with_density(width: usize, height: usize, density: f64) -> Result<GameConfig> {
+        if !(density > 0.0 && density < 1.0) {
+            return Err(Error::InvalidConfig(format!(
+                "mine density must be between 0 and 1, got {}",
+                density
+            )));
+        }
+        let mines = ((width * height) as f64 * density).round() as usize;
+        GameConfig::new(width, height, mines.max(MIN_MINES))
+    }
+
+    /// Creates the configuration of a board whose mines are placed by hand, such
+    /// as a layout or a board file.
+    ///
+    /// These boards can be any size from 1x1 up to the largest custom board, and
+    /// hold any number of mines as long as one cell stays free.
+    pub fn fixed(width: usize, height: usize, mines: usize) -> Result<GameConfig> {
+        if !(1..=MAX_WIDTH).contains(&width) || !(1..=MAX_HEIGHT).contains(&height) {
+            return Err(Error::InvalidConfig(format!(
+                "boards can be at most {}x{}, got {}x{}",
+                MAX_WIDTH, MAX_HEIGHT, width, height
+            )));
+        }
+        if mines >= width * height {
+            return Err(Error::InvalidConfig(format!(
+                "{} mines leave no free cell on a {}x{} board",
+                mines, width, height
+            )));
+        }
+        Ok(GameConfig {
+            width,
+            height,
+            mines,
+        })
+    }
+
+    /// Returns the board width in cells.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the board height in cells.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of mines.
+    pub fn mines(&self) -> usize {
+        self.mines
+    }
+
+    /// Checks that the board leaves room for the cells the first click policy of
+    /// `settings` keeps free.
+    pub fn check_first_click(self, settings: &Settings) -> Result<GameConfig> {
+        let protected = settings
+            .first_click
+            .protected_cells(settings.shape, settings.neighbourhood)
+            .max(1);
+        if self.mines + protected > self.width * self.height {
+            return Err(Error::InvalidConfig(format!(
+                "{} mines don't fit on a {}x{} board with this first click policy",
+                self.mines, self.width, self.height
+            )));
+        }
+        Ok(self)
+    }
+
+    /// Returns the fraction of cells holding mines.
+    pub fn density(&self) -> f64 {
+        self.mines as f64 / (self.width * self.height) as f64
+    }
+}
+
+/// Named difficulty presets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// 9x9 with 10 mines.
+    Beginner,
+    /// 16x16 with 40 mines.
+    Intermediate,
+    /// 30x16 with 99 mines.
+    Expert,
+    /// 40x16 at Expert density.
+    ExpertWide,
+    /// 30x20 with 130 mines.
+    Evil,
+}
+
+impl Preset {
+    /// All presets, in main menu order.
+    pub const ALL: [Preset; 5] = [
+        Preset::Beginner,
+        Preset::Intermediate,
+        Preset::Expert,
+        Preset::ExpertWide,
+        Preset::Evil,
+    ];
+
+    /// Returns the display name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Beginner => "Beginner",
+            Preset::Intermediate => "Intermediate",
+            Preset::Expert => "Expert",
+            Preset::ExpertWide => "Expert Wide",
+            Preset::Evil => "Evil",
+        }
+    }
+
+    /// Returns the name used on the command line.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Preset::Beginner => "beginner",
+            Preset::Intermediate => "intermediate",
+            Preset::Expert => "expert",
+            Preset::ExpertWide => "expert-wide",
+            Preset::Evil => "evil",
+        }
+    }
+
+    /// Returns the board configuration.
+    pub fn config(&self) -> GameConfig {
+        let config = match self {
+            Preset::Beginner => GameConfig::new(9, 9, 10),
+            Preset::Intermediate => GameConfig::new(16, 16, 40),
+            Preset::Expert => GameConfig::new(30, 16, 99),
+            Preset::ExpertWide => {
+                GameConfig::with_density(40, 16, Preset::Expert.config().density())
+            }
+            Preset::Evil => GameConfig::new(30, 20, 130),
+        };
+        config.expect("presets are within the board limits")
+    }
+
+    /// Finds the preset with the given command line name, ignoring case.
+    pub fn from_key(key: &str) -> Option<Preset> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.key().eq_ignore_ascii_case(key))
+    }
+
+    /// Finds the preset matching a board configuration.
+    pub fn from_config(config: GameConfig) -> Option<Preset> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.config() == config)
+    }
+}
+
+/// Selects which fields the status line under the board shows.
+///
+/// Fields are listed in the order they are dropped when the terminal is too narrow,
+/// last one first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatusBarConfig {
+    /// Show the status line at all.
+    pub enabled: bool,
+    /// Revealed cells out of the total safe cells.
+    pub progress: bool,
+    /// Percentage of safe cells revealed.
+    pub percent: bool,
+    /// 3BV solved out of the board's 3BV.
+    pub bbbv: bool,
+    /// Number of flags placed.
+    pub flags: bool,
+    /// Difficulty name.
+    pub difficulty: bool,
+    /// Active gameplay modifiers.
+    pub modifiers: bool,
+    /// Coordinate of the cell under the cursor.
+    pub cursor: bool,
+    /// Seed used to place the mines.
+    pub seed: bool,
+}
+
+/// A field the status line can show, see [`StatusBarConfig`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusField {
+    Progress,
+    Percent,
+    Bbbv,
+    Flags,
+    Difficulty,
+    Modifiers,
+    Cursor,
+    Seed,
+}
+
+impl StatusField {
+    /// Fields offered in the status line menu.
+    pub const ALL: [StatusField; 8] = [
+        StatusField::Progress,
+        StatusField::Percent,
+        StatusField::Bbbv,
+        StatusField::Flags,
+        StatusField::Difficulty,
+        StatusField::Modifiers,
+        StatusField::Cursor,
+        StatusField::Seed,
+    ];
+    /// Display names, matching the order of [`StatusField::ALL`].
+    pub const NAMES: [&'static str; 8] = [
+        "Progress",
+        "Percent",
+        "3BV",
+        "Flags",
+        "Difficulty",
+        "Modifiers",
+        "Cursor",
+        "Seed",
+    ];
+}
+
+impl StatusBarConfig {
+    /// Returns whether the status line shows `field`.
+    pub fn shows(&self, field: StatusField) -> bool {
+        match field {
+            StatusField::Progress => self.progress,
+            StatusField::Percent => self.percent,
+            StatusField::Bbbv => self.bbbv,
+            StatusField::Flags => self.flags,
+            StatusField::Difficulty => self.difficulty,
+            StatusField::Modifiers => self.modifiers,
+            StatusField::Cursor => self.cursor,
+            StatusField::Seed => self.seed,
+        }
+    }
+
+    /// Shows or hides `field`.
+    pub fn set(&mut self, field: StatusField, shown: bool) {
+        let flag = match field {
+            StatusField::Progress => &mut self.progress,
+            StatusField::Percent => &mut self.percent,
+            StatusField::Bbbv => &mut self.bbbv,
+            StatusField::Flags => &mut self.flags,
+            StatusField::Difficulty => &mut self.difficulty,
+            StatusField::Modifiers => &mut self.modifiers,
+            StatusField::Cursor => &mut self.cursor,
+            StatusField::Seed => &mut self.seed,
+        };
+        *flag = shown;
+    }
+}
+
+/// Selects which mouse actions chord (reveal all unflagged neighbours of a number).
+///
+/// In every model a chord only happens when the number of flags around the
+/// number equals the number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClickModel {
+    /// Left click on a revealed number chords.
+    Classic,
+    /// Middle click on a revealed number chords.
+    MiddleClick,
+    /// Pressing left and right together on a revealed number chords.
+    LeftRight,
+}
+
+impl ClickModel {
+    /// All click models, in settings menu order.
+    pub const ALL: [ClickModel; 3] = [
+        ClickModel::Classic,
+        ClickModel::MiddleClick,
+        ClickModel::LeftRight,
+    ];
+    /// Display names, matching the order of [`ClickModel::ALL`].
+    pub const NAMES: [&'static str; 3] = ["Classic", "Middle click", "Left + right"];
+}
+
+/// Selects which cells the first click keeps free of mines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstClick {
+    /// The clicked cell is never a mine.
+    SafeCell,
+    /// The clicked cell and its neighbours are never mines, so the first click opens an area.
+    Opening,
+    /// Mines are placed purely at random; the first click can lose.
+    Unprotected,
+}
+
+impl FirstClick {
+    /// All first click policies, in settings menu order.
+    pub const ALL: [FirstClick; 3] = [
+        FirstClick::SafeCell,
+        FirstClick::Opening,
+        FirstClick::Unprotected,
+    ];
+    /// Display names, matching the order of [`FirstClick::ALL`].
+    pub const NAMES: [&'static str; 3] = ["Safe cell", "Opening", "No protection"];
+
+    /// Returns how many cells this policy keeps free of mines, at most, on a board
+    /// with the given cells and neighbourhood.
+    pub fn protected_cells(&self, shape: Shape, neighbourhood: Neighbourhood) -> usize {
+        match self {
+            FirstClick::SafeCell => 1,
+            FirstClick::Opening => 1 + neighbourhood.offsets(shape, 0).len(),
+            FirstClick::Unprotected => 0,
+        }
+    }
+}
+
+/// Player preferences that carry over between games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Whether the status line is shown, and which fields it shows.
+    pub status_bar: StatusBarConfig,
+    /// Which mouse actions chord.
+    pub click_model: ClickModel,
+    /// Right click on a revealed number flags all its hidden neighbours
+    /// when they must all be mines.
+    pub flag_chord: bool,
+    /// Right click cycles Hidden -> Flagged -> Question -> Hidden instead of
+    /// toggling the flag.
+    pub question_marks: bool,
+    /// Chording skips cells marked with a "?" as well as flagged ones.
+    pub question_blocks_chord: bool,
+    /// Which cells the first click keeps free of mines.
+    pub first_click: FirstClick,
+    /// Seconds to clear the board in, counted from the first click. Running out loses.
+    pub time_limit: Option<u32>,
+    /// Seconds added to the time limit for each opening revealed.
+    pub time_bonus: u32,
+    /// Mines that can be hit before the game is lost; 1 is classic play.
+    pub lives: u8,
+    /// Whether the edges of the board wrap around.
+    pub topology: Topology,
+    /// Square or hexagonal cells.
+    pub shape: Shape,
+    /// The cells each number counts mines over.
+    pub neighbourhood: Neighbourhood,
+    /// Most mines a single cell can hold. Numbers count mines rather than mined
+    /// cells, and right click steps a flag through the counts.
+    pub mines_per_cell: u8,
+    /// Whether revealed numbers may lie.
+    pub noise: Noise,
+    /// Spectators are shown where the hidden mines are, for coaching.
+    pub reveal_for_spectators: bool,
+}
+
+/// Time limits offered in the settings menu, in seconds.
+pub const TIME_LIMITS: [Option<u32>; 7] = [
+    None,
+    Some(30),
+    Some(60),
+    Some(120),
+    Some(180),
+    Some(300),
+    Some(600),
+];
+/// Display names, matching the order of [`TIME_LIMITS`].
+pub const TIME_LIMIT_NAMES: [&str; 7] = ["Off", "0:30", "1:00", "2:00", "3:00", "5:00", "10:00"];
+/// Time bonuses per opening offered in the settings menu, in seconds.
+pub const TIME_BONUSES: [u32; 4] = [0, 1, 3, 5];
+/// Display names, matching the order of [`TIME_BONUSES`].
+pub const TIME_BONUS_NAMES: [&str; 4] = ["Off", "+1s", "+3s", "+5s"];
+
+/// Numbers of lives offered in the settings menu.
+pub const LIVES: [u8; 4] = [1, 2, 3, 5];
+/// Display names, matching the order of [`LIVES`].
+pub const LIVES_NAMES: [&str; 4] = ["Off", "2", "3", "5"];
+
+/// Most mines any cell can hold, in generated boards and layouts alike.
+pub const MAX_MINES_PER_CELL: u8 = 3;
+/// Most mines a cell can hold, as offered in the settings menu.
+pub const MINES_PER_CELL: [u8; 3] = [1, 2, MAX_MINES_PER_CELL];
+/// Display names, matching the order of [`MINES_PER_CELL`].
+pub const MINES_PER_CELL_NAMES: [&str; 3] = ["1", "Up to 2", "Up to 3"];
+
+/// Whether revealed numbers may lie, for practice at reading uncertain boards.
+///
+/// A lying number is off by one from the truth, is never shown on an empty cell
+/// and never shows as empty. The true values are shown when the game ends.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Noise {
+    /// Every number tells the truth.
+    Off,
+    /// Each number lies with this percent chance.
+    Noisy(u8),
+    /// One number in every [`NOISE_REGION`] x [`NOISE_REGION`] block of cells lies.
+    OnePerRegion,
+}
+
+impl Noise {
+    /// Lying number options offered in the settings menu.
+    pub const ALL: [Noise; 4] = [
+        Noise::Off,
+        Noise::Noisy(10),
+        Noise::Noisy(25),
+        Noise::OnePerRegion,
+    ];
+    /// Display names, matching the order of [`Noise::ALL`].
+    pub const NAMES: [&'static str; 4] = ["Off", "10% off by 1", "25% off by 1", "One per region"];
+}
+/// Side of the square regions, in cells, that each get one lie with [`Noise::OnePerRegion`].
+pub const NOISE_REGION: usize = 4;
+
+/// Why a game was lost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LossReason {
+    /// A mine was revealed.
+    Mine,
+    /// The time limit ran out.
+    TimeUp,
+}
+
+/// Kind of game being played, for rules and results that differ between modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GameMode {
+    /// Regular game with a random board.
+    #[default]
+    Classic,
+    /// The daily challenge for `date`. Only the first attempt of the day is `official`;
+    /// later ones are practice and not recorded.
+    Daily { date: NaiveDate, official: bool },
+    /// Unbounded board scored by cells cleared before the first mine.
+    Endless,
 }
 
 /// Color configuration for the terminal UI.
@@ -88,13 +628,21 @@ pub struct ColorConfig {
     pub background: Color,
     pub hidden_cell: Color,
     pub flagged_cell: Color,
+    pub question_cell: Color,
     pub mine: Color,
     pub empty_cell: Color,
-    pub number: [Color; 8],
+    /// Colors of the numbers 1 to [`MAX_NUMBER`], in order.
+    pub number: [Color; MAX_NUMBER as usize],
     pub border: Color,
     pub counter: Color,
+    pub status: Color,
+    /// Cursor and flag colours of the players sharing a screen, in order.
+    pub players: [Color; 4],
 }
 
+/// Highest number a cell can show, reached by the radius 2 neighbourhood.
+pub const MAX_NUMBER: u8 = 24;
+
 /// Minimum allowed board width.
 pub const MIN_WIDTH: usize = 5;
 /// Maximum allowed board width.
@@ -113,11 +661,57 @@ pub const DEFAULT_CONFIG: GameConfig = GameConfig {
     mines: 25,
 };
 
+/// Default settings, with every status line field shown.
+pub const DEFAULT_SETTINGS: Settings = Settings {
+    status_bar: StatusBarConfig {
+        enabled: true,
+        progress: true,
+        percent: true,
+        bbbv: true,
+        flags: true,
+        difficulty: true,
+        modifiers: true,
+        cursor: true,
+        seed: true,
+    },
+    click_model: ClickModel::Classic,
+    flag_chord: false,
+    question_marks: false,
+    question_blocks_chord: false,
+    first_click: FirstClick::SafeCell,
+    time_limit: None,
+    time_bonus: 0,
+    lives: 1,
+    topology: Topology::Bounded,
+    shape: Shape::Square,
+    neighbourhood: Neighbourhood::Touching,
+    mines_per_cell: 1,
+    noise: Noise::Off,
+    reveal_for_spectators: false,
+};
+
+/// Width of the visible area of an endless board, in cells.
+pub const ENDLESS_WIDTH: usize = 30;
+/// Height of the visible area of an endless board, in cells.
+pub const ENDLESS_HEIGHT: usize = 16;
+/// Configuration of the visible area of an endless board. Its mines are dealt
+/// chunk by chunk instead, see [`ENDLESS_MINES_PER_CHUNK`].
+pub const ENDLESS_CONFIG: GameConfig = GameConfig {
+    width: ENDLESS_WIDTH,
+    height: ENDLESS_HEIGHT,
+    mines: 0,
+};
+/// Mines in each 16x16 chunk of an endless board, a density between intermediate and expert.
+pub const ENDLESS_MINES_PER_CHUNK: usize = 45;
+/// Closest an endless board lets the last cell played get to the edge of the view.
+pub const ENDLESS_MARGIN: isize = 3;
+
 /// Default color configuration for the UI.
 pub const COLOR_CONFIG: ColorConfig = ColorConfig {
     background: Color::White,
     hidden_cell: Color::DarkGrey,
     flagged_cell: Color::DarkRed,
+    question_cell: Color::DarkMagenta,
     mine: Color::Black,
     empty_cell: Color::Black,
     number: [
@@ -129,7 +723,31 @@ pub const COLOR_CONFIG: ColorConfig = ColorConfig {
         Color::Cyan,       // 6
         Color::White,      // 7
         Color::Grey,       // 8
+        // Only the larger neighbourhoods reach these: oranges through reds to purples
+        Color::AnsiValue(214), // 9
+        Color::AnsiValue(208), // 10
+        Color::AnsiValue(202), // 11
+        Color::AnsiValue(196), // 12
+        Color::AnsiValue(160), // 13
+        Color::AnsiValue(124), // 14
+        Color::AnsiValue(88),  // 15
+        Color::AnsiValue(89),  // 16
+        Color::AnsiValue(125), // 17
+        Color::AnsiValue(161), // 18
+        Color::AnsiValue(162), // 19
+        Color::AnsiValue(126), // 20
+        Color::AnsiValue(90),  // 21
+        Color::AnsiValue(54),  // 22
+        Color::AnsiValue(55),  // 23
+        Color::AnsiValue(56),  // 24
     ],
     border: Color::Black,
     counter: Color::Blue,
+    status: Color::DarkGrey,
+    players: [
+        Color::Blue,
+        Color::DarkMagenta,
+        Color::DarkCyan,
+        Color::DarkYellow,
+    ],
 };
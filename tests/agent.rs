@@ -0,0 +1,82 @@
+use termsweeper::agent::{self, Agent, Builtin, Probability, View};
+use termsweeper::game_logic::Board;
+use termsweeper::solver::Analysis;
+use termsweeper::types::{Action, CellKind, GameConfig, Preset};
+
+#[test]
+fn solver_only_proves_what_is_true() {
+    for seed in 0..20 {
+        let mut board = Board::new_with_seed(GameConfig::new(16, 16, 40).unwrap(), seed);
+        board.apply_action(Action::Reveal { x: 8, y: 8 });
+        let view = View::of(&board);
+        let analysis = Analysis::of(&view);
+        assert!(analysis.is_exact());
+        let is_mine = |(x, y): (isize, isize)| {
+            matches!(board.get_cell(x, y).unwrap().kind, CellKind::Mine(_))
+        };
+        assert!(
+            analysis
+                .safe_cells(&view)
+                .into_iter()
+                .all(|cell| !is_mine(cell))
+        );
+        assert!(analysis.mines(&view).into_iter().all(is_mine));
+        // The chances add up to the mines still hidden
+        let expected: f64 = view
+            .hidden()
+            .into_iter()
+            .map(|(x, y)| analysis.probability(&view, x, y).unwrap())
+            .sum();
+        assert!(
+            (expected - 40.0).abs() < 1e-6,
+            "seed {}: {}",
+            seed,
+            expected
+        );
+    }
+}
+
+#[test]
+fn partial_search_proves_nothing() {
+    // A row of numbers across a wide board has too many arrangements to count
+    // them all, and the ones counted leave some cells always safe or always mined
+    let width = 50;
+    let mines: Vec<(usize, usize)> = (0..width)
+        .flat_map(|x| [(x, 0), (x, 2)])
+        .filter(|&(x, y)| (x + y) % 3 == 1)
+        .collect();
+    let mut board = Board::from_mines(width, 3, &mines).unwrap();
+    for x in 0..width as isize {
+        board.apply_action(Action::Reveal { x, y: 1 });
+    }
+    let view = View::of(&board);
+    let analysis = Analysis::of(&view);
+    assert!(!analysis.is_exact());
+    assert!(analysis.safe_cells(&view).is_empty());
+    assert!(analysis.mines(&view).is_empty());
+
+    let mut agent = Probability::new();
+    agent.next_action(&view);
+    let thinking = agent.thinking().unwrap();
+    assert!(thinking.safe.is_empty());
+    assert!(thinking.chance.is_some_and(|chance| chance > 0.0));
+}
+
+#[test]
+fn agents_replay_the_same_games_and_rank_as_expected() {
+    let config = Preset::Beginner.config();
+    let wins: Vec<usize> = Builtin::ALL
+        .into_iter()
+        .map(|builtin| {
+            let summary = agent::run_games(builtin, config, 0..40, 3);
+            assert_eq!(summary.games, 40);
+            assert_eq!(
+                agent::run_games(builtin, config, 0..40, 1).wins,
+                summary.wins
+            );
+            summary.wins
+        })
+        .collect();
+    assert!(wins[0] <= wins[1] && wins[1] <= wins[2], "{:?}", wins);
+    assert!(wins[2] > 0);
+}
@@ -0,0 +1,79 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use termsweeper::bench::Target;
+use termsweeper::game_logic::Board;
+use termsweeper::types::{Action, CellState, GameConfig, GameState};
+
+/// Asserts the board's counters agree with a scan of every cell.
+fn assert_counts(board: &Board) {
+    let cells: Vec<_> = (0..board.height as isize)
+        .flat_map(|y| (0..board.width as isize).map(move |x| (x, y)))
+        .map(|(x, y)| *board.get_cell(x, y).unwrap())
+        .collect();
+    let flags: usize = cells.iter().map(|cell| cell.state.flags()).sum();
+    let revealed = cells
+        .iter()
+        .filter(|cell| cell.state == CellState::Revealed && !cell.kind.is_mine())
+        .count();
+    assert_eq!(board.get_flags_count(), flags);
+    assert_eq!(board.get_revealed_count(), revealed);
+    assert_eq!(
+        board.is_finished(),
+        board.get_loss_reason().is_some()
+            || cells
+                .iter()
+                .all(|cell| cell.kind.is_mine() || cell.state == CellState::Revealed)
+    );
+}
+
+#[test]
+fn counters_match_the_grid() {
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut board = Board::new_with_seed(GameConfig::new(12, 10, 30).unwrap(), seed);
+        board.settings.lives = 3;
+        board.settings.mines_per_cell = 2;
+        board.settings.question_marks = true;
+        for _ in 0..2 {
+            let mut state = None;
+            while state.is_none() {
+                let (x, y) = (
+                    rng.random_range(0..12i64) as isize,
+                    rng.random_range(0..10i64) as isize,
+                );
+                state = board.apply_action(match rng.random_range(0..4) {
+                    0 => Action::Reveal { x, y },
+                    1 => Action::Flag { x, y },
+                    2 => Action::Chord { x, y },
+                    _ => Action::FlagChord { x, y },
+                });
+                assert_counts(&board);
+            }
+            if state == Some(GameState::Lost) {
+                board.reveal_all_mines();
+                assert_counts(&board);
+            }
+            board.reset();
+            assert_counts(&board);
+        }
+    }
+    let mut board = Board::from_layout("221\nF*.\nq.f\n").unwrap();
+    assert_eq!(board.get_flags_count(), 2);
+    assert_counts(&board);
+    board.apply_action(Action::Flag { x: 0, y: 1 });
+    board.reset();
+    assert_counts(&board);
+}
+
+#[test]
+fn bench_targets_run_on_their_boards() {
+    for target in Target::ALL {
+        let mut board = target.setup(16);
+        target.run(&mut board);
+        assert_counts(&board);
+    }
+    let mut board = Target::WinCheck.setup(16);
+    assert_eq!(board.check_win_condition(), Some(GameState::Won));
+    let board = Target::FlagCount.setup(16);
+    assert_eq!(board.get_flags_count(), board.get_config().mines());
+}
@@ -0,0 +1,62 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use termsweeper::game_logic::Board;
+use termsweeper::menu::{self, Menu};
+use termsweeper::shape::{Neighbourhood, Shape};
+use termsweeper::types::{DEFAULT_SETTINGS, FirstClick, GameConfig, Preset, Settings, StatusField};
+
+#[test]
+fn openings_need_room_for_every_neighbour() {
+    let opening = Settings {
+        first_click: FirstClick::Opening,
+        ..DEFAULT_SETTINGS
+    };
+    // 10x10 with 91 mines leaves exactly a square opening free
+    let config = GameConfig::new(10, 10, 91).unwrap();
+    assert!(config.check_first_click(&opening).is_ok());
+    let hex = Settings {
+        shape: Shape::Hex,
+        ..opening
+    };
+    assert!(config.check_first_click(&hex).is_ok());
+    let radius2 = Settings {
+        neighbourhood: Neighbourhood::Radius2,
+        ..opening
+    };
+    assert!(config.check_first_click(&radius2).is_err());
+    assert!(
+        GameConfig::new(10, 10, 75)
+            .unwrap()
+            .check_first_click(&radius2)
+            .is_ok()
+    );
+    assert_eq!(
+        FirstClick::Opening.protected_cells(Shape::Hex, Neighbourhood::Knight),
+        7
+    );
+}
+
+#[test]
+fn hex_boards_list_no_neighbourhood() {
+    let mut board = Board::new_with_seed(Preset::Beginner.config(), 1);
+    board.settings.neighbourhood = Neighbourhood::Knight;
+    assert_eq!(board.get_modifiers(), ["knight"]);
+    board.settings.shape = Shape::Hex;
+    assert_eq!(board.get_modifiers(), ["hex"]);
+}
+
+#[test]
+fn status_line_fields_can_be_hidden_from_the_menu() {
+    let mut menu = Menu::new_status_fields_menu(&DEFAULT_SETTINGS);
+    assert_eq!(menu.get_settings(DEFAULT_SETTINGS), DEFAULT_SETTINGS);
+    menu.hovered_index = StatusField::ALL
+        .iter()
+        .position(|field| *field == StatusField::Seed)
+        .unwrap();
+    let left = Event::Key(KeyEvent::new(KeyCode::Left, KeyModifiers::NONE));
+    menu::handle_menu_event(&left, &mut menu);
+    let status_bar = menu.get_settings(DEFAULT_SETTINGS).status_bar;
+    for field in StatusField::ALL {
+        assert_eq!(status_bar.shows(field), field != StatusField::Seed);
+    }
+    assert!(status_bar.enabled);
+}
@@ -0,0 +1,88 @@
+use crossterm::event::KeyCode;
+use termsweeper::coop::{Coop, Crew};
+use termsweeper::game_logic::Board;
+use termsweeper::race::{IJKL_KEYS, WASD_KEYS};
+use termsweeper::types::{Action, CellState, DEFAULT_SETTINGS, GameConfig, GameState};
+
+fn new_board(seed: u64) -> Board {
+    let mut board = Board::new_with_seed(GameConfig::new(9, 9, 10).unwrap(), seed);
+    board.settings = DEFAULT_SETTINGS;
+    board
+}
+
+/// Returns every cell's state, row by row.
+fn states(board: &Board) -> Vec<CellState> {
+    (0..board.height as isize)
+        .flat_map(|y| (0..board.width as isize).map(move |x| (x, y)))
+        .map(|(x, y)| board.get_cell(x, y).unwrap().state)
+        .collect()
+}
+
+#[test]
+fn flags_and_reveals_are_credited_and_reproduced() {
+    let mut board = new_board(3);
+    let mut crew = Crew::new();
+    crew.apply(&mut board, 0, Action::Reveal { x: 4, y: 4 });
+    let opened = crew.contribution(0).revealed;
+    assert_eq!(opened, board.get_revealed_count());
+
+    let mines = board.get_mine_positions();
+    let (first, second) = (mines[0], mines[1]);
+    let (x, y) = (first.0 as isize, first.1 as isize);
+    crew.apply(&mut board, 1, Action::Flag { x, y });
+    assert_eq!(crew.flag_owner(x, y), Some(1));
+    let (x2, y2) = (second.0 as isize, second.1 as isize);
+    crew.apply(&mut board, 0, Action::Flag { x: x2, y: y2 });
+    // Taking a flag down takes it from whoever placed it
+    crew.apply(&mut board, 0, Action::Flag { x, y });
+    assert_eq!(crew.flag_owner(x, y), None);
+    assert_eq!((crew.flags_of(0), crew.flags_of(1)), (1, 0));
+    assert_eq!(crew.contribution(1).flags_placed, 1);
+    // A reveal on a flagged cell does nothing, but still takes its turn
+    crew.apply(&mut board, 1, Action::Reveal { x: x2, y: y2 });
+    assert_eq!(crew.turns().len(), 5);
+    assert_eq!(board.get_revealed_count(), opened);
+
+    let mut copy = new_board(3);
+    let (replayed, state) = Crew::reproduce(&mut copy, crew.turns());
+    assert_eq!(state, GameState::Ongoing);
+    assert_eq!(states(&copy), states(&board));
+    for player in 0..2 {
+        assert_eq!(replayed.contribution(player), crew.contribution(player));
+        assert_eq!(replayed.flags_of(player), crew.flags_of(player));
+    }
+    let actions = |board: &Board| -> Vec<Action> {
+        let replay = board.get_replay().unwrap();
+        replay.events.iter().map(|event| event.action).collect()
+    };
+    assert_eq!(actions(&copy), actions(&board));
+}
+
+#[test]
+fn a_mine_hit_by_anyone_ends_the_game() {
+    let config = GameConfig::new(9, 9, 10).unwrap();
+    let mut coop = Coop::new(
+        config,
+        DEFAULT_SETTINGS,
+        5,
+        &[("ana", WASD_KEYS), ("bo", IJKL_KEYS)],
+    );
+    assert!(coop.handle_key(WASD_KEYS.reveal).is_some());
+    let (x, y) = coop
+        .board
+        .get_mine_positions()
+        .into_iter()
+        .map(|(x, y)| (x as isize, y as isize))
+        .find(|&(x, y)| coop.board.get_cell(x, y).unwrap().state == CellState::Hidden)
+        .unwrap();
+    coop.players[1].cursor = (x, y);
+    assert_eq!(
+        coop.handle_key(KeyCode::Char('o')),
+        Some((1, Action::Reveal { x, y }))
+    );
+    assert_eq!(coop.state, GameState::Lost);
+    assert_eq!(coop.culprit(), Some(1));
+    assert_eq!(coop.crew.contribution(1).mines_hit, 1);
+    // Nobody plays on after that
+    assert_eq!(coop.handle_key(WASD_KEYS.reveal), None);
+}
@@ -0,0 +1,26 @@
+use chrono::NaiveDate;
+use termsweeper::daily;
+use termsweeper::types::{Action, DEFAULT_SETTINGS};
+
+#[test]
+fn daily_board_does_not_depend_on_the_first_click() {
+    let date = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+    let board = daily::new_board(date, true, DEFAULT_SETTINGS);
+    assert!(board.get_revealed_count() > 0);
+    assert!(!board.timer.is_running());
+
+    let mut other = daily::new_board(date, false, DEFAULT_SETTINGS);
+    let hidden = (0..other.height as isize)
+        .flat_map(|y| (0..other.width as isize).map(move |x| (x, y)))
+        .find(|&(x, y)| !other.get_cell(x, y).unwrap().kind.is_mine())
+        .unwrap();
+    other.apply_action(Action::Reveal {
+        x: hidden.0,
+        y: hidden.1,
+    });
+    assert_eq!(other.get_mine_positions(), board.get_mine_positions());
+
+    other.reset();
+    assert_eq!(other.get_mine_positions(), board.get_mine_positions());
+    assert_eq!(other.get_revealed_count(), board.get_revealed_count());
+}
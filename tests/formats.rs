@@ -0,0 +1,126 @@
+use termsweeper::Error;
+use termsweeper::formats::{read_avf, read_mbf, read_rmv, write_mbf, write_rmv};
+use termsweeper::game_logic::Board;
+use termsweeper::replay::ReplayEvent;
+use termsweeper::types::{Action, CellKind, GameState};
+
+// The fixtures are small files written by hand to the published layouts of each
+// format, not recordings from Minesweeper Arbiter or Viennasweeper, so they check
+// the layouts as documented rather than compatibility with those programs.
+const BEGINNER_MBF: &[u8] = include_bytes!("fixtures/beginner.mbf");
+const GAME_AVF: &[u8] = include_bytes!("fixtures/game.avf");
+const GAME_RMV: &[u8] = include_bytes!("fixtures/game.rmv");
+
+#[test]
+fn mbf_loads_into_board() {
+    let board = read_mbf(BEGINNER_MBF).unwrap();
+    assert_eq!((board.width, board.height), (9, 9));
+    assert_eq!(board.get_remaining_mines(), 10);
+    assert_eq!(board.get_cell(0, 0).unwrap().kind, CellKind::Mine(1));
+    assert_eq!(board.get_cell(4, 4).unwrap().kind, CellKind::Mine(1));
+    // (4, 3) touches the mines at (3, 3) and (4, 4)
+    assert_eq!(board.get_cell(4, 3).unwrap().kind, CellKind::Number(2));
+}
+
+#[test]
+fn mbf_round_trips() {
+    let board = read_mbf(BEGINNER_MBF).unwrap();
+    let mut expected = BEGINNER_MBF[4..].chunks(2).collect::<Vec<_>>();
+    let written = write_mbf(&board);
+    let mut actual = written[4..].chunks(2).collect::<Vec<_>>();
+    expected.sort();
+    actual.sort();
+    assert_eq!(written[..4], BEGINNER_MBF[..4]);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn avf_reads_board_and_actions() {
+    let replay = read_avf(GAME_AVF).unwrap();
+    assert_eq!((replay.width, replay.height), (8, 8));
+    assert_eq!(replay.mines.len(), 10);
+    assert!(replay.mines.contains(&(7, 0)));
+    assert!(replay.mines.contains(&(3, 5)));
+    assert!(!replay.question_marks);
+    assert!(!replay.completed);
+    assert_eq!(replay.bbbv, Some(2));
+    assert_eq!(
+        replay.events,
+        vec![
+            ReplayEvent {
+                time_ms: 1100,
+                action: Action::Reveal { x: 0, y: 0 },
+            },
+            ReplayEvent {
+                time_ms: 2000,
+                action: Action::Flag { x: 7, y: 0 },
+            },
+        ]
+    );
+}
+
+#[test]
+fn rmv_reads_board_and_actions() {
+    let replay = read_rmv(GAME_RMV).unwrap();
+    assert_eq!((replay.width, replay.height), (5, 5));
+    assert_eq!(replay.mines, vec![(4, 4)]);
+    assert!(replay.completed);
+    assert_eq!(replay.bbbv, Some(1));
+    assert_eq!(
+        replay.events,
+        vec![
+            ReplayEvent {
+                time_ms: 500,
+                action: Action::Flag { x: 4, y: 4 },
+            },
+            ReplayEvent {
+                time_ms: 1000,
+                action: Action::Reveal { x: 0, y: 0 },
+            },
+        ]
+    );
+}
+
+#[test]
+fn rmv_replay_plays_to_a_win() {
+    let replay = read_rmv(GAME_RMV).unwrap();
+    let mut board = replay.to_board().unwrap();
+    let total = board.get_3bv();
+    assert_eq!(board.get_3bv_progress(), Some((0, total)));
+    let states: Vec<_> = replay
+        .events
+        .iter()
+        .filter_map(|event| board.apply_action(event.action))
+        .collect();
+    assert_eq!(states, vec![GameState::Won]);
+    assert_eq!(board.get_3bv_progress(), Some((total, total)));
+}
+
+#[test]
+fn recorded_game_round_trips_through_rmv() {
+    let mut board = Board::from_mines(5, 5, &[(4, 4), (0, 4)]).unwrap();
+    board.apply_action(Action::Flag { x: 4, y: 4 });
+    board.apply_action(Action::Reveal { x: 4, y: 0 });
+    board.apply_action(Action::Reveal { x: 0, y: 3 });
+    let replay = board.get_replay().unwrap();
+    assert_eq!(replay.events.len(), 3);
+
+    let read_back = read_rmv(&write_rmv(&replay).unwrap()).unwrap();
+    assert_eq!(read_back.bbbv, Some(board.get_3bv()));
+    assert_eq!(read_back, replay);
+}
+
+#[test]
+fn truncated_files_report_the_offset() {
+    match read_rmv(&GAME_RMV[..40]) {
+        Err(Error::Format { format, offset, .. }) => {
+            assert_eq!(format, "RMV");
+            assert!(offset <= 40);
+        }
+        other => panic!("expected a format error, got {:?}", other.map(|_| ())),
+    }
+    assert!(matches!(
+        read_avf(&GAME_AVF[..20]),
+        Err(Error::Format { format: "AVF", .. })
+    ));
+}
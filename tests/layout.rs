@@ -0,0 +1,33 @@
+use termsweeper::Error;
+use termsweeper::game_logic::Board;
+use termsweeper::types::{Action, CellState};
+
+fn parse_error_at(text: &str) -> (usize, usize) {
+    match Board::from_layout(text) {
+        Err(Error::Parse { line, column, .. }) => (line, column),
+        other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn mine_counts_are_checked() {
+    assert_eq!(parse_error_at("mines: 0,0=9\n*.\n"), (1, 8));
+    assert_eq!(parse_error_at("mines: 0,0=3\n*.\n"), (1, 8));
+    assert_eq!(parse_error_at("**\n**\n"), (2, 1));
+    assert!(Board::from_layout("mines: 0,0=3\n*...\n").is_ok());
+    assert!(Board::from_mines(4, 1, &[(0, 0); 4]).is_err());
+    assert!(Board::from_mines(4, 1, &[(0, 0); 3]).is_ok());
+}
+
+#[test]
+fn flag_chord_with_stacked_mines() {
+    // The 2 could be a pair in either hidden neighbour, or one mine in each
+    let mut board = Board::from_layout("mines: 1,0=2\n2*\n.2\n").unwrap();
+    board.apply_action(Action::FlagChord { x: 0, y: 0 });
+    assert_eq!(board.get_flags_count(), 0);
+
+    // A lone hidden neighbour holds all the missing mines
+    let mut board = Board::from_layout("mines: 1,0=2\n2*\n22\n").unwrap();
+    board.apply_action(Action::FlagChord { x: 0, y: 0 });
+    assert_eq!(board.get_cell(1, 0).unwrap().state, CellState::Flagged(2));
+}
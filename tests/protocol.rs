@@ -0,0 +1,131 @@
+use serde_json::Value;
+use termsweeper::protocol::{Session, VERSION};
+
+/// Sends one request line and parses the response.
+fn send(session: &mut Session, line: &str) -> Value {
+    let response = session.handle_line(line).expect("a response");
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["v"], VERSION);
+    response
+}
+
+/// Asserts the response is an error with `code`.
+fn assert_error(response: &Value, code: &str) {
+    assert_eq!(response["type"], "error", "{}", response);
+    assert_eq!(response["code"], code, "{}", response);
+}
+
+#[test]
+fn malformed_requests_are_rejected() {
+    let mut session = Session::new();
+    assert_eq!(session.handle_line("  "), None);
+    assert_error(&send(&mut session, "{not json"), "bad_json");
+    assert_error(&send(&mut session, "[1, 2]"), "bad_request");
+    assert_error(&send(&mut session, r#"{"id":7}"#), "bad_request");
+    assert_error(&send(&mut session, r#"{"cmd":3}"#), "bad_request");
+    assert_error(&send(&mut session, r#"{"cmd":"dig"}"#), "unknown_command");
+    assert_error(
+        &send(&mut session, r#"{"cmd":"state","v":99}"#),
+        "unsupported_version",
+    );
+    let response = send(&mut session, r#"{"cmd":"dig","id":"a"}"#);
+    assert_eq!(response["id"], "a");
+}
+
+#[test]
+fn new_checks_its_fields() {
+    let mut session = Session::new();
+    for request in [
+        r#"{"cmd":"new","width":9,"height":9}"#,
+        r#"{"cmd":"new","width":"9","height":9,"mines":10}"#,
+        r#"{"cmd":"new","width":-9,"height":9,"mines":10}"#,
+        r#"{"cmd":"new","preset":"beginner","seed":"x"}"#,
+    ] {
+        assert_error(&send(&mut session, request), "bad_request");
+    }
+    for request in [
+        r#"{"cmd":"new","width":1000,"height":9,"mines":10}"#,
+        r#"{"cmd":"new","width":9,"height":9,"mines":81}"#,
+        r#"{"cmd":"new","preset":"huge"}"#,
+    ] {
+        assert_error(&send(&mut session, request), "invalid_config");
+    }
+    assert!(session.board().is_none());
+}
+
+#[test]
+fn actions_need_an_ongoing_game_on_the_board() {
+    let mut session = Session::new();
+    assert_error(&send(&mut session, r#"{"cmd":"state"}"#), "no_game");
+    assert_error(
+        &send(&mut session, r#"{"cmd":"reveal","x":0,"y":0}"#),
+        "no_game",
+    );
+
+    let state = send(
+        &mut session,
+        r#"{"cmd":"new","preset":"beginner","seed":5}"#,
+    );
+    assert_eq!(state["type"], "state");
+    assert_eq!(state["outcome"], "playing");
+    assert_error(
+        &send(&mut session, r#"{"cmd":"flag","x":9,"y":0}"#),
+        "out_of_bounds",
+    );
+    assert_error(
+        &send(&mut session, r#"{"cmd":"chord","x":0,"y":-1}"#),
+        "out_of_bounds",
+    );
+    assert_error(
+        &send(&mut session, r#"{"cmd":"reveal","x":0}"#),
+        "bad_request",
+    );
+
+    send(&mut session, r#"{"cmd":"reveal","x":4,"y":4}"#);
+    let (x, y) = session.board().unwrap().get_mine_positions()[0];
+    let state = send(
+        &mut session,
+        &format!(r#"{{"cmd":"reveal","x":{},"y":{}}}"#, x, y),
+    );
+    assert_eq!(state["outcome"], "lost");
+    assert_error(
+        &send(&mut session, r#"{"cmd":"reveal","x":0,"y":0}"#),
+        "game_over",
+    );
+}
+
+#[test]
+fn hidden_mines_are_never_sent() {
+    let mut session = Session::new();
+    send(
+        &mut session,
+        r#"{"cmd":"new","preset":"intermediate","seed":3}"#,
+    );
+    let mut states = vec![send(&mut session, r#"{"cmd":"reveal","x":8,"y":8}"#)];
+    let mines = session.board().unwrap().get_mine_positions();
+    for &(x, y) in &mines[..3] {
+        let flag = format!(r#"{{"cmd":"flag","x":{},"y":{}}}"#, x, y);
+        states.push(send(&mut session, &flag));
+    }
+    let (x, y) = mines[3];
+    let reveal = format!(r#"{{"cmd":"reveal","x":{},"y":{}}}"#, x, y);
+    states.push(send(&mut session, &reveal));
+    assert_eq!(states.last().unwrap()["outcome"], "lost");
+
+    for state in states {
+        let rows: Vec<&str> = state["board"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|row| row.as_str().unwrap())
+            .collect();
+        assert!(
+            rows.iter()
+                .all(|row| row.chars().all(|ch| "#F?X012345678".contains(ch)))
+        );
+        // Only the mine that ended the game is shown
+        let shown: usize = rows.iter().map(|row| row.matches('X').count()).sum();
+        assert_eq!(shown, (state["outcome"] == "lost") as usize);
+        assert!(state.get("seed").is_none());
+    }
+}
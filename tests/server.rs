@@ -0,0 +1,199 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+use std::time::Duration;
+use termsweeper::race;
+use termsweeper::server::{MAX_LINE, Server};
+use termsweeper::types::{DEFAULT_SETTINGS, GameConfig};
+
+/// A client connected to an in-process server.
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    /// Connects and reads the `hello` line.
+    fn connect(addr: SocketAddr) -> Client {
+        let stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut client = Client {
+            reader: BufReader::new(stream.try_clone().unwrap()),
+            writer: stream,
+        };
+        assert_eq!(client.recv()["type"], "hello");
+        client
+    }
+
+    /// Sends a request and returns the response.
+    fn send(&mut self, line: &str) -> Value {
+        writeln!(self.writer, "{}", line).unwrap();
+        self.recv()
+    }
+
+    /// Reads the next line sent by the server.
+    fn recv(&mut self) -> Value {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+}
+
+fn start_server() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").unwrap();
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+    addr
+}
+
+#[test]
+fn lobby_lists_hosted_rooms() {
+    let addr = start_server();
+    let mut host = Client::connect(addr);
+    let mut guest = Client::connect(addr);
+    assert_eq!(
+        guest.send(r#"{"cmd":"list"}"#)["rooms"],
+        Value::Array(vec![])
+    );
+
+    let room = host.send(r#"{"cmd":"host","room":"friday","id":1}"#);
+    assert_eq!(
+        (&room["type"], &room["room"], &room["id"]),
+        (&"room".into(), &"friday".into(), &1.into())
+    );
+    host.send(r#"{"cmd":"new","preset":"beginner"}"#);
+
+    let lobby = guest.send(r#"{"cmd":"list"}"#);
+    assert_eq!(lobby["type"], "lobby");
+    let rooms = lobby["rooms"].as_array().unwrap();
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0]["room"], "friday");
+    assert_eq!(rooms[0]["players"], 1);
+    assert_eq!(rooms[0]["width"], 9);
+    assert_eq!(rooms[0]["outcome"], "playing");
+}
+
+#[test]
+fn players_in_a_room_share_the_board() {
+    let addr = start_server();
+    let mut host = Client::connect(addr);
+    let mut guest = Client::connect(addr);
+    host.send(r#"{"cmd":"host","room":"coop"}"#);
+    assert_eq!(guest.send(r#"{"cmd":"join","room":"coop"}"#)["players"], 2);
+
+    let state = host.send(r#"{"cmd":"new","preset":"beginner","seed":7,"id":"n"}"#);
+    assert_eq!(state["id"], "n");
+    let update = guest.recv();
+    assert_eq!(update["type"], "state");
+    assert!(update.get("id").is_none());
+
+    let state = guest.send(r#"{"cmd":"reveal","x":4,"y":4}"#);
+    let update = host.recv();
+    assert_eq!(update["board"], state["board"]);
+    assert_eq!(update["revealed"], state["revealed"]);
+    assert_ne!(update["revealed"], 0);
+    // The guest is credited with everything revealed so far
+    assert_eq!(update["players"][1]["revealed"], state["revealed"]);
+    assert_eq!(update["players"][0]["revealed"], 0);
+}
+
+#[test]
+fn rooms_have_their_own_games() {
+    let addr = start_server();
+    let mut first = Client::connect(addr);
+    let mut second = Client::connect(addr);
+    first.send(r#"{"cmd":"host","room":"a"}"#);
+    second.send(r#"{"cmd":"host","room":"b"}"#);
+    first.send(r#"{"cmd":"new","preset":"beginner"}"#);
+    let state = second.send(r#"{"cmd":"new","preset":"expert"}"#);
+    assert_eq!(state["width"], 30);
+    assert_eq!(first.send(r#"{"cmd":"state"}"#)["width"], 9);
+}
+
+#[test]
+fn lobby_errors() {
+    let addr = start_server();
+    let mut first = Client::connect(addr);
+    let mut second = Client::connect(addr);
+    let error = first.send(r#"{"cmd":"reveal","x":0,"y":0,"id":5}"#);
+    assert_eq!(
+        (&error["code"], &error["id"]),
+        (&"not_in_room".into(), &5.into())
+    );
+    assert_eq!(
+        first.send(r#"{"cmd":"join","room":"x"}"#)["code"],
+        "no_room"
+    );
+    assert_eq!(first.send(r#"{"cmd":"leave"}"#)["code"], "not_in_room");
+    assert_eq!(first.send(r#"{"cmd":"host"}"#)["code"], "bad_request");
+    assert_eq!(first.send("not json")["code"], "bad_json");
+
+    first.send(r#"{"cmd":"host","room":"x"}"#);
+    assert_eq!(
+        second.send(r#"{"cmd":"host","room":"x"}"#)["code"],
+        "room_exists"
+    );
+    assert_eq!(first.send(r#"{"cmd":"leave"}"#)["room"], Value::Null);
+    // The room closed with its last player, so the name is free again
+    assert_eq!(second.send(r#"{"cmd":"host","room":"x"}"#)["room"], "x");
+}
+
+#[test]
+fn race_rooms_deal_the_same_board_and_declare_a_winner() {
+    let addr = start_server();
+    let mut ana = Client::connect(addr);
+    let mut bo = Client::connect(addr);
+    let room = ana.send(r#"{"cmd":"host","room":"duel","race":true,"name":"ana"}"#);
+    assert_eq!(room["race"], true);
+    assert_eq!(
+        bo.send(r#"{"cmd":"join","room":"duel","name":"bo"}"#)["name"],
+        "bo"
+    );
+
+    // Everyone is sent the deal, then their board
+    let deal = bo.send(r#"{"cmd":"new","preset":"beginner","seed":11,"id":1}"#);
+    assert_eq!(deal["opponents"], serde_json::json!(["ana"]));
+    let state = bo.recv();
+    assert_eq!(state["id"], 1);
+    let deal = ana.recv();
+    assert_eq!((&deal["type"], &deal["seed"]), (&"race".into(), &11.into()));
+    assert_eq!(deal["opponents"], serde_json::json!(["bo"]));
+    assert_eq!(ana.recv()["board"], state["board"]);
+
+    // The client can deal the same board itself from the seed
+    let config = GameConfig::new(9, 9, 10).unwrap();
+    let (board, _) = race::new_board(config, DEFAULT_SETTINGS, 11);
+    let (x, y) = board.get_mine_positions()[0];
+    ana.send(&format!(r#"{{"cmd":"reveal","x":{},"y":{}}}"#, x, y));
+    let progress = bo.recv();
+    assert_eq!(
+        (&progress["player"], &progress["alive"]),
+        (&"ana".into(), &false.into())
+    );
+    let result = bo.recv();
+    assert_eq!(
+        (&result["winner"], &result["won"]),
+        (&"bo".into(), &true.into())
+    );
+    assert_eq!(ana.recv()["won"], false);
+}
+
+#[test]
+fn overlong_lines_disconnect() {
+    let addr = start_server();
+    let mut client = Client::connect(addr);
+    let line = format!(r#"{{"cmd":"list","pad":"{}"}}"#, "x".repeat(MAX_LINE));
+    // The server may hang up before the whole line is written
+    let _ = writeln!(client.writer, "{}", line);
+    let mut rest = String::new();
+    assert_eq!(client.reader.read_line(&mut rest).unwrap_or(0), 0);
+
+    // Lines up to the limit are read as usual
+    let mut client = Client::connect(addr);
+    let pad = "x".repeat(MAX_LINE - 100);
+    let list = client.send(&format!(r#"{{"cmd":"list","pad":"{}"}}"#, pad));
+    assert_eq!(list["rooms"], Value::Array(vec![]));
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+use termsweeper::game_logic::Board;
+use termsweeper::shape::Shape;
+use termsweeper::spectate::{self, Broadcast, Frame};
+use termsweeper::types::{Action, DEFAULT_SETTINGS, GameConfig, GameState};
+
+fn next(frames: &std::sync::mpsc::Receiver<Option<Frame>>) -> Option<Frame> {
+    frames.recv_timeout(Duration::from_secs(5)).unwrap()
+}
+
+#[test]
+fn spectators_see_the_board_but_not_the_mines() {
+    let broadcast = Broadcast::bind("127.0.0.1:0").unwrap();
+    let frames = spectate::watch(broadcast.local_addr()).unwrap();
+    // Nothing is on screen yet
+    assert_eq!(next(&frames), None);
+
+    let mut board = Board::new_with_seed(GameConfig::new(9, 9, 10).unwrap(), 1);
+    board.apply_action(Action::Reveal { x: 4, y: 4 });
+    board.cursor = Some((2, 3));
+    broadcast.show(&board, GameState::Ongoing);
+    let frame = next(&frames).unwrap();
+    let expected = Frame::of(&board, GameState::Ongoing).unwrap();
+    assert_eq!(frame.rows, expected.rows);
+    assert_eq!(frame.cursor, Some((2, 3)));
+    assert!(frame.rows.iter().all(|row| !row.contains('*')));
+
+    // Coaching shows every hidden mine
+    board.settings.reveal_for_spectators = true;
+    broadcast.show(&board, GameState::Ongoing);
+    let frame = next(&frames).unwrap();
+    let shown = frame
+        .rows
+        .iter()
+        .map(|row| row.matches('*').count())
+        .sum::<usize>();
+    assert_eq!(shown, 10);
+
+    broadcast.show(&board, GameState::Menu);
+    assert_eq!(next(&frames), None);
+}
+
+#[test]
+fn frames_follow_the_screen() {
+    // An endless board shows the area around the start, not the cells from (0, 0)
+    let mut board = Board::new_endless(4, DEFAULT_SETTINGS);
+    let (left, top) = board.viewport;
+    board.cursor = Some((0, 0));
+    let frame = Frame::of(&board, GameState::Ongoing).unwrap();
+    assert_eq!(frame.origin, (left, top));
+    assert_eq!(frame.cursor, Some((-left, -top)));
+    let at_cursor = frame.rows[-top as usize].chars().nth(-left as usize);
+    assert_ne!(at_cursor, Some('#'));
+    assert_eq!(Frame::from_line(&frame.to_line()), Some(frame));
+
+    // Numbers above 9 are sent whole, along with the shape of the cells
+    let mut board = Board::from_layout(
+        "neighbourhood: radius2\nmines: 0,0=3\n*****.\n*****.\n**_**.\n*****.\n*****.\n",
+    )
+    .unwrap();
+    board.settings.shape = Shape::Hex;
+    let frame = Frame::of(&board, GameState::Ongoing).unwrap();
+    assert_eq!(frame.rows[2], "##+###");
+    assert_eq!(frame.numbers, vec![((2, 2), 26)]);
+    assert_eq!(frame.shape, Shape::Hex);
+    assert_eq!(Frame::from_line(&frame.to_line()), Some(frame));
+}
@@ -0,0 +1,79 @@
+use chrono::NaiveDate;
+use std::fs;
+use termsweeper::game_logic::Board;
+use termsweeper::shape::{Neighbourhood, Shape};
+use termsweeper::stats::{
+    self, CLASSIC_LOG, HEX_LOG, KNIGHT_LOG, LIAR_LOG, LIVES_LOG, Outcome, ResultEntry, ResultLog,
+    Rules, TORUS_LOG, TWO_PER_CELL_LOG,
+};
+use termsweeper::topology::Topology;
+use termsweeper::types::{DEFAULT_SETTINGS, Noise, Preset, Settings};
+
+#[test]
+fn variants_get_their_own_log() {
+    let mut board = Board::new_with_seed(Preset::Beginner.config(), 1);
+    assert_eq!(stats::log_for(&board), Some(CLASSIC_LOG));
+    board.settings.topology = Topology::Torus;
+    assert_eq!(stats::log_for(&board), Some(TORUS_LOG));
+    board.settings.neighbourhood = Neighbourhood::Knight;
+    assert_eq!(stats::log_for(&board), Some(KNIGHT_LOG));
+    board.settings.shape = Shape::Hex;
+    assert_eq!(stats::log_for(&board), Some(HEX_LOG));
+    board.settings.mines_per_cell = 2;
+    assert_eq!(stats::log_for(&board), Some(TWO_PER_CELL_LOG));
+    board.settings.noise = Noise::Noisy(10);
+    assert_eq!(stats::log_for(&board), Some(LIAR_LOG));
+}
+
+#[test]
+fn results_with_different_rules_are_summarised_apart() {
+    let path = std::env::temp_dir().join(format!("termsweeper-stats-{}.txt", std::process::id()));
+    fs::write(&path, "2026-01-01 won 5000 9x9/10\n").unwrap();
+    let mut log = ResultLog::load(path.clone()).unwrap();
+    // Lines from before rules were recorded are classic games
+    assert_eq!(log.entries[0].rules, Rules::default());
+
+    let mut board = Board::new_with_seed(Preset::Beginner.config(), 1);
+    for (lives, topology, time_ms) in [
+        (2, Topology::Bounded, 4000),
+        (5, Topology::Bounded, 3000),
+        (2, Topology::Torus, 2000),
+        (2, Topology::Bounded, 6000),
+    ] {
+        board.settings = Settings {
+            lives,
+            topology,
+            time_limit: Some(60),
+            ..DEFAULT_SETTINGS
+        };
+        if topology == Topology::Bounded {
+            assert_eq!(stats::log_for(&board), Some(LIVES_LOG));
+        }
+        log.push(ResultEntry {
+            date: NaiveDate::from_ymd_opt(2026, 1, 2).unwrap(),
+            outcome: Outcome::Won,
+            time_ms,
+            config: board.get_config(),
+            rules: Rules::of(&board.settings),
+        })
+        .unwrap();
+    }
+
+    let log = ResultLog::load(path.clone()).unwrap();
+    fs::remove_file(&path).unwrap();
+    let summaries: Vec<(Vec<String>, usize, Option<u32>)> = log
+        .summaries()
+        .into_iter()
+        .map(|(_, rules, summary)| (rules.tags(), summary.played, summary.best_ms))
+        .collect();
+    let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        summaries,
+        [
+            (tags(&[]), 1, Some(5000)),
+            (tags(&["lives=2", "time=60"]), 2, Some(4000)),
+            (tags(&["lives=5", "time=60"]), 1, Some(3000)),
+            (tags(&["torus", "lives=2", "time=60"]), 1, Some(2000)),
+        ]
+    );
+}